@@ -0,0 +1,126 @@
+//! A [`tower_service::Service`] connector handing out mock streams instead of opening sockets.
+//!
+//! Suitable for `tonic::transport::Endpoint::connect_with_connector` (or any hyper based client),
+//! so clients can be pointed at an in-memory scripted or echo server.
+#![warn(missing_docs)]
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::future::{ready, Ready};
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::task::{self, Poll};
+
+use hyper_util::rt::TokioIo;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tower_service::Service;
+
+use crate::stream::{CheckedMockStream, CheckedMockStreamBuilder};
+
+type StreamFactory<S> = Arc<dyn Fn() -> S + Send + Sync>;
+
+enum Source<S> {
+    Factory(StreamFactory<S>),
+    Queue(Arc<Mutex<VecDeque<S>>>),
+}
+
+impl<S> Clone for Source<S> {
+    fn clone(&self) -> Self {
+        match self {
+            Source::Factory(f) => Source::Factory(f.clone()),
+            Source::Queue(q) => Source::Queue(q.clone()),
+        }
+    }
+}
+
+/// A connector returning a new mock stream (wrapped into [`TokioIo`]) on every connect.
+///
+/// Request (usually `http::Uri`) is ignored.
+pub struct MockConnector<S> {
+    source: Source<S>,
+}
+
+impl MockConnector<CheckedMockStream> {
+    /// Creates a connector, which build a new [`CheckedMockStream`] from `builder` on every connect.
+    pub fn new(builder: CheckedMockStreamBuilder) -> Self {
+        MockConnector::from_fn(move || builder.clone().build())
+    }
+}
+
+impl<S> MockConnector<S> {
+    /// Creates a connector, which call `f` on every connect (for example for return
+    /// one side of `tokio::io::duplex` with in-memory server on other side).
+    pub fn from_fn<F>(f: F) -> Self
+    where
+        F: Fn() -> S + Send + Sync + 'static,
+    {
+        MockConnector {
+            source: Source::Factory(Arc::new(f)),
+        }
+    }
+
+    /// Creates a connector, which return streams in order (one per connect).
+    ///
+    /// Connect fails with [`io::ErrorKind::ConnectionRefused`] when streams are exhausted.
+    pub fn from_streams<I>(streams: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+    {
+        MockConnector {
+            source: Source::Queue(Arc::new(Mutex::new(streams.into_iter().collect()))),
+        }
+    }
+
+    /// Gets a count of streams remaining to connect (`None` for unlimited).
+    pub fn remaining(&self) -> Option<usize> {
+        match &self.source {
+            Source::Factory(_) => None,
+            Source::Queue(q) => Some(q.lock().unwrap().len()),
+        }
+    }
+
+    fn connect(&self) -> io::Result<S> {
+        match &self.source {
+            Source::Factory(f) => Ok(f()),
+            Source::Queue(q) => q.lock().unwrap().pop_front().ok_or_else(|| {
+                io::Error::new(io::ErrorKind::ConnectionRefused, "no more mock streams")
+            }),
+        }
+    }
+}
+
+impl<S> Clone for MockConnector<S> {
+    fn clone(&self) -> Self {
+        MockConnector {
+            source: self.source.clone(),
+        }
+    }
+}
+
+impl<S> fmt::Debug for MockConnector<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockConnector")
+            .field("remaining", &self.remaining())
+            .finish()
+    }
+}
+
+impl<S, R> Service<R> for MockConnector<S>
+where
+    S: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+{
+    type Response = TokioIo<S>;
+    type Error = io::Error;
+    type Future = Ready<io::Result<TokioIo<S>>>;
+
+    fn poll_ready(&mut self, _: &mut task::Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _: R) -> Self::Future {
+        ready(self.connect().map(TokioIo::new))
+    }
+}
+
+#[cfg(test)]
+mod tests_tokio;
@@ -0,0 +1,71 @@
+use super::MockConnector;
+
+use crate::stream::CheckedMockStreamBuilder;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tower_service::Service;
+
+#[tokio::test]
+async fn mock_connector_builder() {
+    let mut connector = MockConnector::new(
+        CheckedMockStreamBuilder::new()
+            .write(b"PING\n".to_vec())
+            .read(b"PONG\n".to_vec()),
+    );
+    assert_eq!(connector.remaining(), None);
+
+    for _ in 0..2 {
+        let mut stream = connector.call("mock://localhost").await.unwrap().into_inner();
+        stream.write_all(b"PING\n").await.unwrap();
+        let mut buf = Vec::new();
+        stream.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"PONG\n");
+        assert_eq!(stream.written(), b"PING\n");
+    }
+}
+
+#[tokio::test]
+async fn mock_connector_streams() {
+    let mut connector = MockConnector::from_streams(vec![
+        CheckedMockStreamBuilder::new().read(b"First\n".to_vec()).build(),
+        CheckedMockStreamBuilder::new().read(b"Second\n".to_vec()).build(),
+    ]);
+    let mut cloned = connector.clone();
+    assert_eq!(connector.remaining(), Some(2));
+
+    let mut buf = Vec::new();
+    let mut stream = connector.call(()).await.unwrap().into_inner();
+    stream.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"First\n");
+
+    buf.clear();
+    let mut stream = cloned.call(()).await.unwrap().into_inner();
+    stream.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"Second\n");
+
+    let err = connector.call(()).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::ConnectionRefused);
+}
+
+#[tokio::test]
+async fn mock_connector_duplex() {
+    let mut connector = MockConnector::from_fn(|| {
+        let (client, mut server) = tokio::io::duplex(64);
+        tokio::spawn(async move {
+            let mut buf = [0; 64];
+            loop {
+                match server.read(&mut buf).await {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => server.write_all(&buf[..n]).await.unwrap(),
+                }
+            }
+        });
+        client
+    });
+
+    let mut stream = connector.call(()).await.unwrap().into_inner();
+    stream.write_all(b"echo").await.unwrap();
+    let mut buf = [0; 4];
+    stream.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"echo");
+}
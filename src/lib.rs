@@ -1 +1,16 @@
+pub mod prelude;
+pub mod proto;
 pub mod stream;
+
+pub use stream::MismatchError;
+
+#[cfg(feature = "tower")]
+pub mod connector;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::stream::assert::{assert_written_contains, assert_written_eq};
+    pub use crate::stream::dsl::duration;
+    #[cfg(feature = "insta")]
+    pub use insta;
+}
@@ -0,0 +1,54 @@
+//! Common imports for tests.
+//!
+//! Sync tests import `netmock::prelude::*` (with [`std::io::Read`] and [`std::io::Write`]),
+//! async tests import `netmock::prelude::tokio::*` (with tokio `AsyncReadExt` and `AsyncWriteExt`
+//! instead, as both traits pairs in scope make read/write methods of mock streams ambiguous).
+//!
+//! ```
+//! use netmock::prelude::*;
+//!
+//! let mut stream = CheckedMockStreamBuilder::new().write(b"PING\r\n").build();
+//! stream.write_all(b"PING\r\n").unwrap();
+//! assert_written!(stream, "PING\r\n");
+//! stream.assert_done();
+//! ```
+#![warn(missing_docs)]
+
+pub use std::io::{Read, Write};
+
+pub use crate::stream::{
+    Chaos, CheckedMockStream, CheckedMockStreamBuilder, CurrentAction, Divergence, ExhaustedWrite,
+    Fault, FaultStream, MismatchError, MismatchPolicy, Payload, ReadMode, RecordingStream,
+    SharedMockStream, SimpleMockStream, WrittenMode,
+};
+pub use crate::{assert_written, assert_written_contains, mock_stream};
+
+#[cfg(feature = "insta")]
+pub use crate::assert_written_snapshot;
+
+/// Common imports for async (tokio) tests.
+///
+/// ```
+/// use netmock::prelude::tokio::*;
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let mut stream = CheckedMockStreamBuilder::new().write(b"PING\r\n").build();
+/// stream.write_all(b"PING\r\n").await.unwrap();
+/// assert_written!(stream, "PING\r\n");
+/// # }
+/// ```
+#[cfg(feature = "tokio")]
+pub mod tokio {
+    pub use ::tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    pub use crate::stream::{
+        Chaos, CheckedMockStream, CheckedMockStreamBuilder, CurrentAction, Divergence,
+        ExhaustedWrite, Fault, FaultStream, MismatchError, MismatchPolicy, Payload, ReadMode,
+        RecordingStream, SharedMockStream, SimpleMockStream, WrittenMode,
+    };
+    pub use crate::{assert_written, assert_written_contains, mock_stream};
+
+    #[cfg(feature = "insta")]
+    pub use crate::assert_written_snapshot;
+}
@@ -0,0 +1,431 @@
+//! DNS wire-format helpers (RFC 1035) for build query expectations and response packets.
+//!
+//! Messages are built as UDP payloads, use [`tcp_frame`] for DNS over TCP (length-prefixed) streams.
+//! There is no datagram mock in the crate: UDP payloads are plain bytes, which can be sent or
+//! compared by the test itself.
+//!
+//! Encoding panics on values, which can't be represented in wire format (labels longer than 63
+//! bytes, TXT strings longer than 255 bytes, records or messages longer than 65535 bytes), instead
+//! of building another message.
+
+use std::convert::TryFrom;
+use std::io::{self, Error, ErrorKind};
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+/// DNS class IN.
+pub const CLASS_IN: u16 = 1;
+
+/// A DNS record type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordType {
+    /// IPv4 address.
+    A,
+    /// IPv6 address.
+    Aaaa,
+    /// Service locator.
+    Srv,
+    /// Text strings.
+    Txt,
+    /// Any other type (by code).
+    Other(u16),
+}
+
+impl RecordType {
+    /// Gets a wire code of record type.
+    pub fn code(self) -> u16 {
+        match self {
+            RecordType::A => 1,
+            RecordType::Txt => 16,
+            RecordType::Aaaa => 28,
+            RecordType::Srv => 33,
+            RecordType::Other(code) => code,
+        }
+    }
+
+    /// Gets a record type from wire code.
+    pub fn from_code(code: u16) -> Self {
+        match code {
+            1 => RecordType::A,
+            16 => RecordType::Txt,
+            28 => RecordType::Aaaa,
+            33 => RecordType::Srv,
+            code => RecordType::Other(code),
+        }
+    }
+}
+
+/// A DNS response code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rcode {
+    /// No error.
+    NoError,
+    /// Format error.
+    FormErr,
+    /// Server failure.
+    ServFail,
+    /// Non-existent domain.
+    NxDomain,
+    /// Not implemented.
+    NotImp,
+    /// Query refused.
+    Refused,
+}
+
+impl Rcode {
+    /// Gets a wire code of response code.
+    pub fn code(self) -> u8 {
+        match self {
+            Rcode::NoError => 0,
+            Rcode::FormErr => 1,
+            Rcode::ServFail => 2,
+            Rcode::NxDomain => 3,
+            Rcode::NotImp => 4,
+            Rcode::Refused => 5,
+        }
+    }
+}
+
+/// A resource record data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RData {
+    /// IPv4 address.
+    A(Ipv4Addr),
+    /// IPv6 address.
+    Aaaa(Ipv6Addr),
+    /// Service locator.
+    Srv {
+        /// Priority.
+        priority: u16,
+        /// Weight.
+        weight: u16,
+        /// Port.
+        port: u16,
+        /// Target host name.
+        target: String,
+    },
+    /// Text strings (each up to 255 bytes).
+    Txt(Vec<Vec<u8>>),
+}
+
+impl RData {
+    /// Gets a record type of data.
+    pub fn record_type(&self) -> RecordType {
+        match self {
+            RData::A(_) => RecordType::A,
+            RData::Aaaa(_) => RecordType::Aaaa,
+            RData::Srv { .. } => RecordType::Srv,
+            RData::Txt(_) => RecordType::Txt,
+        }
+    }
+
+    fn encode(&self, buf: &mut Vec<u8>) {
+        match self {
+            RData::A(addr) => buf.extend_from_slice(&addr.octets()),
+            RData::Aaaa(addr) => buf.extend_from_slice(&addr.octets()),
+            RData::Srv {
+                priority,
+                weight,
+                port,
+                target,
+            } => {
+                buf.extend_from_slice(&priority.to_be_bytes());
+                buf.extend_from_slice(&weight.to_be_bytes());
+                buf.extend_from_slice(&port.to_be_bytes());
+                encode_name(buf, target);
+            }
+            RData::Txt(strings) => {
+                for s in strings {
+                    assert!(
+                        s.len() <= 255,
+                        "dns TXT string longer than 255 bytes: {} bytes",
+                        s.len()
+                    );
+                    buf.push(s.len() as u8);
+                    buf.extend_from_slice(s);
+                }
+            }
+        }
+    }
+}
+
+/// A resource record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    /// Owner name.
+    pub name: String,
+    /// Time to live (in seconds).
+    pub ttl: u32,
+    /// Record data.
+    pub data: RData,
+}
+
+impl Record {
+    /// Creates a new record.
+    pub fn new(name: &str, ttl: u32, data: RData) -> Self {
+        Record {
+            name: name.to_string(),
+            ttl,
+            data,
+        }
+    }
+
+    fn encode(&self, buf: &mut Vec<u8>) {
+        encode_name(buf, &self.name);
+        buf.extend_from_slice(&self.data.record_type().code().to_be_bytes());
+        buf.extend_from_slice(&CLASS_IN.to_be_bytes());
+        buf.extend_from_slice(&self.ttl.to_be_bytes());
+        let len_pos = buf.len();
+        buf.extend_from_slice(&[0, 0]);
+        self.data.encode(buf);
+        let len = wire_len(buf.len() - len_pos - 2, "record data");
+        buf[len_pos..len_pos + 2].copy_from_slice(&len.to_be_bytes());
+    }
+}
+
+/// A parsed DNS question (as sent by resolver).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query {
+    /// Message ID.
+    pub id: u16,
+    /// Recursion desired flag.
+    pub recursion_desired: bool,
+    /// Queried name (without trailing dot).
+    pub name: String,
+    /// Queried record type.
+    pub qtype: RecordType,
+    /// Queried class.
+    pub qclass: u16,
+}
+
+impl Query {
+    /// Creates a new query (IN class, recursion desired).
+    pub fn new(id: u16, name: &str, qtype: RecordType) -> Self {
+        Query {
+            id,
+            recursion_desired: true,
+            name: name.trim_end_matches('.').to_string(),
+            qtype,
+            qclass: CLASS_IN,
+        }
+    }
+
+    /// Encodes query to wire format.
+    ///
+    /// # Panics
+    ///
+    /// If a label of name is longer than 63 bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.name.len() + 18);
+        buf.extend_from_slice(&self.id.to_be_bytes());
+        buf.push(if self.recursion_desired { 0x01 } else { 0x00 });
+        buf.push(0);
+        buf.extend_from_slice(&[0, 1, 0, 0, 0, 0, 0, 0]);
+        encode_name(&mut buf, &self.name);
+        buf.extend_from_slice(&self.qtype.code().to_be_bytes());
+        buf.extend_from_slice(&self.qclass.to_be_bytes());
+        buf
+    }
+
+    /// Parses (first question of) DNS query message.
+    pub fn parse(msg: &[u8]) -> io::Result<Query> {
+        if msg.len() < 12 {
+            return Err(invalid("dns message too short"));
+        }
+        if msg[2] & 0x80 != 0 {
+            return Err(invalid("dns message is not a query"));
+        }
+        let qdcount = u16::from_be_bytes([msg[4], msg[5]]);
+        if qdcount == 0 {
+            return Err(invalid("dns query without question"));
+        }
+        let (name, pos) = decode_name(msg, 12)?;
+        if msg.len() < pos + 4 {
+            return Err(invalid("dns question truncated"));
+        }
+        Ok(Query {
+            id: u16::from_be_bytes([msg[0], msg[1]]),
+            recursion_desired: msg[2] & 0x01 != 0,
+            name,
+            qtype: RecordType::from_code(u16::from_be_bytes([msg[pos], msg[pos + 1]])),
+            qclass: u16::from_be_bytes([msg[pos + 2], msg[pos + 3]]),
+        })
+    }
+
+    /// Creates a response builder for this query (with same ID and question).
+    pub fn response(&self) -> Response {
+        Response {
+            query: self.clone(),
+            rcode: Rcode::NoError,
+            authoritative: false,
+            truncated: false,
+            answers: Vec::new(),
+        }
+    }
+}
+
+/// A builder for DNS response message.
+#[derive(Debug, Clone)]
+pub struct Response {
+    query: Query,
+    rcode: Rcode,
+    authoritative: bool,
+    truncated: bool,
+    answers: Vec<Record>,
+}
+
+impl Response {
+    /// Creates a new response builder for query.
+    pub fn new(id: u16, name: &str, qtype: RecordType) -> Self {
+        Query::new(id, name, qtype).response()
+    }
+
+    /// Creates a new NXDOMAIN response.
+    pub fn nxdomain(id: u16, name: &str, qtype: RecordType) -> Self {
+        Response::new(id, name, qtype).rcode(Rcode::NxDomain)
+    }
+
+    /// Set response code.
+    pub fn rcode(mut self, rcode: Rcode) -> Self {
+        self.rcode = rcode;
+        self
+    }
+
+    /// Set authoritative answer flag.
+    pub fn authoritative(mut self, authoritative: bool) -> Self {
+        self.authoritative = authoritative;
+        self
+    }
+
+    /// Set truncation (TC) flag.
+    pub fn truncated(mut self, truncated: bool) -> Self {
+        self.truncated = truncated;
+        self
+    }
+
+    /// Add answer record.
+    pub fn answer(mut self, record: Record) -> Self {
+        self.answers.push(record);
+        self
+    }
+
+    /// Add answer record for queried name.
+    pub fn answer_data(self, ttl: u32, data: RData) -> Self {
+        let record = Record::new(&self.query.name, ttl, data);
+        self.answer(record)
+    }
+
+    /// Encodes response to wire format.
+    ///
+    /// # Panics
+    ///
+    /// If a value can't be represented in wire format (see [module docs](self)).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = self.query.to_bytes();
+        let mut flags = 0x80; // QR
+        if self.authoritative {
+            flags |= 0x04;
+        }
+        if self.truncated {
+            flags |= 0x02;
+        }
+        if self.query.recursion_desired {
+            flags |= 0x01;
+        }
+        buf[2] = flags;
+        buf[3] = 0x80 | self.rcode.code(); // RA
+        buf[6..8].copy_from_slice(&wire_len(self.answers.len(), "answer count").to_be_bytes());
+        for answer in &self.answers {
+            answer.encode(&mut buf);
+        }
+        buf
+    }
+}
+
+/// Frame DNS message for TCP transport (2-byte length prefix).
+///
+/// # Panics
+///
+/// If message is longer than 65535 bytes.
+pub fn tcp_frame(msg: &[u8]) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(msg.len() + 2);
+    buf.extend_from_slice(&wire_len(msg.len(), "message").to_be_bytes());
+    buf.extend_from_slice(msg);
+    buf
+}
+
+/// Split TCP stream data (for example written) to DNS messages.
+pub fn split_tcp_frames(mut data: &[u8]) -> io::Result<Vec<&[u8]>> {
+    let mut msgs = Vec::new();
+    while !data.is_empty() {
+        if data.len() < 2 {
+            return Err(invalid("dns tcp frame length truncated"));
+        }
+        let len = u16::from_be_bytes([data[0], data[1]]) as usize;
+        if data.len() < len + 2 {
+            return Err(invalid("dns tcp frame truncated"));
+        }
+        msgs.push(&data[2..len + 2]);
+        data = &data[len + 2..];
+    }
+    Ok(msgs)
+}
+
+fn invalid(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
+
+// 16-bit length (or count) of wire format
+fn wire_len(len: usize, what: &str) -> u16 {
+    u16::try_from(len).unwrap_or_else(|_| panic!("dns {} longer than 65535: {}", what, len))
+}
+
+fn encode_name(buf: &mut Vec<u8>, name: &str) {
+    for label in name.trim_end_matches('.').split('.') {
+        if label.is_empty() {
+            continue;
+        }
+        assert!(
+            label.len() <= 63,
+            "dns label longer than 63 bytes: {:?}",
+            label
+        );
+        buf.push(label.len() as u8);
+        buf.extend_from_slice(label.as_bytes());
+    }
+    buf.push(0);
+}
+
+// decode name (with compression pointers), returns name and position after it
+fn decode_name(msg: &[u8], mut pos: usize) -> io::Result<(String, usize)> {
+    let mut name = String::new();
+    let mut end = None;
+    let mut jumps = 0;
+    loop {
+        let len = *msg.get(pos).ok_or_else(|| invalid("dns name truncated"))? as usize;
+        if len & 0xc0 == 0xc0 {
+            let lo = *msg.get(pos + 1).ok_or_else(|| invalid("dns name truncated"))? as usize;
+            if end.is_none() {
+                end = Some(pos + 2);
+            }
+            jumps += 1;
+            if jumps > 16 {
+                return Err(invalid("dns name compression loop"));
+            }
+            pos = ((len & 0x3f) << 8) | lo;
+            continue;
+        }
+        pos += 1;
+        if len == 0 {
+            break;
+        }
+        let label = msg
+            .get(pos..pos + len)
+            .ok_or_else(|| invalid("dns name truncated"))?;
+        if !name.is_empty() {
+            name.push('.');
+        }
+        name.push_str(&String::from_utf8_lossy(label));
+        pos += len;
+    }
+    Ok((name, end.unwrap_or(pos)))
+}
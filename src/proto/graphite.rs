@@ -0,0 +1,423 @@
+//! Graphite (carbon) plaintext and pickle protocol helpers.
+//!
+//! Pickle payloads are encoded with pickle protocol 2 (as `pickle.dumps(metrics, protocol=2)`),
+//! decoder supports opcodes, used by common senders (protocols 0-4).
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::{self, Error, ErrorKind};
+
+/// A graphite datapoint.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metric {
+    /// Metric path.
+    pub path: String,
+    /// Unix timestamp.
+    pub timestamp: i64,
+    /// Value.
+    pub value: f64,
+}
+
+impl Metric {
+    /// Creates a new datapoint.
+    pub fn new(path: &str, value: f64, timestamp: i64) -> Self {
+        Metric {
+            path: path.to_string(),
+            timestamp,
+            value,
+        }
+    }
+}
+
+impl fmt::Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.path, self.value, self.timestamp)
+    }
+}
+
+/// Encodes metrics in plaintext protocol (`path value timestamp\n` lines).
+pub fn plaintext(metrics: &[Metric]) -> Vec<u8> {
+    let mut buf = String::new();
+    for m in metrics {
+        buf.push_str(&m.to_string());
+        buf.push('\n');
+    }
+    buf.into_bytes()
+}
+
+/// Parses plaintext protocol lines (for example written data).
+pub fn parse_plaintext(data: &[u8]) -> io::Result<Vec<Metric>> {
+    let text = std::str::from_utf8(data).map_err(|e| invalid(&e.to_string()))?;
+    let mut metrics = Vec::new();
+    for line in text.lines() {
+        if line.is_empty() {
+            continue;
+        }
+        let mut fields = line.split_whitespace();
+        let (path, value, timestamp) = match (fields.next(), fields.next(), fields.next()) {
+            (Some(path), Some(value), Some(timestamp)) if fields.next().is_none() => {
+                (path, value, timestamp)
+            }
+            _ => return Err(invalid(&format!("invalid plaintext line: {:?}", line))),
+        };
+        let value = value
+            .parse()
+            .map_err(|_| invalid(&format!("invalid value: {:?}", line)))?;
+        let timestamp = parse_timestamp(timestamp)
+            .ok_or_else(|| invalid(&format!("invalid timestamp: {:?}", line)))?;
+        metrics.push(Metric::new(path, value, timestamp));
+    }
+    Ok(metrics)
+}
+
+/// Encodes metrics in pickle protocol (4-byte big-endian length prefix and pickled
+/// list of `(path, (timestamp, value))` tuples).
+pub fn pickle(metrics: &[Metric]) -> Vec<u8> {
+    let mut payload = vec![0x80, 2, b']', b'('];
+    for m in metrics {
+        let path = m.path.as_bytes();
+        payload.push(b'X');
+        payload.extend_from_slice(&(path.len() as u32).to_le_bytes());
+        payload.extend_from_slice(path);
+        if m.timestamp >= i32::MIN as i64 && m.timestamp <= i32::MAX as i64 {
+            payload.push(b'J');
+            payload.extend_from_slice(&(m.timestamp as i32).to_le_bytes());
+        } else {
+            payload.extend_from_slice(&[0x8a, 8]);
+            payload.extend_from_slice(&m.timestamp.to_le_bytes());
+        }
+        payload.push(b'G');
+        payload.extend_from_slice(&m.value.to_be_bytes());
+        payload.extend_from_slice(&[0x86, 0x86]);
+    }
+    payload.extend_from_slice(b"e.");
+
+    let mut buf = Vec::with_capacity(payload.len() + 4);
+    buf.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    buf.extend_from_slice(&payload);
+    buf
+}
+
+/// Parses pickle protocol frames (for example written data), all frames must be complete.
+pub fn parse_pickle(mut data: &[u8]) -> io::Result<Vec<Metric>> {
+    let mut metrics = Vec::new();
+    while !data.is_empty() {
+        if data.len() < 4 {
+            return Err(invalid("pickle frame length truncated"));
+        }
+        let len = u32::from_be_bytes([data[0], data[1], data[2], data[3]]) as usize;
+        let end = match len.checked_add(4) {
+            Some(end) if end <= data.len() => end,
+            _ => return Err(invalid("pickle frame truncated")),
+        };
+        metrics.extend(unpickle_metrics(&data[4..end])?);
+        data = &data[end..];
+    }
+    Ok(metrics)
+}
+
+/// Asserts, that data (for example written) is a pickle protocol frames with expected metrics.
+pub fn assert_pickle(data: &[u8], expected: &[Metric]) {
+    match parse_pickle(data) {
+        Ok(metrics) => assert_eq!(metrics, expected, "pickle metrics mismatch"),
+        Err(err) => panic!("invalid pickle data: {}", err),
+    }
+}
+
+/// Asserts, that data (for example written) is a plaintext protocol lines with expected metrics.
+pub fn assert_plaintext(data: &[u8], expected: &[Metric]) {
+    match parse_plaintext(data) {
+        Ok(metrics) => assert_eq!(metrics, expected, "plaintext metrics mismatch"),
+        Err(err) => panic!("invalid plaintext data: {}", err),
+    }
+}
+
+fn invalid(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
+
+fn parse_timestamp(s: &str) -> Option<i64> {
+    s.parse::<i64>()
+        .ok()
+        .or_else(|| s.parse::<f64>().ok().map(|f| f as i64))
+}
+
+#[derive(Debug, Clone)]
+enum Value {
+    Mark,
+    None,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    List(Vec<Value>),
+    Tuple(Vec<Value>),
+}
+
+impl Value {
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Int(i) => Some(*i as f64),
+            Value::Bool(b) => Some(*b as u8 as f64),
+            Value::Float(f) => Some(*f),
+            Value::Str(s) => s.parse().ok(),
+            _ => None,
+        }
+    }
+
+    fn as_timestamp(&self) -> Option<i64> {
+        match self {
+            Value::Int(i) => Some(*i),
+            Value::Float(f) => Some(*f as i64),
+            Value::Str(s) => parse_timestamp(s),
+            _ => None,
+        }
+    }
+}
+
+fn unpickle_metrics(payload: &[u8]) -> io::Result<Vec<Metric>> {
+    let items = match unpickle(payload)? {
+        Value::List(items) | Value::Tuple(items) => items,
+        v => return Err(invalid(&format!("pickle: expected list, got {:?}", v))),
+    };
+    let mut metrics = Vec::with_capacity(items.len());
+    for item in items {
+        let metric = match &item {
+            Value::Tuple(pair) | Value::List(pair) if pair.len() == 2 => match (&pair[0], &pair[1]) {
+                (Value::Str(path), Value::Tuple(point)) | (Value::Str(path), Value::List(point))
+                    if point.len() == 2 =>
+                {
+                    match (point[0].as_timestamp(), point[1].as_f64()) {
+                        (Some(timestamp), Some(value)) => Some(Metric::new(path, value, timestamp)),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+        metrics.push(metric.ok_or_else(|| invalid(&format!("pickle: invalid metric {:?}", item)))?);
+    }
+    Ok(metrics)
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> io::Result<&'a [u8]> {
+        // length comes from (untrusted) data and can be out of range
+        let v = self
+            .pos
+            .checked_add(n)
+            .and_then(|end| self.data.get(self.pos..end))
+            .ok_or_else(|| invalid("pickle truncated"))?;
+        self.pos += n;
+        Ok(v)
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> io::Result<usize> {
+        let b = self.take(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+    }
+
+    fn u64(&mut self) -> io::Result<usize> {
+        let b = self.take(8)?;
+        let mut v = [0; 8];
+        v.copy_from_slice(b);
+        usize::try_from(u64::from_le_bytes(v)).map_err(|_| invalid("pickle length overflow"))
+    }
+
+    fn line(&mut self) -> io::Result<&'a str> {
+        let rest = &self.data[self.pos..];
+        let end = rest
+            .iter()
+            .position(|&c| c == b'\n')
+            .ok_or_else(|| invalid("pickle truncated"))?;
+        self.pos += end + 1;
+        std::str::from_utf8(&rest[..end]).map_err(|e| invalid(&e.to_string()))
+    }
+
+    fn string(&mut self, n: usize) -> io::Result<Value> {
+        Ok(Value::Str(String::from_utf8_lossy(self.take(n)?).into_owned()))
+    }
+}
+
+fn pop(stack: &mut Vec<Value>) -> io::Result<Value> {
+    stack.pop().ok_or_else(|| invalid("pickle stack underflow"))
+}
+
+fn pop_mark(stack: &mut Vec<Value>) -> io::Result<Vec<Value>> {
+    let mark = stack
+        .iter()
+        .rposition(|v| matches!(v, Value::Mark))
+        .ok_or_else(|| invalid("pickle mark not found"))?;
+    let items = stack.split_off(mark + 1);
+    stack.pop();
+    Ok(items)
+}
+
+fn append(stack: &mut [Value], items: Vec<Value>) -> io::Result<()> {
+    match stack.last_mut() {
+        Some(Value::List(list)) => {
+            list.extend(items);
+            Ok(())
+        }
+        _ => Err(invalid("pickle append to non-list")),
+    }
+}
+
+fn unpickle(payload: &[u8]) -> io::Result<Value> {
+    let mut r = Reader {
+        data: payload,
+        pos: 0,
+    };
+    let mut stack: Vec<Value> = Vec::new();
+    let mut memo: HashMap<usize, Value> = HashMap::new();
+    loop {
+        let op = r.u8()?;
+        match op {
+            0x80 => {
+                r.u8()?; // PROTO
+            }
+            0x95 => {
+                r.take(8)?; // FRAME
+            }
+            b'.' => return pop(&mut stack),
+            b'(' => stack.push(Value::Mark),
+            b']' => stack.push(Value::List(Vec::new())),
+            b')' => stack.push(Value::Tuple(Vec::new())),
+            b'l' => {
+                let items = pop_mark(&mut stack)?;
+                stack.push(Value::List(items));
+            }
+            b't' => {
+                let items = pop_mark(&mut stack)?;
+                stack.push(Value::Tuple(items));
+            }
+            0x85..=0x87 => {
+                let n = (op - 0x84) as usize;
+                if stack.len() < n {
+                    return Err(invalid("pickle stack underflow"));
+                }
+                let items = stack.split_off(stack.len() - n);
+                stack.push(Value::Tuple(items));
+            }
+            b'a' => {
+                let item = pop(&mut stack)?;
+                append(&mut stack, vec![item])?;
+            }
+            b'e' => {
+                let items = pop_mark(&mut stack)?;
+                append(&mut stack, items)?;
+            }
+            b'N' => stack.push(Value::None),
+            0x88 => stack.push(Value::Bool(true)),
+            0x89 => stack.push(Value::Bool(false)),
+            b'J' => {
+                let b = r.take(4)?;
+                stack.push(Value::Int(i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as i64));
+            }
+            b'K' => {
+                let v = r.u8()?;
+                stack.push(Value::Int(v as i64));
+            }
+            b'M' => {
+                let b = r.take(2)?;
+                stack.push(Value::Int(u16::from_le_bytes([b[0], b[1]]) as i64));
+            }
+            0x8a => {
+                let n = r.u8()? as usize;
+                let b = r.take(n)?;
+                if n > 8 {
+                    return Err(invalid("pickle long overflow"));
+                }
+                let fill = if b.last().is_some_and(|&v| v & 0x80 != 0) {
+                    0xff
+                } else {
+                    0
+                };
+                let mut v = [fill; 8];
+                v[..n].copy_from_slice(b);
+                stack.push(Value::Int(i64::from_le_bytes(v)));
+            }
+            b'I' | b'L' => {
+                let line = r.line()?.trim_end_matches('L');
+                let v = match line {
+                    "01" => Value::Bool(true),
+                    "00" => Value::Bool(false),
+                    _ => Value::Int(line.parse().map_err(|_| invalid("pickle invalid int"))?),
+                };
+                stack.push(v);
+            }
+            b'F' => {
+                let v = r.line()?.parse().map_err(|_| invalid("pickle invalid float"))?;
+                stack.push(Value::Float(v));
+            }
+            b'G' => {
+                let b = r.take(8)?;
+                let mut v = [0; 8];
+                v.copy_from_slice(b);
+                stack.push(Value::Float(f64::from_be_bytes(v)));
+            }
+            b'X' | b'T' | b'B' => {
+                let n = r.u32()?;
+                let v = r.string(n)?;
+                stack.push(v);
+            }
+            0x8c | b'U' | b'C' => {
+                let n = r.u8()? as usize;
+                let v = r.string(n)?;
+                stack.push(v);
+            }
+            0x8d | 0x8e => {
+                let n = r.u64()?;
+                let v = r.string(n)?;
+                stack.push(v);
+            }
+            b'S' => {
+                let line = r.line()?;
+                let s = line.trim_matches(|c| c == '\'' || c == '"');
+                stack.push(Value::Str(s.to_string()));
+            }
+            b'V' => {
+                let line = r.line()?;
+                stack.push(Value::Str(line.to_string()));
+            }
+            b'p' => {
+                let idx = r.line()?.parse().map_err(|_| invalid("pickle invalid memo"))?;
+                let top = stack.last().ok_or_else(|| invalid("pickle stack underflow"))?;
+                memo.insert(idx, top.clone());
+            }
+            b'q' | b'r' | 0x94 => {
+                let idx = match op {
+                    b'q' => r.u8()? as usize,
+                    b'r' => r.u32()?,
+                    _ => memo.len(),
+                };
+                let top = stack.last().ok_or_else(|| invalid("pickle stack underflow"))?;
+                memo.insert(idx, top.clone());
+            }
+            b'g' | b'h' | b'j' => {
+                let idx = match op {
+                    b'g' => r.line()?.parse().map_err(|_| invalid("pickle invalid memo"))?,
+                    b'h' => r.u8()? as usize,
+                    _ => r.u32()?,
+                };
+                let v = memo
+                    .get(&idx)
+                    .cloned()
+                    .ok_or_else(|| invalid("pickle memo not found"))?;
+                stack.push(v);
+            }
+            op => return Err(invalid(&format!("pickle unsupported opcode 0x{:02x}", op))),
+        }
+    }
+}
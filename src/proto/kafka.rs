@@ -0,0 +1,253 @@
+//! Kafka wire-protocol framing helpers (size-prefixed requests and responses).
+//!
+//! Only request/response headers are handled, bodies can be composed with [`Encoder`].
+
+use std::io::{self, Error, ErrorKind};
+
+/// `Produce` API key.
+pub const PRODUCE: i16 = 0;
+/// `Fetch` API key.
+pub const FETCH: i16 = 1;
+/// `ListOffsets` API key.
+pub const LIST_OFFSETS: i16 = 2;
+/// `Metadata` API key.
+pub const METADATA: i16 = 3;
+/// `OffsetCommit` API key.
+pub const OFFSET_COMMIT: i16 = 8;
+/// `OffsetFetch` API key.
+pub const OFFSET_FETCH: i16 = 9;
+/// `FindCoordinator` API key.
+pub const FIND_COORDINATOR: i16 = 10;
+/// `JoinGroup` API key.
+pub const JOIN_GROUP: i16 = 11;
+/// `Heartbeat` API key.
+pub const HEARTBEAT: i16 = 12;
+/// `SyncGroup` API key.
+pub const SYNC_GROUP: i16 = 14;
+/// `SaslHandshake` API key.
+pub const SASL_HANDSHAKE: i16 = 17;
+/// `ApiVersions` API key.
+pub const API_VERSIONS: i16 = 18;
+
+/// A request header (v1, or v2 with `flexible` set).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestHeader {
+    /// API key.
+    pub api_key: i16,
+    /// API version.
+    pub api_version: i16,
+    /// Correlation ID.
+    pub correlation_id: i32,
+    /// Client ID (nullable).
+    pub client_id: Option<String>,
+    /// Flexible version (header v2, with tagged fields).
+    pub flexible: bool,
+}
+
+impl RequestHeader {
+    /// Creates a new request header (v1).
+    pub fn new(api_key: i16, api_version: i16, correlation_id: i32, client_id: &str) -> Self {
+        RequestHeader {
+            api_key,
+            api_version,
+            correlation_id,
+            client_id: Some(client_id.to_string()),
+            flexible: false,
+        }
+    }
+
+    /// Set flexible version (header v2, with empty tagged fields).
+    pub fn flexible(mut self, flexible: bool) -> Self {
+        self.flexible = flexible;
+        self
+    }
+
+    fn encode(&self, enc: Encoder) -> Encoder {
+        let enc = enc
+            .i16(self.api_key)
+            .i16(self.api_version)
+            .i32(self.correlation_id)
+            .nullable_string(self.client_id.as_deref());
+        if self.flexible {
+            enc.empty_tagged_fields()
+        } else {
+            enc
+        }
+    }
+}
+
+/// Builds a size-prefixed request frame.
+pub fn request(header: &RequestHeader, body: &[u8]) -> Vec<u8> {
+    frame(header.encode(Encoder::new()).raw(body).finish())
+}
+
+/// Builds a size-prefixed response frame (header v0).
+pub fn response(correlation_id: i32, body: &[u8]) -> Vec<u8> {
+    frame(Encoder::new().i32(correlation_id).raw(body).finish())
+}
+
+/// Builds a size-prefixed response frame for flexible versions (header v1, with empty tagged fields).
+pub fn response_flexible(correlation_id: i32, body: &[u8]) -> Vec<u8> {
+    frame(
+        Encoder::new()
+            .i32(correlation_id)
+            .empty_tagged_fields()
+            .raw(body)
+            .finish(),
+    )
+}
+
+/// Parses a request frame (without size prefix, see [`split_frames`]) to header (v1) and body.
+///
+/// For flexible versions tagged fields are left in the body.
+pub fn parse_request(frame: &[u8]) -> io::Result<(RequestHeader, &[u8])> {
+    if frame.len() < 10 {
+        return Err(invalid("kafka request header truncated"));
+    }
+    let client_id_len = i16::from_be_bytes([frame[8], frame[9]]);
+    let (client_id, body) = if client_id_len < 0 {
+        (None, &frame[10..])
+    } else {
+        let end = 10 + client_id_len as usize;
+        let client_id = frame
+            .get(10..end)
+            .ok_or_else(|| invalid("kafka client id truncated"))?;
+        (
+            Some(String::from_utf8_lossy(client_id).into_owned()),
+            &frame[end..],
+        )
+    };
+    let header = RequestHeader {
+        api_key: i16::from_be_bytes([frame[0], frame[1]]),
+        api_version: i16::from_be_bytes([frame[2], frame[3]]),
+        correlation_id: i32::from_be_bytes([frame[4], frame[5], frame[6], frame[7]]),
+        client_id,
+        flexible: false,
+    };
+    Ok((header, body))
+}
+
+/// Split stream data (for example written) to frames (without size prefix).
+pub fn split_frames(mut data: &[u8]) -> io::Result<Vec<&[u8]>> {
+    let mut frames = Vec::new();
+    while !data.is_empty() {
+        if data.len() < 4 {
+            return Err(invalid("kafka frame size truncated"));
+        }
+        let size = i32::from_be_bytes([data[0], data[1], data[2], data[3]]);
+        if size < 0 || data.len() < size as usize + 4 {
+            return Err(invalid("kafka frame truncated"));
+        }
+        frames.push(&data[4..size as usize + 4]);
+        data = &data[size as usize + 4..];
+    }
+    Ok(frames)
+}
+
+/// Adds a size prefix to frame.
+pub fn frame(payload: Vec<u8>) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(payload.len() + 4);
+    buf.extend_from_slice(&(payload.len() as i32).to_be_bytes());
+    buf.extend(payload);
+    buf
+}
+
+/// A builder for request/response bodies with Kafka primitive types.
+#[derive(Debug, Clone, Default)]
+pub struct Encoder {
+    buf: Vec<u8>,
+}
+
+impl Encoder {
+    /// Creates a new empty encoder.
+    pub fn new() -> Self {
+        Encoder::default()
+    }
+
+    /// Append `INT8`.
+    pub fn i8(mut self, v: i8) -> Self {
+        self.buf.push(v as u8);
+        self
+    }
+
+    /// Append `INT16`.
+    pub fn i16(mut self, v: i16) -> Self {
+        self.buf.extend_from_slice(&v.to_be_bytes());
+        self
+    }
+
+    /// Append `INT32`.
+    pub fn i32(mut self, v: i32) -> Self {
+        self.buf.extend_from_slice(&v.to_be_bytes());
+        self
+    }
+
+    /// Append `INT64`.
+    pub fn i64(mut self, v: i64) -> Self {
+        self.buf.extend_from_slice(&v.to_be_bytes());
+        self
+    }
+
+    /// Append `UNSIGNED_VARINT`.
+    pub fn unsigned_varint(mut self, mut v: u32) -> Self {
+        while v >= 0x80 {
+            self.buf.push((v as u8) | 0x80);
+            v >>= 7;
+        }
+        self.buf.push(v as u8);
+        self
+    }
+
+    /// Append `STRING`.
+    pub fn string(self, s: &str) -> Self {
+        self.nullable_string(Some(s))
+    }
+
+    /// Append `NULLABLE_STRING`.
+    pub fn nullable_string(self, s: Option<&str>) -> Self {
+        match s {
+            Some(s) => self.i16(s.len() as i16).raw(s.as_bytes()),
+            None => self.i16(-1),
+        }
+    }
+
+    /// Append `COMPACT_STRING`.
+    pub fn compact_string(self, s: &str) -> Self {
+        self.unsigned_varint(s.len() as u32 + 1).raw(s.as_bytes())
+    }
+
+    /// Append `BYTES`.
+    pub fn bytes(self, b: &[u8]) -> Self {
+        self.i32(b.len() as i32).raw(b)
+    }
+
+    /// Append `ARRAY` length.
+    pub fn array_len(self, len: usize) -> Self {
+        self.i32(len as i32)
+    }
+
+    /// Append `COMPACT_ARRAY` length.
+    pub fn compact_array_len(self, len: usize) -> Self {
+        self.unsigned_varint(len as u32 + 1)
+    }
+
+    /// Append empty tagged fields.
+    pub fn empty_tagged_fields(self) -> Self {
+        self.unsigned_varint(0)
+    }
+
+    /// Append raw bytes.
+    pub fn raw(mut self, b: &[u8]) -> Self {
+        self.buf.extend_from_slice(b);
+        self
+    }
+
+    /// Gets encoded bytes.
+    pub fn finish(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+fn invalid(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
@@ -0,0 +1,126 @@
+//! Memcached ASCII (text) protocol helpers.
+
+/// `STORED` response.
+pub const STORED: &[u8] = b"STORED\r\n";
+/// `NOT_STORED` response.
+pub const NOT_STORED: &[u8] = b"NOT_STORED\r\n";
+/// `EXISTS` response.
+pub const EXISTS: &[u8] = b"EXISTS\r\n";
+/// `NOT_FOUND` response.
+pub const NOT_FOUND: &[u8] = b"NOT_FOUND\r\n";
+/// `DELETED` response.
+pub const DELETED: &[u8] = b"DELETED\r\n";
+/// `END` response (end of retrieval).
+pub const END: &[u8] = b"END\r\n";
+/// `ERROR` response (unknown command).
+pub const ERROR: &[u8] = b"ERROR\r\n";
+
+/// A storage command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Storage {
+    /// `set`
+    Set,
+    /// `add`
+    Add,
+    /// `replace`
+    Replace,
+    /// `append`
+    Append,
+    /// `prepend`
+    Prepend,
+}
+
+impl Storage {
+    fn name(self) -> &'static str {
+        match self {
+            Storage::Set => "set",
+            Storage::Add => "add",
+            Storage::Replace => "replace",
+            Storage::Append => "append",
+            Storage::Prepend => "prepend",
+        }
+    }
+}
+
+/// Builds `get <key>*\r\n` command.
+pub fn get(keys: &[&str]) -> Vec<u8> {
+    retrieval("get", keys)
+}
+
+/// Builds `gets <key>*\r\n` command.
+pub fn gets(keys: &[&str]) -> Vec<u8> {
+    retrieval("gets", keys)
+}
+
+fn retrieval(cmd: &str, keys: &[&str]) -> Vec<u8> {
+    let mut buf = cmd.as_bytes().to_vec();
+    for key in keys {
+        buf.push(b' ');
+        buf.extend_from_slice(key.as_bytes());
+    }
+    buf.extend_from_slice(b"\r\n");
+    buf
+}
+
+/// Builds `set <key> <flags> <exptime> <bytes>\r\n<data>\r\n` command.
+pub fn set(key: &str, flags: u32, exptime: u32, data: &[u8]) -> Vec<u8> {
+    storage(Storage::Set, key, flags, exptime, data, false)
+}
+
+/// Builds storage command (`set`, `add`, etc.) with optional `noreply`.
+pub fn storage(
+    cmd: Storage,
+    key: &str,
+    flags: u32,
+    exptime: u32,
+    data: &[u8],
+    noreply: bool,
+) -> Vec<u8> {
+    let noreply = if noreply { " noreply" } else { "" };
+    let mut buf = format!(
+        "{} {} {} {} {}{}\r\n",
+        cmd.name(),
+        key,
+        flags,
+        exptime,
+        data.len(),
+        noreply
+    )
+    .into_bytes();
+    buf.extend_from_slice(data);
+    buf.extend_from_slice(b"\r\n");
+    buf
+}
+
+/// Builds `delete <key>\r\n` command.
+pub fn delete(key: &str) -> Vec<u8> {
+    format!("delete {}\r\n", key).into_bytes()
+}
+
+/// Builds `VALUE <key> <flags> <bytes>\r\n<data>\r\n` response item (without `END`).
+pub fn value(key: &str, flags: u32, data: &[u8]) -> Vec<u8> {
+    let mut buf = format!("VALUE {} {} {}\r\n", key, flags, data.len()).into_bytes();
+    buf.extend_from_slice(data);
+    buf.extend_from_slice(b"\r\n");
+    buf
+}
+
+/// Builds a full retrieval response: `VALUE` items (key, flags, data) and `END`.
+pub fn values(items: &[(&str, u32, &[u8])]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    for (key, flags, data) in items {
+        buf.extend_from_slice(&value(key, *flags, data));
+    }
+    buf.extend_from_slice(END);
+    buf
+}
+
+/// Builds `SERVER_ERROR <msg>\r\n` response.
+pub fn server_error(msg: &str) -> Vec<u8> {
+    format!("SERVER_ERROR {}\r\n", msg).into_bytes()
+}
+
+/// Builds `CLIENT_ERROR <msg>\r\n` response.
+pub fn client_error(msg: &str) -> Vec<u8> {
+    format!("CLIENT_ERROR {}\r\n", msg).into_bytes()
+}
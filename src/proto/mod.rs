@@ -0,0 +1,14 @@
+//! Protocol helpers for build expected writes and scripted reads for mock streams.
+#![warn(missing_docs)]
+
+pub mod dns;
+pub mod graphite;
+#[cfg(feature = "kafka")]
+pub mod kafka;
+pub mod memcached;
+pub mod postgres;
+pub mod statsd;
+pub mod syslog;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,284 @@
+//! PostgreSQL wire protocol (v3) helpers for startup, authentication and simple query messages.
+//!
+//! Frontend messages (sent by client) are for expected writes, backend messages are for scripted reads.
+//! Authentication is stubbed: MD5 and SCRAM hashes are not calculated, pass precomputed values.
+
+use std::io::{self, Error, ErrorKind};
+
+/// Protocol version 3.0 code.
+pub const PROTOCOL_VERSION: i32 = 196608;
+/// `SSLRequest` code.
+pub const SSL_REQUEST_CODE: i32 = 80877103;
+
+/// `ReadyForQuery` status: idle.
+pub const IDLE: u8 = b'I';
+/// `ReadyForQuery` status: in transaction.
+pub const IN_TRANSACTION: u8 = b'T';
+/// `ReadyForQuery` status: failed transaction.
+pub const FAILED_TRANSACTION: u8 = b'E';
+
+/// Builds `StartupMessage` with parameters (`user`, `database`, etc.).
+pub fn startup(params: &[(&str, &str)]) -> Vec<u8> {
+    let mut body = PROTOCOL_VERSION.to_be_bytes().to_vec();
+    for (name, value) in params {
+        push_cstr(&mut body, name);
+        push_cstr(&mut body, value);
+    }
+    body.push(0);
+    untyped(&body)
+}
+
+/// Builds `SSLRequest`.
+pub fn ssl_request() -> Vec<u8> {
+    untyped(&SSL_REQUEST_CODE.to_be_bytes())
+}
+
+/// Builds (single byte) response for `SSLRequest`: `S` (accepted) or `N` (refused).
+pub fn ssl_response(accepted: bool) -> Vec<u8> {
+    vec![if accepted { b'S' } else { b'N' }]
+}
+
+/// Builds `PasswordMessage` (cleartext or precomputed `md5...` hash).
+pub fn password(password: &str) -> Vec<u8> {
+    let mut body = Vec::new();
+    push_cstr(&mut body, password);
+    message(b'p', &body)
+}
+
+/// Builds `SASLInitialResponse`.
+pub fn sasl_initial_response(mechanism: &str, data: &[u8]) -> Vec<u8> {
+    let mut body = Vec::new();
+    push_cstr(&mut body, mechanism);
+    body.extend_from_slice(&(data.len() as i32).to_be_bytes());
+    body.extend_from_slice(data);
+    message(b'p', &body)
+}
+
+/// Builds `SASLResponse`.
+pub fn sasl_response(data: &[u8]) -> Vec<u8> {
+    message(b'p', data)
+}
+
+/// Builds `Query` (simple query).
+pub fn query(sql: &str) -> Vec<u8> {
+    let mut body = Vec::new();
+    push_cstr(&mut body, sql);
+    message(b'Q', &body)
+}
+
+/// Builds `Terminate`.
+pub fn terminate() -> Vec<u8> {
+    message(b'X', &[])
+}
+
+/// Builds `AuthenticationOk`.
+pub fn auth_ok() -> Vec<u8> {
+    auth(0, &[])
+}
+
+/// Builds `AuthenticationCleartextPassword`.
+pub fn auth_cleartext() -> Vec<u8> {
+    auth(3, &[])
+}
+
+/// Builds `AuthenticationMD5Password` with salt.
+pub fn auth_md5(salt: [u8; 4]) -> Vec<u8> {
+    auth(5, &salt)
+}
+
+/// Builds `AuthenticationSASL` with mechanisms list (for example `SCRAM-SHA-256`).
+pub fn auth_sasl(mechanisms: &[&str]) -> Vec<u8> {
+    let mut data = Vec::new();
+    for mechanism in mechanisms {
+        push_cstr(&mut data, mechanism);
+    }
+    data.push(0);
+    auth(10, &data)
+}
+
+/// Builds `AuthenticationSASLContinue`.
+pub fn auth_sasl_continue(data: &[u8]) -> Vec<u8> {
+    auth(11, data)
+}
+
+/// Builds `AuthenticationSASLFinal`.
+pub fn auth_sasl_final(data: &[u8]) -> Vec<u8> {
+    auth(12, data)
+}
+
+/// Builds `ParameterStatus`.
+pub fn parameter_status(name: &str, value: &str) -> Vec<u8> {
+    let mut body = Vec::new();
+    push_cstr(&mut body, name);
+    push_cstr(&mut body, value);
+    message(b'S', &body)
+}
+
+/// Builds `BackendKeyData`.
+pub fn backend_key_data(pid: i32, secret: i32) -> Vec<u8> {
+    let mut body = pid.to_be_bytes().to_vec();
+    body.extend_from_slice(&secret.to_be_bytes());
+    message(b'K', &body)
+}
+
+/// Builds `ReadyForQuery` with transaction status ([`IDLE`], [`IN_TRANSACTION`], [`FAILED_TRANSACTION`]).
+pub fn ready_for_query(status: u8) -> Vec<u8> {
+    message(b'Z', &[status])
+}
+
+/// Builds a successful startup sequence: `AuthenticationOk`, `ParameterStatus` for each parameter,
+/// `BackendKeyData` and `ReadyForQuery` (idle).
+pub fn startup_ok(params: &[(&str, &str)]) -> Vec<u8> {
+    let mut buf = auth_ok();
+    for (name, value) in params {
+        buf.extend(parameter_status(name, value));
+    }
+    buf.extend(backend_key_data(1, 1));
+    buf.extend(ready_for_query(IDLE));
+    buf
+}
+
+/// Builds `RowDescription` for text (`text` type) columns.
+pub fn row_description(columns: &[&str]) -> Vec<u8> {
+    let mut body = (columns.len() as i16).to_be_bytes().to_vec();
+    for column in columns {
+        push_cstr(&mut body, column);
+        body.extend_from_slice(&0i32.to_be_bytes()); // table oid
+        body.extend_from_slice(&0i16.to_be_bytes()); // column attr
+        body.extend_from_slice(&25i32.to_be_bytes()); // text type oid
+        body.extend_from_slice(&(-1i16).to_be_bytes()); // type size
+        body.extend_from_slice(&(-1i32).to_be_bytes()); // type modifier
+        body.extend_from_slice(&0i16.to_be_bytes()); // text format
+    }
+    message(b'T', &body)
+}
+
+/// Builds `DataRow` (in text format, `None` for NULL).
+pub fn data_row(values: &[Option<&str>]) -> Vec<u8> {
+    let mut body = (values.len() as i16).to_be_bytes().to_vec();
+    for value in values {
+        match value {
+            Some(v) => {
+                body.extend_from_slice(&(v.len() as i32).to_be_bytes());
+                body.extend_from_slice(v.as_bytes());
+            }
+            None => body.extend_from_slice(&(-1i32).to_be_bytes()),
+        }
+    }
+    message(b'D', &body)
+}
+
+/// Builds `CommandComplete` with tag (for example `SELECT 1`).
+pub fn command_complete(tag: &str) -> Vec<u8> {
+    let mut body = Vec::new();
+    push_cstr(&mut body, tag);
+    message(b'C', &body)
+}
+
+/// Builds `EmptyQueryResponse`.
+pub fn empty_query_response() -> Vec<u8> {
+    message(b'I', &[])
+}
+
+/// Builds `ErrorResponse` with severity (`ERROR`, `FATAL`), SQLSTATE code and message.
+pub fn error_response(severity: &str, code: &str, msg: &str) -> Vec<u8> {
+    let mut body = Vec::new();
+    for (field, value) in [
+        (b'S', severity),
+        (b'V', severity),
+        (b'C', code),
+        (b'M', msg),
+    ] {
+        body.push(field);
+        push_cstr(&mut body, value);
+    }
+    body.push(0);
+    message(b'E', &body)
+}
+
+/// Builds a full simple query response: `RowDescription`, `DataRow`s, `CommandComplete`
+/// (`SELECT <rows>`) and `ReadyForQuery` (idle).
+pub fn simple_query_response(columns: &[&str], rows: &[&[Option<&str>]]) -> Vec<u8> {
+    let mut buf = row_description(columns);
+    for row in rows {
+        buf.extend(data_row(row));
+    }
+    buf.extend(command_complete(&format!("SELECT {}", rows.len())));
+    buf.extend(ready_for_query(IDLE));
+    buf
+}
+
+/// Parses `StartupMessage` (for example written) to protocol version and parameters.
+pub fn parse_startup(data: &[u8]) -> io::Result<(i32, Vec<(String, String)>)> {
+    if data.len() < 8 {
+        return Err(invalid("postgres startup message truncated"));
+    }
+    let len = i32::from_be_bytes([data[0], data[1], data[2], data[3]]) as usize;
+    if len < 8 || data.len() < len {
+        return Err(invalid("postgres startup message truncated"));
+    }
+    let version = i32::from_be_bytes([data[4], data[5], data[6], data[7]]);
+    let mut fields = data[8..len].split(|&c| c == 0);
+    let mut params = Vec::new();
+    while let Some(name) = fields.next() {
+        if name.is_empty() {
+            break;
+        }
+        let value = fields
+            .next()
+            .ok_or_else(|| invalid("postgres startup parameter value missing"))?;
+        params.push((
+            String::from_utf8_lossy(name).into_owned(),
+            String::from_utf8_lossy(value).into_owned(),
+        ));
+    }
+    Ok((version, params))
+}
+
+/// Split typed messages stream (for example written after startup) to (type, body) pairs.
+pub fn split_messages(mut data: &[u8]) -> io::Result<Vec<(u8, &[u8])>> {
+    let mut msgs = Vec::new();
+    while !data.is_empty() {
+        if data.len() < 5 {
+            return Err(invalid("postgres message header truncated"));
+        }
+        let len = i32::from_be_bytes([data[1], data[2], data[3], data[4]]);
+        if len < 4 || data.len() < len as usize + 1 {
+            return Err(invalid("postgres message truncated"));
+        }
+        let end = len as usize + 1;
+        msgs.push((data[0], &data[5..end]));
+        data = &data[end..];
+    }
+    Ok(msgs)
+}
+
+fn auth(code: i32, data: &[u8]) -> Vec<u8> {
+    let mut body = code.to_be_bytes().to_vec();
+    body.extend_from_slice(data);
+    message(b'R', &body)
+}
+
+fn message(tag: u8, body: &[u8]) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(body.len() + 5);
+    buf.push(tag);
+    buf.extend_from_slice(&(body.len() as i32 + 4).to_be_bytes());
+    buf.extend_from_slice(body);
+    buf
+}
+
+fn untyped(body: &[u8]) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(body.len() + 4);
+    buf.extend_from_slice(&(body.len() as i32 + 4).to_be_bytes());
+    buf.extend_from_slice(body);
+    buf
+}
+
+fn push_cstr(buf: &mut Vec<u8>, s: &str) {
+    buf.extend_from_slice(s.as_bytes());
+    buf.push(0);
+}
+
+fn invalid(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
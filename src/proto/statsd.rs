@@ -0,0 +1,201 @@
+//! StatsD protocol helpers (with DogStatsD tags extension).
+//!
+//! Packets may contain several newline-separated metrics, so helpers work both with
+//! datagrams and with written stream data. There is no UDP mock in the crate, datagram payloads
+//! are checked with [`assert_statsd`] like written data.
+
+use std::fmt;
+use std::io::{self, Error, ErrorKind};
+
+/// A statsd metric value (with type).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// Counter (`c`).
+    Counter(f64),
+    /// Gauge (`g`). Negative gauge is encoded by metric as reset to zero and delta (`0|g` line
+    /// and `-5|g` line), [`parse`] reads such lines back as a single gauge.
+    Gauge(f64),
+    /// Gauge delta (`g` with explicit sign).
+    GaugeDelta(f64),
+    /// Timer (`ms`).
+    Timer(f64),
+    /// Histogram (`h`).
+    Histogram(f64),
+    /// Distribution (`d`).
+    Distribution(f64),
+    /// Set (`s`).
+    Set(String),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Counter(v) => write!(f, "{}|c", v),
+            Value::Gauge(v) => write!(f, "{}|g", v),
+            Value::GaugeDelta(v) => write!(f, "{:+}|g", v),
+            Value::Timer(v) => write!(f, "{}|ms", v),
+            Value::Histogram(v) => write!(f, "{}|h", v),
+            Value::Distribution(v) => write!(f, "{}|d", v),
+            Value::Set(v) => write!(f, "{}|s", v),
+        }
+    }
+}
+
+/// A statsd metric.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metric {
+    /// Metric name.
+    pub name: String,
+    /// Metric value.
+    pub value: Value,
+    /// Sample rate (`@rate`).
+    pub sample_rate: Option<f64>,
+    /// Tags (`#tag1,tag2`).
+    pub tags: Vec<String>,
+}
+
+impl Metric {
+    /// Creates a new metric.
+    pub fn new(name: &str, value: Value) -> Self {
+        Metric {
+            name: name.to_string(),
+            value,
+            sample_rate: None,
+            tags: Vec::new(),
+        }
+    }
+
+    /// Set sample rate.
+    pub fn sample_rate(mut self, rate: f64) -> Self {
+        self.sample_rate = Some(rate);
+        self
+    }
+
+    /// Add tag.
+    pub fn tag(mut self, tag: &str) -> Self {
+        self.tags.push(tag.to_string());
+        self
+    }
+
+    /// Encodes metric (without trailing newline).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.to_string().into_bytes()
+    }
+
+    /// Parses a single metric line.
+    pub fn parse(line: &str) -> io::Result<Metric> {
+        let (name, rest) = line
+            .split_once(':')
+            .ok_or_else(|| invalid("statsd: missing ':'", line))?;
+        let mut fields = rest.split('|');
+        let value = fields.next().unwrap_or_default();
+        let kind = fields
+            .next()
+            .ok_or_else(|| invalid("statsd: missing type", line))?;
+        let number = || {
+            value
+                .parse::<f64>()
+                .map_err(|_| invalid("statsd: invalid value", line))
+        };
+        let value = match kind {
+            "c" => Value::Counter(number()?),
+            "g" if value.starts_with('+') || value.starts_with('-') => Value::GaugeDelta(number()?),
+            "g" => Value::Gauge(number()?),
+            "ms" => Value::Timer(number()?),
+            "h" => Value::Histogram(number()?),
+            "d" => Value::Distribution(number()?),
+            "s" => Value::Set(value.to_string()),
+            _ => return Err(invalid("statsd: unknown type", line)),
+        };
+        let mut metric = Metric::new(name, value);
+        for field in fields {
+            if let Some(rate) = field.strip_prefix('@') {
+                metric.sample_rate = Some(
+                    rate.parse()
+                        .map_err(|_| invalid("statsd: invalid sample rate", line))?,
+                );
+            } else if let Some(tags) = field.strip_prefix('#') {
+                metric.tags = tags.split(',').map(|t| t.to_string()).collect();
+            } else {
+                return Err(invalid("statsd: unknown field", line));
+            }
+        }
+        Ok(metric)
+    }
+}
+
+impl fmt::Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.value {
+            // signed value is a delta, so negative gauge is set from zero
+            Value::Gauge(v) if v.is_sign_negative() && v != 0.0 => {
+                self.fmt_value(f, &Value::Gauge(0.0))?;
+                f.write_str("\n")?;
+                self.fmt_value(f, &Value::GaugeDelta(v))
+            }
+            ref value => self.fmt_value(f, value),
+        }
+    }
+}
+
+impl Metric {
+    fn fmt_value(&self, f: &mut fmt::Formatter<'_>, value: &Value) -> fmt::Result {
+        write!(f, "{}:{}", self.name, value)?;
+        if let Some(rate) = self.sample_rate {
+            write!(f, "|@{}", rate)?;
+        }
+        if !self.tags.is_empty() {
+            write!(f, "|#{}", self.tags.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses newline-separated metrics from datagram (or written stream data).
+///
+/// Gauge reset to zero, followed by negative delta of the same metric, is read as negative gauge.
+pub fn parse(data: &[u8]) -> io::Result<Vec<Metric>> {
+    let text = std::str::from_utf8(data).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    let mut metrics: Vec<Metric> = Vec::new();
+    for line in text.lines().filter(|line| !line.is_empty()) {
+        let metric = Metric::parse(line)?;
+        if let Value::GaugeDelta(delta) = metric.value {
+            let reset = Metric {
+                value: Value::Gauge(0.0),
+                ..metric.clone()
+            };
+            match metrics.last_mut() {
+                Some(prev) if delta < 0.0 && *prev == reset => {
+                    prev.value = Value::Gauge(delta);
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        metrics.push(metric);
+    }
+    Ok(metrics)
+}
+
+/// Asserts, that data (datagram or written stream data) contains a metric with name, value and sample rate.
+pub fn assert_statsd(data: &[u8], name: &str, value: Value, sample_rate: Option<f64>) {
+    let metrics = match parse(data) {
+        Ok(metrics) => metrics,
+        Err(err) => panic!("invalid statsd data: {}", err),
+    };
+    let found = metrics
+        .iter()
+        .any(|m| m.name == name && m.value == value && m.sample_rate == sample_rate);
+    assert!(
+        found,
+        "statsd metric {}:{} (sample rate {:?}) not found in {:?}",
+        name,
+        value,
+        sample_rate,
+        String::from_utf8_lossy(data)
+    );
+}
+
+fn invalid(msg: &str, line: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("{}: {:?}", msg, line))
+}
@@ -0,0 +1,248 @@
+//! Syslog message helpers (RFC 3164 and RFC 5424) with octet-counted (RFC 6587) TCP framing.
+
+use std::io::{self, Error, ErrorKind};
+
+/// A parsed syslog message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    /// Facility (`PRI / 8`).
+    pub facility: u8,
+    /// Severity (`PRI % 8`).
+    pub severity: u8,
+    /// Protocol version (`1` for RFC 5424, `None` for RFC 3164).
+    pub version: Option<u8>,
+    /// Timestamp (as is), `None` for nil value.
+    pub timestamp: Option<String>,
+    /// Hostname, `None` for nil value.
+    pub hostname: Option<String>,
+    /// Application name (or RFC 3164 tag).
+    pub app_name: Option<String>,
+    /// Process ID.
+    pub proc_id: Option<String>,
+    /// Message ID (RFC 5424 only).
+    pub msg_id: Option<String>,
+    /// Structured data elements (RFC 5424 only).
+    pub structured_data: Vec<SdElement>,
+    /// Message text.
+    pub msg: String,
+}
+
+/// A structured data element (`[id name="value" ...]`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SdElement {
+    /// SD-ID.
+    pub id: String,
+    /// Parameters (name, value).
+    pub params: Vec<(String, String)>,
+}
+
+impl SdElement {
+    /// Gets a parameter value by name.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+impl Message {
+    /// Parses RFC 5424 (detected by version after PRI) or RFC 3164 message.
+    pub fn parse(data: &[u8]) -> io::Result<Message> {
+        let text = std::str::from_utf8(data)
+            .map_err(|e| invalid(&e.to_string()))?
+            .trim_end_matches(['\n', '\r', '\0']);
+        let rest = text
+            .strip_prefix('<')
+            .ok_or_else(|| invalid("syslog: missing PRI"))?;
+        let end = rest
+            .find('>')
+            .ok_or_else(|| invalid("syslog: missing PRI"))?;
+        let pri: u8 = rest[..end]
+            .parse()
+            .ok()
+            .filter(|&pri| pri < 192)
+            .ok_or_else(|| invalid("syslog: invalid PRI"))?;
+        let rest = &rest[end + 1..];
+        let mut msg = Message {
+            facility: pri / 8,
+            severity: pri % 8,
+            version: None,
+            timestamp: None,
+            hostname: None,
+            app_name: None,
+            proc_id: None,
+            msg_id: None,
+            structured_data: Vec::new(),
+            msg: String::new(),
+        };
+        if let Some(rest) = rest.strip_prefix("1 ") {
+            msg.version = Some(1);
+            msg.parse_5424(rest)?;
+        } else {
+            msg.parse_3164(rest);
+        }
+        Ok(msg)
+    }
+
+    /// Gets a structured data element by SD-ID.
+    pub fn sd(&self, id: &str) -> Option<&SdElement> {
+        self.structured_data.iter().find(|e| e.id == id)
+    }
+
+    fn parse_5424(&mut self, s: &str) -> io::Result<()> {
+        let mut fields = s.splitn(6, ' ');
+        let mut next = || {
+            fields
+                .next()
+                .map(nil)
+                .ok_or_else(|| invalid("syslog: truncated header"))
+        };
+        self.timestamp = next()?;
+        self.hostname = next()?;
+        self.app_name = next()?;
+        self.proc_id = next()?;
+        self.msg_id = next()?;
+        let rest = fields.next().unwrap_or("");
+        let rest = if let Some(rest) = rest.strip_prefix('-') {
+            rest
+        } else {
+            self.parse_sd(rest)?
+        };
+        let rest = rest.strip_prefix(' ').unwrap_or(rest);
+        self.msg = rest.strip_prefix('\u{feff}').unwrap_or(rest).to_string();
+        Ok(())
+    }
+
+    // parse structured data elements, returns rest
+    fn parse_sd<'a>(&mut self, mut s: &'a str) -> io::Result<&'a str> {
+        while let Some(rest) = s.strip_prefix('[') {
+            let id_end = rest
+                .find([' ', ']'])
+                .ok_or_else(|| invalid("syslog: truncated structured data"))?;
+            let mut element = SdElement {
+                id: rest[..id_end].to_string(),
+                params: Vec::new(),
+            };
+            let mut rest = &rest[id_end..];
+            loop {
+                rest = rest.trim_start_matches(' ');
+                if let Some(r) = rest.strip_prefix(']') {
+                    rest = r;
+                    break;
+                }
+                let eq = rest
+                    .find("=\"")
+                    .ok_or_else(|| invalid("syslog: invalid structured data param"))?;
+                let name = rest[..eq].to_string();
+                let mut value = String::new();
+                let mut chars = rest[eq + 2..].char_indices();
+                let mut end = None;
+                while let Some((i, c)) = chars.next() {
+                    match c {
+                        '\\' => {
+                            if let Some((_, c)) = chars.next() {
+                                value.push(c);
+                            }
+                        }
+                        '"' => {
+                            end = Some(eq + 2 + i + 1);
+                            break;
+                        }
+                        c => value.push(c),
+                    }
+                }
+                let end = end.ok_or_else(|| invalid("syslog: unterminated param value"))?;
+                element.params.push((name, value));
+                rest = &rest[end..];
+            }
+            self.structured_data.push(element);
+            s = rest;
+        }
+        Ok(s)
+    }
+
+    fn parse_3164(&mut self, s: &str) {
+        // Mmm dd hh:mm:ss
+        let mut rest = s;
+        if s.len() >= 16 && s.as_bytes()[3] == b' ' && s.as_bytes()[15] == b' ' {
+            self.timestamp = Some(s[..15].to_string());
+            rest = &s[16..];
+            if let Some((host, r)) = rest.split_once(' ') {
+                if !host.ends_with(':') && !host.contains('[') {
+                    self.hostname = Some(host.to_string());
+                    rest = r;
+                }
+            }
+        }
+        if let Some(colon) = rest.find(": ") {
+            let tag = &rest[..colon];
+            if !tag.is_empty() && !tag.contains(' ') {
+                match tag.find('[') {
+                    Some(open) if tag.ends_with(']') => {
+                        self.app_name = Some(tag[..open].to_string());
+                        self.proc_id = Some(tag[open + 1..tag.len() - 1].to_string());
+                    }
+                    _ => self.app_name = Some(tag.to_string()),
+                }
+                rest = &rest[colon + 2..];
+            }
+        }
+        self.msg = rest.to_string();
+    }
+}
+
+/// Encodes message with octet-counting framing (`<len> <msg>`).
+pub fn octet_counted(msg: &[u8]) -> Vec<u8> {
+    let mut buf = format!("{} ", msg.len()).into_bytes();
+    buf.extend_from_slice(msg);
+    buf
+}
+
+/// Split TCP stream data (for example written) with octet-counting framing to messages.
+pub fn split_octet_counted(mut data: &[u8]) -> io::Result<Vec<&[u8]>> {
+    let mut msgs = Vec::new();
+    while !data.is_empty() {
+        let space = data
+            .iter()
+            .position(|&c| c == b' ')
+            .ok_or_else(|| invalid("syslog: octet count truncated"))?;
+        let len: usize = std::str::from_utf8(&data[..space])
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(|| invalid("syslog: invalid octet count"))?;
+        let start = space + 1;
+        if data.len() < start + len {
+            return Err(invalid("syslog: message truncated"));
+        }
+        msgs.push(&data[start..start + len]);
+        data = &data[start + len..];
+    }
+    Ok(msgs)
+}
+
+/// Asserts, that data is a syslog message with facility, severity and text.
+pub fn assert_syslog(data: &[u8], facility: u8, severity: u8, msg: &str) -> Message {
+    let parsed = match Message::parse(data) {
+        Ok(parsed) => parsed,
+        Err(err) => panic!("invalid syslog message {:?}: {}", String::from_utf8_lossy(data), err),
+    };
+    assert_eq!(
+        (parsed.facility, parsed.severity, parsed.msg.as_str()),
+        (facility, severity, msg),
+        "syslog message mismatch"
+    );
+    parsed
+}
+
+fn nil(s: &str) -> Option<String> {
+    if s == "-" {
+        None
+    } else {
+        Some(s.to_string())
+    }
+}
+
+fn invalid(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
@@ -0,0 +1,434 @@
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use crate::stream::CheckedMockStreamBuilder;
+
+use super::{dns, graphite, memcached, postgres, statsd, syslog};
+
+#[cfg(feature = "kafka")]
+use super::kafka;
+
+#[test]
+fn dns_query_response() {
+    let query = dns::Query::new(0x1234, "example.com.", dns::RecordType::A);
+    let wire = query.to_bytes();
+    assert_eq!(
+        wire,
+        b"\x12\x34\x01\x00\x00\x01\x00\x00\x00\x00\x00\x00\x07example\x03com\x00\x00\x01\x00\x01"
+    );
+    assert_eq!(dns::Query::parse(&wire).unwrap(), query);
+
+    let response = query
+        .response()
+        .answer_data(300, dns::RData::A(Ipv4Addr::new(127, 0, 0, 1)))
+        .answer(dns::Record::new(
+            "example.com",
+            60,
+            dns::RData::Aaaa(Ipv6Addr::LOCALHOST),
+        ))
+        .to_bytes();
+    assert_eq!(&response[..12], b"\x12\x34\x81\x80\x00\x01\x00\x02\x00\x00\x00\x00");
+    assert_eq!(
+        &response[wire.len()..wire.len() + 27],
+        b"\x07example\x03com\x00\x00\x01\x00\x01\x00\x00\x01\x2c\x00\x04\x7f\x00\x00\x01"
+    );
+
+    let nx = dns::Response::nxdomain(1, "missing.test", dns::RecordType::Srv)
+        .truncated(true)
+        .to_bytes();
+    assert_eq!(&nx[2..8], b"\x83\x83\x00\x01\x00\x00");
+
+    let srv = dns::Response::new(2, "_http._tcp.test", dns::RecordType::Srv)
+        .answer_data(
+            10,
+            dns::RData::Srv {
+                priority: 1,
+                weight: 2,
+                port: 80,
+                target: "web.test".to_string(),
+            },
+        )
+        .to_bytes();
+    assert!(srv.ends_with(b"\x00\x10\x00\x01\x00\x02\x00\x50\x03web\x04test\x00"));
+
+    let txt = dns::Response::new(3, "test", dns::RecordType::Txt)
+        .answer_data(10, dns::RData::Txt(vec![b"v=1".to_vec()]))
+        .to_bytes();
+    assert!(txt.ends_with(b"\x00\x04\x03v=1"));
+
+    assert!(dns::Query::parse(&nx).is_err());
+    assert!(dns::Query::parse(&wire[..14]).is_err());
+}
+
+#[test]
+#[should_panic(expected = "dns label longer than 63 bytes")]
+fn dns_long_label() {
+    dns::Query::new(1, &format!("{}.test", "a".repeat(64)), dns::RecordType::A).to_bytes();
+}
+
+#[test]
+#[should_panic(expected = "dns TXT string longer than 255 bytes: 256 bytes")]
+fn dns_long_txt() {
+    dns::Response::new(1, "test", dns::RecordType::Txt)
+        .answer_data(10, dns::RData::Txt(vec![vec![b'x'; 256]]))
+        .to_bytes();
+}
+
+#[test]
+#[should_panic(expected = "dns message longer than 65535: 65536")]
+fn dns_long_tcp_frame() {
+    dns::tcp_frame(&vec![0; 65536]);
+}
+
+#[test]
+fn dns_tcp_stream() {
+    let query = dns::Query::new(7, "example.com", dns::RecordType::Aaaa);
+    let response = query
+        .response()
+        .answer_data(1, dns::RData::Aaaa(Ipv6Addr::LOCALHOST))
+        .to_bytes();
+    let mut stream = CheckedMockStreamBuilder::new()
+        .write(dns::tcp_frame(&query.to_bytes()))
+        .read(dns::tcp_frame(&response))
+        .build();
+
+    stream.write_all(&dns::tcp_frame(&query.to_bytes())).unwrap();
+    let frames = dns::split_tcp_frames(stream.written()).unwrap();
+    assert_eq!(frames.len(), 1);
+    assert_eq!(dns::Query::parse(frames[0]).unwrap(), query);
+
+    let mut buf = Vec::new();
+    stream.read_to_end(&mut buf).unwrap();
+    assert_eq!(dns::split_tcp_frames(&buf).unwrap(), vec![&response[..]]);
+    assert!(dns::split_tcp_frames(&buf[..buf.len() - 1]).is_err());
+}
+
+#[test]
+fn memcached_get_set() {
+    let mut stream = CheckedMockStreamBuilder::new()
+        .write(memcached::set("key", 1, 0, b"value"))
+        .read(memcached::STORED.to_vec())
+        .write(memcached::get(&["key", "missing"]))
+        .read(memcached::values(&[("key", 1, b"value")]))
+        .write(memcached::storage(memcached::Storage::Add, "key", 0, 60, b"", true))
+        .write(memcached::delete("key"))
+        .read(memcached::server_error("out of memory"))
+        .build();
+
+    stream.write_all(b"set key 1 0 5\r\nvalue\r\n").unwrap();
+    let mut buf = [0; 8];
+    stream.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"STORED\r\n");
+
+    stream.write_all(b"get key missing\r\n").unwrap();
+    let mut buf = vec![0; 27];
+    stream.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"VALUE key 1 5\r\nvalue\r\nEND\r\n");
+
+    stream.write_all(b"add key 0 60 0 noreply\r\n\r\n").unwrap();
+    stream.write_all(b"delete key\r\n").unwrap();
+    let mut buf = Vec::new();
+    stream.read_to_end(&mut buf).unwrap();
+    assert_eq!(&buf, b"SERVER_ERROR out of memory\r\n");
+
+    assert_eq!(memcached::gets(&["a"]), b"gets a\r\n");
+    assert_eq!(memcached::client_error("bad"), b"CLIENT_ERROR bad\r\n");
+}
+
+#[test]
+fn graphite_plaintext() {
+    let metrics = vec![
+        graphite::Metric::new("a.b", 1.5, 1700000000),
+        graphite::Metric::new("c", 2.0, 1700000001),
+    ];
+    let mut stream = CheckedMockStreamBuilder::new()
+        .write(graphite::plaintext(&metrics))
+        .build();
+    stream.write_all(b"a.b 1.5 1700000000\nc 2 1700000001\n").unwrap();
+    graphite::assert_plaintext(stream.written(), &metrics);
+
+    assert!(graphite::parse_plaintext(b"a.b 1.5\n").is_err());
+    assert!(graphite::parse_plaintext(b"a.b x 1700000000\n").is_err());
+}
+
+#[test]
+fn graphite_pickle() {
+    let metrics = vec![
+        graphite::Metric::new("a.b", 1.5, 1700000000),
+        graphite::Metric::new("c", 2.0, 1700000001),
+    ];
+    let encoded = graphite::pickle(&metrics);
+    assert_eq!(&encoded[..4], &((encoded.len() - 4) as u32).to_be_bytes());
+    graphite::assert_pickle(&encoded, &metrics);
+
+    // python: pickle.dumps([('a.b', (1700000000, 1.5)), ('c', (1700000001, 2))], protocol=N)
+    let payloads: [&[u8]; 3] = [
+        b"(lp0\n(Va.b\np1\n(I1700000000\nF1.5\ntp2\ntp3\na(Vc\np4\n(I1700000001\nI2\ntp5\ntp6\na.",
+        b"\x80\x02]q\x00(X\x03\x00\x00\x00a.bq\x01J\x00\xf1SeG?\xf8\x00\x00\x00\x00\x00\x00\x86q\x02\x86q\x03X\x01\x00\x00\x00cq\x04J\x01\xf1SeK\x02\x86q\x05\x86q\x06e.",
+        b"\x80\x04\x95,\x00\x00\x00\x00\x00\x00\x00]\x94(\x8c\x03a.b\x94J\x00\xf1SeG?\xf8\x00\x00\x00\x00\x00\x00\x86\x94\x86\x94\x8c\x01c\x94J\x01\xf1SeK\x02\x86\x94\x86\x94e.",
+    ];
+    let mut written = Vec::new();
+    for payload in payloads.iter() {
+        written.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        written.extend_from_slice(payload);
+    }
+    let mut stream = CheckedMockStreamBuilder::new().write(written.clone()).build();
+    stream.write_all(&written).unwrap();
+    let parsed = graphite::parse_pickle(stream.written()).unwrap();
+    assert_eq!(parsed.len(), 6);
+    for chunk in parsed.chunks(2) {
+        assert_eq!(chunk, &metrics[..]);
+    }
+
+    assert!(graphite::parse_pickle(&encoded[..encoded.len() - 1]).is_err());
+    assert!(graphite::parse_pickle(b"\x00\x00\x00\x02N.").is_err());
+    // malformed length of BINUNICODE8 is an error, not overflow
+    let frame = b"\x00\x00\x00\x09\x8d\xff\xff\xff\xff\xff\xff\xff\xff";
+    assert!(graphite::parse_pickle(frame).is_err());
+    assert!(graphite::parse_pickle(b"\xff\xff\xff\xff").is_err());
+}
+
+#[test]
+fn statsd_metrics() {
+    use statsd::Value::*;
+
+    let packet = b"requests:1|c|@0.5\nlatency:12.5|ms|#env:test,host:a\nusers:bob|s\nload:-2|g\n";
+    statsd::assert_statsd(packet, "requests", Counter(1.0), Some(0.5));
+    statsd::assert_statsd(packet, "latency", Timer(12.5), None);
+    statsd::assert_statsd(packet, "users", Set("bob".to_string()), None);
+    statsd::assert_statsd(packet, "load", GaugeDelta(-2.0), None);
+
+    let metrics = statsd::parse(packet).unwrap();
+    assert_eq!(metrics.len(), 4);
+    assert_eq!(metrics[1].tags, vec!["env:test", "host:a"]);
+
+    let metric = statsd::Metric::new("requests", Counter(1.0))
+        .sample_rate(0.5)
+        .tag("env:test");
+    assert_eq!(metric.to_bytes(), b"requests:1|c|@0.5|#env:test");
+    assert_eq!(statsd::Value::GaugeDelta(3.0).to_string(), "+3|g");
+
+    // negative gauge is set from zero and survives round trip
+    let gauge = statsd::Metric::new("load", Gauge(-5.0)).tag("env:test");
+    assert_eq!(gauge.to_bytes(), b"load:0|g|#env:test\nload:-5|g|#env:test");
+    assert_eq!(statsd::parse(&gauge.to_bytes()).unwrap(), vec![gauge]);
+    let gauge = statsd::Metric::new("load", Gauge(5.0));
+    assert_eq!(statsd::parse(&gauge.to_bytes()).unwrap(), vec![gauge]);
+
+    let mut stream = CheckedMockStreamBuilder::new()
+        .write(metric.to_bytes())
+        .build();
+    stream.write_all(b"requests:1|c|@0.5|#env:test").unwrap();
+    assert_eq!(statsd::parse(stream.written()).unwrap(), vec![metric]);
+
+    assert!(statsd::parse(b"requests|c").is_err());
+    assert!(statsd::parse(b"requests:x|c").is_err());
+    assert!(statsd::parse(b"requests:1|x").is_err());
+}
+
+#[test]
+#[should_panic(expected = "statsd metric requests:2|c")]
+fn statsd_metrics_mismatch() {
+    statsd::assert_statsd(b"requests:1|c", "requests", statsd::Value::Counter(2.0), None);
+}
+
+#[test]
+fn syslog_messages() {
+    let msg = syslog::assert_syslog(
+        b"<165>1 2003-10-11T22:14:15.003Z host.example.com evntslog 42 ID47 [exampleSDID@32473 iut=\"3\" eventSource=\"App\\\"x\\\"\"][meta seq=\"1\"] \xef\xbb\xbfAn application event\n",
+        20,
+        5,
+        "An application event",
+    );
+    assert_eq!(msg.version, Some(1));
+    assert_eq!(msg.timestamp.as_deref(), Some("2003-10-11T22:14:15.003Z"));
+    assert_eq!(msg.hostname.as_deref(), Some("host.example.com"));
+    assert_eq!(msg.app_name.as_deref(), Some("evntslog"));
+    assert_eq!(msg.proc_id.as_deref(), Some("42"));
+    assert_eq!(msg.msg_id.as_deref(), Some("ID47"));
+    assert_eq!(msg.structured_data.len(), 2);
+    let sd = msg.sd("exampleSDID@32473").unwrap();
+    assert_eq!(sd.param("iut"), Some("3"));
+    assert_eq!(sd.param("eventSource"), Some("App\"x\""));
+    assert_eq!(msg.sd("meta").unwrap().param("seq"), Some("1"));
+
+    let msg = syslog::assert_syslog(b"<34>1 - - app - - - started", 4, 2, "started");
+    assert_eq!(msg.hostname, None);
+    assert!(msg.structured_data.is_empty());
+
+    let msg = syslog::assert_syslog(
+        b"<34>Oct 11 22:14:15 mymachine su[123]: 'su root' failed",
+        4,
+        2,
+        "'su root' failed",
+    );
+    assert_eq!(msg.version, None);
+    assert_eq!(msg.timestamp.as_deref(), Some("Oct 11 22:14:15"));
+    assert_eq!(msg.hostname.as_deref(), Some("mymachine"));
+    assert_eq!(msg.app_name.as_deref(), Some("su"));
+    assert_eq!(msg.proc_id.as_deref(), Some("123"));
+
+    assert!(syslog::Message::parse(b"no pri").is_err());
+    assert!(syslog::Message::parse(b"<999>1 - - - - - -").is_err());
+    assert!(syslog::Message::parse(b"<1>1 - -").is_err());
+}
+
+#[test]
+fn syslog_octet_counted() {
+    let first = b"<13>1 - host app - - - first".to_vec();
+    let second = b"<14>Oct  1 00:00:00 host app: second".to_vec();
+    let mut expected = syslog::octet_counted(&first);
+    expected.extend(syslog::octet_counted(&second));
+    assert!(expected.starts_with(b"28 <13>1"));
+
+    let mut stream = CheckedMockStreamBuilder::new().write(expected.clone()).build();
+    stream.write_all(&expected).unwrap();
+    let msgs = syslog::split_octet_counted(stream.written()).unwrap();
+    assert_eq!(msgs, vec![&first[..], &second[..]]);
+    syslog::assert_syslog(msgs[0], 1, 5, "first");
+    syslog::assert_syslog(msgs[1], 1, 6, "second");
+
+    assert!(syslog::split_octet_counted(&expected[..expected.len() - 1]).is_err());
+    assert!(syslog::split_octet_counted(b"x <13>").is_err());
+}
+
+#[cfg(feature = "kafka")]
+#[test]
+fn kafka_exchange() {
+    let header = kafka::RequestHeader::new(kafka::API_VERSIONS, 0, 1, "client");
+    let request = kafka::request(&header, b"");
+    assert_eq!(
+        request,
+        b"\x00\x00\x00\x10\x00\x12\x00\x00\x00\x00\x00\x01\x00\x06client"
+    );
+    let body = kafka::Encoder::new()
+        .i16(0)
+        .array_len(1)
+        .i16(kafka::METADATA)
+        .i16(0)
+        .i16(12)
+        .finish();
+    let response = kafka::response(1, &body);
+    assert_eq!(&response[..8], b"\x00\x00\x00\x10\x00\x00\x00\x01");
+
+    let mut stream = CheckedMockStreamBuilder::new()
+        .write(request.clone())
+        .read(response)
+        .build();
+    stream.write_all(&request).unwrap();
+    let frames = kafka::split_frames(stream.written()).unwrap();
+    let (parsed, req_body) = kafka::parse_request(frames[0]).unwrap();
+    assert_eq!(parsed, header);
+    assert!(req_body.is_empty());
+
+    let mut buf = Vec::new();
+    stream.read_to_end(&mut buf).unwrap();
+    let frames = kafka::split_frames(&buf).unwrap();
+    assert_eq!(frames.len(), 1);
+    assert_eq!(&frames[0][..4], b"\x00\x00\x00\x01");
+    assert_eq!(&frames[0][4..], &body[..]);
+
+    let flexible = kafka::RequestHeader::new(kafka::API_VERSIONS, 3, 2, "c").flexible(true);
+    let request = kafka::request(
+        &flexible,
+        &kafka::Encoder::new()
+            .compact_string("netmock")
+            .compact_string("1.0")
+            .empty_tagged_fields()
+            .finish(),
+    );
+    let (parsed, req_body) = kafka::parse_request(&request[4..]).unwrap();
+    assert_eq!(parsed.correlation_id, 2);
+    assert_eq!(req_body, b"\x00\x08netmock\x041.0\x00");
+    assert_eq!(&kafka::response_flexible(2, b"")[4..], b"\x00\x00\x00\x02\x00");
+    assert_eq!(kafka::Encoder::new().unsigned_varint(300).finish(), b"\xac\x02");
+
+    assert!(kafka::split_frames(&request[..request.len() - 1]).is_err());
+    assert!(kafka::parse_request(b"\x00\x12").is_err());
+}
+
+#[test]
+fn postgres_startup_query() {
+    let startup = postgres::startup(&[("user", "postgres"), ("database", "test")]);
+    assert_eq!(
+        startup,
+        b"\x00\x00\x00\x25\x00\x03\x00\x00user\x00postgres\x00database\x00test\x00\x00"
+    );
+    let mut stream = CheckedMockStreamBuilder::new()
+        .write(postgres::ssl_request())
+        .read(postgres::ssl_response(false))
+        .write(startup.clone())
+        .read(postgres::auth_md5([1, 2, 3, 4]))
+        .write(postgres::password("md53175bce1d3201d16594cebf9d7eb3f9d"))
+        .read(postgres::startup_ok(&[("server_version", "16.0")]))
+        .write(postgres::query("SELECT 1"))
+        .read(postgres::simple_query_response(
+            &["?column?"],
+            &[&[Some("1")]],
+        ))
+        .write(postgres::query("SELECT x"))
+        .read(postgres::error_response(
+            "ERROR",
+            "42703",
+            "column \"x\" does not exist",
+        ))
+        .read(postgres::ready_for_query(postgres::IDLE))
+        .write(postgres::terminate())
+        .build();
+
+    stream.write_all(&postgres::ssl_request()).unwrap();
+    let mut buf = [0; 1];
+    stream.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"N");
+
+    stream.write_all(&startup).unwrap();
+    let (version, params) = postgres::parse_startup(&stream.written()[8..]).unwrap();
+    assert_eq!(version, postgres::PROTOCOL_VERSION);
+    assert_eq!(params[0], ("user".to_string(), "postgres".to_string()));
+    let mut buf = [0; 13];
+    stream.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"R\x00\x00\x00\x0c\x00\x00\x00\x05\x01\x02\x03\x04");
+
+    stream
+        .write_all(&postgres::password("md53175bce1d3201d16594cebf9d7eb3f9d"))
+        .unwrap();
+    let mut buf = vec![0; postgres::startup_ok(&[("server_version", "16.0")]).len()];
+    stream.read_exact(&mut buf).unwrap();
+    let msgs = postgres::split_messages(&buf).unwrap();
+    let tags: Vec<u8> = msgs.iter().map(|(tag, _)| *tag).collect();
+    assert_eq!(tags, b"RSKZ");
+    assert_eq!(msgs[1].1, b"server_version\x0016.0\x00");
+
+    let mut tags = Vec::new();
+    for sql in ["SELECT 1", "SELECT x"].iter() {
+        stream.write_all(&postgres::query(sql)).unwrap();
+        let mut header = [0; 5];
+        loop {
+            stream.read_exact(&mut header).unwrap();
+            let len = u32::from_be_bytes([header[1], header[2], header[3], header[4]]);
+            let mut body = vec![0; len as usize - 4];
+            stream.read_exact(&mut body).unwrap();
+            tags.push(header[0]);
+            if header[0] == b'Z' {
+                break;
+            }
+        }
+    }
+    assert_eq!(tags, b"TDCZEZ");
+    stream.write_all(&postgres::terminate()).unwrap();
+
+    let written = postgres::split_messages(&stream.written()[startup.len() + 8..]).unwrap();
+    assert_eq!(written[1], (b'Q', &b"SELECT 1\x00"[..]));
+    assert_eq!(written[3], (b'X', &b""[..]));
+
+    assert_eq!(
+        postgres::auth_sasl(&["SCRAM-SHA-256"]),
+        b"R\x00\x00\x00\x17\x00\x00\x00\x0aSCRAM-SHA-256\x00\x00"
+    );
+    assert_eq!(
+        postgres::sasl_initial_response("SCRAM-SHA-256", b"n,,n=,r=x"),
+        b"p\x00\x00\x00\x1fSCRAM-SHA-256\x00\x00\x00\x00\x09n,,n=,r=x"
+    );
+    assert!(postgres::split_messages(b"Z\x00\x00\x00\x05").is_err());
+    assert!(postgres::parse_startup(&startup[..10]).is_err());
+}
@@ -0,0 +1,107 @@
+//! `proptest` [`Arbitrary`] implementation for [`CheckedMockStreamBuilder`].
+//!
+//! Generated scenarios are random interleavings of reads, writes, waits and errors, bounded by
+//! [`ScenarioParams`].
+
+use std::io::{Error, ErrorKind};
+use std::sync::Arc;
+use std::time::Duration;
+
+use proptest::arbitrary::Arbitrary;
+use proptest::collection::vec;
+use proptest::sample::select;
+use proptest::strategy::{BoxedStrategy, Strategy, Union};
+
+use super::error::ScriptedError;
+use super::{Action, CheckedMockStreamBuilder};
+
+// error kinds, returned by generated scenarios
+const ERROR_KINDS: &[ErrorKind] = &[
+    ErrorKind::ConnectionReset,
+    ErrorKind::ConnectionAborted,
+    ErrorKind::BrokenPipe,
+    ErrorKind::TimedOut,
+    ErrorKind::UnexpectedEof,
+    ErrorKind::Other,
+];
+
+/// Bounds for generated scenarios.
+#[derive(Debug, Clone)]
+pub struct ScenarioParams {
+    /// Maximum actions count.
+    pub max_actions: usize,
+    /// Maximum read/write payload length (payloads are not empty).
+    pub max_payload: usize,
+    /// Maximum wait duration (waits are not generated if zero).
+    pub max_wait: Duration,
+    /// Weight of read actions.
+    pub read_weight: u32,
+    /// Weight of write actions.
+    pub write_weight: u32,
+    /// Weight of read/write errors (errors are not generated if zero).
+    pub error_weight: u32,
+}
+
+impl Default for ScenarioParams {
+    fn default() -> Self {
+        ScenarioParams {
+            max_actions: 16,
+            max_payload: 64,
+            max_wait: Duration::from_millis(10),
+            read_weight: 4,
+            write_weight: 4,
+            error_weight: 1,
+        }
+    }
+}
+
+impl Arbitrary for CheckedMockStreamBuilder {
+    type Parameters = ScenarioParams;
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(params: Self::Parameters) -> Self::Strategy {
+        let payload = || vec(proptest::num::u8::ANY, 1..=params.max_payload.max(1));
+        let error =
+            || select(ERROR_KINDS).prop_map(|kind| Arc::new(ScriptedError::new(Error::from(kind))));
+
+        let mut arms: Vec<(u32, BoxedStrategy<Action>)> = vec![
+            (
+                params.read_weight,
+                payload().prop_map(|data| Action::Read(data.into())).boxed(),
+            ),
+            (
+                params.write_weight,
+                payload()
+                    .prop_map(|data| Action::Write(data.into()))
+                    .boxed(),
+            ),
+        ];
+        if params.error_weight > 0 {
+            arms.push((
+                params.error_weight,
+                error().prop_map(Action::ReadError).boxed(),
+            ));
+            arms.push((
+                params.error_weight,
+                error().prop_map(Action::WriteError).boxed(),
+            ));
+        }
+        if !params.max_wait.is_zero() {
+            let max = params.max_wait.as_millis() as u64;
+            arms.push((
+                1,
+                (0..=max)
+                    .prop_map(|ms| Action::Wait(Duration::from_millis(ms)))
+                    .boxed(),
+            ));
+        }
+        let arms: Vec<_> = arms.into_iter().filter(|(w, _)| *w > 0).collect();
+
+        vec(Union::new_weighted(arms), 0..=params.max_actions)
+            .prop_map(|actions| CheckedMockStreamBuilder {
+                actions: actions.into(),
+                ..Default::default()
+            })
+            .boxed()
+    }
+}
@@ -0,0 +1,249 @@
+//! Assertions of written data with readable failure output (escaped context and hexdump).
+//!
+//! Written data can be checked as a whole, by contained pattern or as text lines
+//! (see [`CheckedMockStream::expect_lines`]), regardless of write call boundaries.
+//!
+//! ```
+//! use netmock::stream::CheckedMockStreamBuilder;
+//! use std::io::Write;
+//!
+//! let mut stream = CheckedMockStreamBuilder::new().write(b"PING\r\n").build();
+//! stream.write_all(b"PING\r\n").unwrap();
+//! netmock::assert_written!(stream, b"PING\r\n");
+//! netmock::assert_written_contains!(stream, "PING");
+//! ```
+
+use std::fmt;
+
+use super::diff::mismatch_offset;
+use super::dump::{hexdump, preview};
+use super::util::escape;
+use super::{CheckedMockStream, SimpleMockStream};
+
+// maximum length of mismatch context
+const CONTEXT_LEN: usize = 32;
+
+// rows of hexdump before mismatch row
+const HEXDUMP_BEFORE: usize = 2;
+
+// rows of hexdump in failure output
+const HEXDUMP_ROWS: usize = 5;
+
+// maximum length of written data hexdump in failure output of contains assertion
+const HEXDUMP_LEN: usize = 256;
+
+fn prefix(msg: Option<fmt::Arguments<'_>>) -> String {
+    match msg {
+        Some(msg) => format!("{}: ", msg),
+        None => String::new(),
+    }
+}
+
+// hexdump rows around offset
+fn hexdump_around(data: &[u8], offset: usize) -> String {
+    let start = (offset / 16).saturating_sub(HEXDUMP_BEFORE) * 16;
+    let start = start.min(data.len());
+    let end = data.len().min(start + HEXDUMP_ROWS * 16);
+    hexdump(&data[start..end], start)
+}
+
+#[doc(hidden)]
+#[track_caller]
+pub fn assert_written_eq(written: &[u8], expected: &[u8], msg: Option<fmt::Arguments<'_>>) {
+    if written == expected {
+        return;
+    }
+    let offset = mismatch_offset(expected, written);
+    let context = |data: &[u8]| {
+        let end = data.len().min(offset + CONTEXT_LEN);
+        escape(&data[offset.min(end)..end])
+    };
+    let text = format!(
+        "{}written data mismatch at offset {} (written {} bytes, expected {} bytes)\n  written:  \"{}\"\n  expected: \"{}\"\nwritten:\n{}expected:\n{}",
+        prefix(msg),
+        offset,
+        written.len(),
+        expected.len(),
+        context(written),
+        context(expected),
+        hexdump_around(written, offset),
+        hexdump_around(expected, offset),
+    );
+    #[cfg(feature = "pretty")]
+    let text = text + &super::pretty::diff(expected, written, offset);
+    panic!("{}", text);
+}
+
+#[doc(hidden)]
+#[track_caller]
+pub fn assert_written_contains(written: &[u8], pattern: &[u8], msg: Option<fmt::Arguments<'_>>) {
+    if pattern.is_empty() || written.windows(pattern.len()).any(|w| w == pattern) {
+        return;
+    }
+    let truncated = if written.len() > HEXDUMP_LEN {
+        "...\n"
+    } else {
+        ""
+    };
+    panic!(
+        "{}written data ({} bytes) does not contain {}\nwritten:\n{}{}",
+        prefix(msg),
+        written.len(),
+        preview(pattern),
+        hexdump(&written[..written.len().min(HEXDUMP_LEN)], 0),
+        truncated,
+    );
+}
+
+// split written data to lines (terminated by `\n` or `\r\n`)
+fn split_lines(written: &[u8]) -> Vec<&[u8]> {
+    let written = written.strip_suffix(b"\n").unwrap_or(written);
+    if written.is_empty() {
+        return Vec::new();
+    }
+    written
+        .split(|&b| b == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .collect()
+}
+
+#[track_caller]
+fn assert_lines(written: &[u8], expected: &[&[u8]]) {
+    let lines = split_lines(written);
+    if lines == expected {
+        return;
+    }
+    let n = lines
+        .iter()
+        .zip(expected)
+        .take_while(|(line, expected)| line == expected)
+        .count();
+    let reason = match (lines.get(n), expected.get(n)) {
+        (Some(line), Some(expected)) => format!(
+            "written line {} mismatch: expected \"{}\", written \"{}\"",
+            n + 1,
+            escape(expected),
+            escape(line)
+        ),
+        (None, Some(expected)) => format!(
+            "written {} lines, expected {}: missing line {} \"{}\"",
+            lines.len(),
+            expected.len(),
+            n + 1,
+            escape(expected)
+        ),
+        (Some(line), None) => format!(
+            "written {} lines, expected {}: unexpected line {} \"{}\"",
+            lines.len(),
+            expected.len(),
+            n + 1,
+            escape(line)
+        ),
+        (None, None) => unreachable!(),
+    };
+    let text: Vec<String> = lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| format!("  {:>4} \"{}\"", i + 1, escape(line)))
+        .collect();
+    panic!("{}\nwritten lines:\n{}", reason, text.join("\n"));
+}
+
+impl SimpleMockStream {
+    /// Assert written data splits into exactly these lines (terminated by `\n` or `\r\n`),
+    /// regardless of how many writes produced them.
+    ///
+    /// ```
+    /// use netmock::stream::SimpleMockStream;
+    /// use std::io::Write;
+    ///
+    /// let mut stream = SimpleMockStream::empty();
+    /// stream.write_all(b"HELO a.b\r\nMAIL").unwrap();
+    /// stream.write_all(b" FROM:<a@b>\r\n").unwrap();
+    /// stream.expect_lines(["HELO a.b", "MAIL FROM:<a@b>"]);
+    /// ```
+    #[track_caller]
+    pub fn expect_lines<I, S>(&self, lines: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let expected: Vec<S> = lines.into_iter().collect();
+        let expected: Vec<&[u8]> = expected.iter().map(|s| s.as_ref().as_bytes()).collect();
+        assert_lines(self.written(), &expected);
+    }
+}
+
+impl CheckedMockStream {
+    /// Assert written data splits into exactly these lines (terminated by `\n` or `\r\n`),
+    /// regardless of how many writes produced them.
+    #[track_caller]
+    pub fn expect_lines<I, S>(&self, lines: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let expected: Vec<S> = lines.into_iter().collect();
+        let expected: Vec<&[u8]> = expected.iter().map(|s| s.as_ref().as_bytes()).collect();
+        assert_lines(self.written(), &expected);
+    }
+}
+
+/// Assert written data of mock stream is equal to expected bytes or string.
+///
+/// On failure the offset of the first mismatched byte, escaped context and hexdump are printed.
+///
+/// ```
+/// use netmock::stream::SimpleMockStream;
+/// use std::io::Write;
+///
+/// let mut stream = SimpleMockStream::empty();
+/// stream.write_all(b"PING\r\n").unwrap();
+/// netmock::assert_written!(stream, b"PING\r\n");
+/// netmock::assert_written!(stream, "PING\r\n", "after {} requests", 1);
+/// ```
+#[macro_export]
+macro_rules! assert_written {
+    ($stream:expr, $expected:expr $(,)?) => {
+        $crate::__private::assert_written_eq(
+            ::std::convert::AsRef::<[u8]>::as_ref(&$stream.written()),
+            ::std::convert::AsRef::<[u8]>::as_ref(&$expected),
+            ::std::option::Option::None,
+        )
+    };
+    ($stream:expr, $expected:expr, $($arg:tt)+) => {
+        $crate::__private::assert_written_eq(
+            ::std::convert::AsRef::<[u8]>::as_ref(&$stream.written()),
+            ::std::convert::AsRef::<[u8]>::as_ref(&$expected),
+            ::std::option::Option::Some(::std::format_args!($($arg)+)),
+        )
+    };
+}
+
+/// Assert written data of mock stream contains bytes or string.
+///
+/// ```
+/// use netmock::stream::SimpleMockStream;
+/// use std::io::Write;
+///
+/// let mut stream = SimpleMockStream::empty();
+/// stream.write_all(b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n").unwrap();
+/// netmock::assert_written_contains!(stream, "Host: example.com\r\n");
+/// ```
+#[macro_export]
+macro_rules! assert_written_contains {
+    ($stream:expr, $pattern:expr $(,)?) => {
+        $crate::__private::assert_written_contains(
+            ::std::convert::AsRef::<[u8]>::as_ref(&$stream.written()),
+            ::std::convert::AsRef::<[u8]>::as_ref(&$pattern),
+            ::std::option::Option::None,
+        )
+    };
+    ($stream:expr, $pattern:expr, $($arg:tt)+) => {
+        $crate::__private::assert_written_contains(
+            ::std::convert::AsRef::<[u8]>::as_ref(&$stream.written()),
+            ::std::convert::AsRef::<[u8]>::as_ref(&$pattern),
+            ::std::option::Option::Some(::std::format_args!($($arg)+)),
+        )
+    };
+}
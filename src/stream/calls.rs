@@ -0,0 +1,239 @@
+//! Call-count expectations of [`CheckedMockStream`] operations.
+//!
+//! Counts of read, write and flush calls are verified by [`CheckedMockStream::diff`] and
+//! [`CheckedMockStream::assert_done`], or on drop of the stream, if not verified before, so tests can
+//! assert, that the code under test doesn't make pathological numbers of tiny I/O calls. Only completed
+//! operations are counted: a wait returned as `WouldBlock` error isn't a call and async operation is
+//! counted once, not every poll.
+//!
+//! ```
+//! use netmock::stream::{at_most, exactly, CheckedMockStreamBuilder};
+//! use std::io::Write;
+//!
+//! let mut stream = CheckedMockStreamBuilder::new()
+//!     .write(b"PING\r\n")
+//!     .expect_write_calls(at_most(1))
+//!     .expect_flush_calls(exactly(1))
+//!     .build();
+//! stream.write_all(b"PING\r\n").unwrap();
+//! stream.flush().unwrap();
+//! stream.assert_done();
+//! ```
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use super::{CheckedMockStream, CheckedMockStreamBuilder, Divergence, Step};
+
+/// An expected count of operation calls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CallCount {
+    min: usize,
+    max: Option<usize>,
+}
+
+impl CallCount {
+    /// Check if count of calls is expected.
+    pub fn contains(&self, calls: usize) -> bool {
+        calls >= self.min && self.max.map_or(true, |max| calls <= max)
+    }
+}
+
+/// Exactly `n` calls.
+impl From<usize> for CallCount {
+    fn from(n: usize) -> Self {
+        exactly(n)
+    }
+}
+
+impl fmt::Display for CallCount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.min, self.max) {
+            (min, Some(max)) if min == max => write!(f, "exactly {}", min),
+            (0, Some(max)) => write!(f, "at most {}", max),
+            (min, Some(max)) => write!(f, "between {} and {}", min, max),
+            (min, None) => write!(f, "at least {}", min),
+        }
+    }
+}
+
+/// Expect exactly `n` calls.
+pub fn exactly(n: usize) -> CallCount {
+    CallCount {
+        min: n,
+        max: Some(n),
+    }
+}
+
+/// Expect at most `n` calls.
+pub fn at_most(n: usize) -> CallCount {
+    CallCount {
+        min: 0,
+        max: Some(n),
+    }
+}
+
+/// Expect at least `n` calls.
+pub fn at_least(n: usize) -> CallCount {
+    CallCount { min: n, max: None }
+}
+
+/// Expect from `min` to `max` calls (inclusive).
+pub fn between(min: usize, max: usize) -> CallCount {
+    CallCount {
+        min,
+        max: Some(max),
+    }
+}
+
+// expectations of read, write and flush calls
+#[derive(Debug, Clone, Copy, Default)]
+pub(super) struct CallExpectations {
+    read: Option<CallCount>,
+    write: Option<CallCount>,
+    flush: Option<CallCount>,
+}
+
+impl CallExpectations {
+    pub(super) fn is_empty(&self) -> bool {
+        self.read.is_none() && self.write.is_none() && self.flush.is_none()
+    }
+}
+
+// counted operation
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Call {
+    Read,
+    Write,
+    Flush,
+}
+
+// counts of calls
+#[derive(Debug, Default)]
+pub(super) struct Calls {
+    expected: CallExpectations,
+    pub(super) read: usize,
+    pub(super) write: usize,
+    pub(super) flush: usize,
+    // expectations are verified by diff (not checked on drop)
+    verified: AtomicBool,
+}
+
+impl Calls {
+    pub(super) fn new(expected: CallExpectations) -> Self {
+        Calls {
+            expected,
+            ..Default::default()
+        }
+    }
+
+    // count a call, if its step is completed (sync and async operations)
+    pub(super) fn count<T>(&mut self, call: Call, step: &Step<T>) {
+        if let Step::Ready(_) = step {
+            match call {
+                Call::Read => self.read += 1,
+                Call::Write => self.write += 1,
+                Call::Flush => self.flush += 1,
+            }
+        }
+    }
+
+    pub(super) fn reset(&mut self) {
+        *self = Calls::new(self.expected);
+    }
+
+    // drop expectations without check (stream is replaced)
+    pub(super) fn dismiss(&self) {
+        self.verified.store(true, Ordering::Relaxed);
+    }
+
+    // divergences of counts from expectations
+    pub(super) fn check(&self) -> Vec<Divergence> {
+        self.verified.store(true, Ordering::Relaxed);
+        let counts = [
+            ("read", self.read, self.expected.read),
+            ("write", self.write, self.expected.write),
+            ("flush", self.flush, self.expected.flush),
+        ];
+        counts
+            .iter()
+            .filter_map(|&(operation, calls, expected)| match expected {
+                Some(expected) if !expected.contains(calls) => Some(Divergence::Calls {
+                    operation,
+                    calls,
+                    expected,
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl CheckedMockStreamBuilder {
+    /// Expect count of read calls (`n` or [`exactly`], [`at_most`], [`at_least`], [`between`]).
+    ///
+    /// # Panics
+    ///
+    /// The stream panics on drop, if expectation is not met and is not verified before.
+    pub fn expect_read_calls<C: Into<CallCount>>(mut self, calls: C) -> Self {
+        self.calls.read = Some(calls.into());
+        self
+    }
+
+    /// Expect count of write calls (`n` or [`exactly`], [`at_most`], [`at_least`], [`between`]).
+    ///
+    /// # Panics
+    ///
+    /// The stream panics on drop, if expectation is not met and is not verified before.
+    pub fn expect_write_calls<C: Into<CallCount>>(mut self, calls: C) -> Self {
+        self.calls.write = Some(calls.into());
+        self
+    }
+
+    /// Expect count of flush calls (`n` or [`exactly`], [`at_most`], [`at_least`], [`between`]).
+    /// Flush implied by shutdown is not counted.
+    ///
+    /// # Panics
+    ///
+    /// The stream panics on drop, if expectation is not met and is not verified before.
+    pub fn expect_flush_calls<C: Into<CallCount>>(mut self, calls: C) -> Self {
+        self.calls.flush = Some(calls.into());
+        self
+    }
+}
+
+impl CheckedMockStream {
+    /// Gets counts of read, write and flush calls.
+    pub fn calls(&self) -> (usize, usize, usize) {
+        (self.calls.read, self.calls.write, self.calls.flush)
+    }
+
+    // step of operation call (counted, if completed)
+    pub(super) fn call_step<T, F: FnOnce(&mut Self) -> Step<T>>(
+        &mut self,
+        call: Call,
+        step: F,
+    ) -> Step<T> {
+        let step = step(self);
+        self.calls.count(call, &step);
+        step
+    }
+}
+
+/// Call-count expectations are checked on drop, if not verified by [`CheckedMockStream::diff`]
+/// or [`CheckedMockStream::assert_done`] (and the thread is not panicking).
+impl Drop for CheckedMockStream {
+    fn drop(&mut self) {
+        if self.calls.expected.is_empty()
+            || self.calls.verified.load(Ordering::Relaxed)
+            || std::thread::panicking()
+        {
+            return;
+        }
+        let diff = self.calls.check();
+        if !diff.is_empty() {
+            let text: Vec<String> = diff.iter().map(|d| format!("  {}", d)).collect();
+            panic!("call count expectations failed:\n{}", text.join("\n"));
+        }
+    }
+}
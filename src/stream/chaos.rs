@@ -0,0 +1,218 @@
+//! Seeded random fault injection (chaos mode) for [`CheckedMockStream`].
+//!
+//! Injected faults do not consume scenario actions, so a resilient client (which retry failed
+//! operations and handle short reads) must still complete the scenario.
+//! Injected error messages contain the seed, set `NETMOCK_CHAOS_SEED` environment variable
+//! (see [`Chaos::from_env`]) to reproduce the run.
+
+use std::io::{Error, ErrorKind};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[cfg(feature = "tokio")]
+use std::task;
+
+use super::{CheckedMockStream, CheckedMockStreamBuilder, Step};
+
+/// Environment variable with chaos mode seed.
+pub const CHAOS_SEED_ENV: &str = "NETMOCK_CHAOS_SEED";
+
+/// Chaos mode configuration (probabilities are in range `0.0..=1.0`).
+#[derive(Debug, Clone)]
+pub struct Chaos {
+    seed: u64,
+    error: f64,
+    error_kind: ErrorKind,
+    short_read: f64,
+    delay: f64,
+    max_delay: Duration,
+    pending: f64,
+}
+
+impl Chaos {
+    /// Create chaos mode configuration with seed (no faults are enabled).
+    pub fn new(seed: u64) -> Self {
+        Chaos {
+            seed,
+            error: 0.0,
+            error_kind: ErrorKind::Interrupted,
+            short_read: 0.0,
+            delay: 0.0,
+            max_delay: Duration::ZERO,
+            pending: 0.0,
+        }
+    }
+
+    /// Create chaos mode configuration with seed from `NETMOCK_CHAOS_SEED` environment variable
+    /// (or random seed, if not set).
+    pub fn from_env() -> Self {
+        let seed = std::env::var(CHAOS_SEED_ENV)
+            .ok()
+            .and_then(|s| s.trim().parse().ok())
+            .unwrap_or_else(|| {
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |d| d.as_nanos() as u64)
+            });
+        Chaos::new(seed)
+    }
+
+    /// Inject errors of `kind` before read/write with probability.
+    pub fn errors(mut self, probability: f64, kind: ErrorKind) -> Self {
+        self.error = probability;
+        self.error_kind = kind;
+        self
+    }
+
+    /// Return partial data on read with probability.
+    pub fn short_reads(mut self, probability: f64) -> Self {
+        self.short_read = probability;
+        self
+    }
+
+    /// Delay read/write (up to `max`) with probability.
+    pub fn delays(mut self, probability: f64, max: Duration) -> Self {
+        self.delay = probability;
+        self.max_delay = max;
+        self
+    }
+
+    /// Return spurious `Poll::Pending` (with immediate wake) before async read/write with probability.
+    ///
+    /// Stress-test handling of spurious wakeups by async code, sync operations are not affected.
+    pub fn spurious_pending(mut self, probability: f64) -> Self {
+        self.pending = probability;
+        self
+    }
+
+    /// Get seed.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl CheckedMockStreamBuilder {
+    /// Enable chaos mode (seeded random fault injection) for built stream.
+    pub fn chaos(mut self, chaos: Chaos) -> Self {
+        self.chaos = Some(chaos);
+        self
+    }
+}
+
+// chaos mode state of stream
+#[derive(Debug)]
+pub(super) struct ChaosState {
+    config: Chaos,
+    rng: u64,
+    delayed: bool,
+    // spurious pending is returned for read/write, next poll must proceed
+    #[cfg(feature = "tokio")]
+    yielded: [bool; 2],
+}
+
+impl ChaosState {
+    pub(super) fn new(config: Chaos) -> Self {
+        ChaosState {
+            rng: config.seed,
+            config,
+            delayed: false,
+            #[cfg(feature = "tokio")]
+            yielded: [false; 2],
+        }
+    }
+
+    // splitmix64
+    fn next(&mut self) -> u64 {
+        self.rng = self.rng.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.rng;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn hit(&mut self, probability: f64) -> bool {
+        probability > 0.0 && ((self.next() >> 11) as f64 / (1u64 << 53) as f64) < probability
+    }
+
+    // inject delay or error before operation
+    fn inject<T>(&mut self) -> Option<Step<T>> {
+        if self.delayed {
+            self.delayed = false;
+        } else if self.hit(self.config.delay) {
+            self.delayed = true;
+            let max = self.config.max_delay.as_nanos() as u64;
+            let delay = if max == 0 { 0 } else { self.next() % (max + 1) };
+            return Some(Step::Wait(Duration::from_nanos(delay)));
+        }
+        if self.hit(self.config.error) {
+            return Some(Step::Ready(Err(Error::new(
+                self.config.error_kind,
+                format!("chaos (seed {}): injected error", self.config.seed),
+            ))));
+        }
+        None
+    }
+
+    // spurious pending before async operation
+    #[cfg(feature = "tokio")]
+    fn pending(&mut self, read: bool) -> bool {
+        let yielded = &mut self.yielded[read as usize];
+        if *yielded {
+            *yielded = false;
+            return false;
+        }
+        let hit = self.hit(self.config.pending);
+        self.yielded[read as usize] = hit;
+        hit
+    }
+
+    // length for short read
+    fn read_len(&mut self, len: usize) -> usize {
+        if len > 1 && self.hit(self.config.short_read) {
+            1 + (self.next() % (len as u64 - 1)) as usize
+        } else {
+            len
+        }
+    }
+}
+
+impl CheckedMockStream {
+    /// Get chaos mode seed (if enabled).
+    pub fn chaos_seed(&self) -> Option<u64> {
+        self.chaos.as_ref().map(|c| c.config.seed)
+    }
+
+    // chaos step before read, returns (possible shortened) buffer length
+    pub(super) fn chaos_read(&mut self, len: usize) -> Result<usize, Step<usize>> {
+        if self.action >= self.actions.len() || len == 0 {
+            return Ok(len);
+        }
+        match self.chaos {
+            Some(ref mut chaos) => match chaos.inject() {
+                Some(step) => Err(step),
+                None => Ok(chaos.read_len(len)),
+            },
+            None => Ok(len),
+        }
+    }
+
+    // spurious pending before async read/write (waker is woken immediately)
+    #[cfg(feature = "tokio")]
+    pub(super) fn chaos_pending(&mut self, cx: &mut task::Context<'_>, read: bool) -> bool {
+        if self.action >= self.actions.len() {
+            return false;
+        }
+        let pending = self.chaos.as_mut().is_some_and(|chaos| chaos.pending(read));
+        if pending {
+            cx.waker().wake_by_ref();
+        }
+        pending
+    }
+
+    // chaos step before write
+    pub(super) fn chaos_write(&mut self, len: usize) -> Option<Step<usize>> {
+        if self.action >= self.actions.len() || len == 0 {
+            return None;
+        }
+        self.chaos.as_mut().and_then(|chaos| chaos.inject())
+    }
+}
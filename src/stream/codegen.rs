@@ -0,0 +1,90 @@
+//! Rust source generation for [`CheckedMockStreamBuilder`] scenario.
+//!
+//! Useful for bootstrap test fixtures from recorded ([`super::RecordingStream`]) or loaded scenario.
+
+use std::fmt::Write as _;
+use std::time::Duration;
+
+use super::{Action, CheckedMockStreamBuilder};
+
+impl CheckedMockStreamBuilder {
+    /// Render scenario as Rust source (a ready-to-paste builder chain with byte literals).
+    pub fn to_rust(&self) -> String {
+        let mut code = String::from("CheckedMockStreamBuilder::new()\n");
+        for action in &self.actions {
+            let _ = match action {
+                Action::Read(data) => writeln!(code, "    .read({}.to_vec())", byte_literal(data)),
+                Action::Write(data) => {
+                    writeln!(code, "    .write({}.to_vec())", byte_literal(data))
+                }
+                Action::ReadFile(path) => {
+                    writeln!(code, "    .read_file({:?})", path.display().to_string())
+                }
+                Action::ReadEvery(data, d) => writeln!(
+                    code,
+                    "    .read_every({}.to_vec(), {})",
+                    byte_literal(data),
+                    duration_expr(*d)
+                ),
+                Action::Wait(d) => writeln!(code, "    .wait({})", duration_expr(*d)),
+                Action::WaitRead(d) => writeln!(code, "    .wait_read({})", duration_expr(*d)),
+                Action::WaitWrite(d) => writeln!(code, "    .wait_write({})", duration_expr(*d)),
+                Action::WaitUntil(d) => writeln!(code, "    .wait_until({})", duration_expr(*d)),
+                Action::Barrier => writeln!(code, "    .barrier()"),
+                Action::PeerHalfClose => writeln!(code, "    .peer_half_close()"),
+                Action::PeerFullClose => writeln!(code, "    .peer_full_close()"),
+                Action::SyncPoint(point) => writeln!(
+                    code,
+                    "    .sync_point(&sync_point) // SyncPoint::new({})",
+                    point.parties()
+                ),
+                Action::ReadError(err) => writeln!(
+                    code,
+                    "    .read_error(std::io::Error::new(std::io::ErrorKind::{:?}, {:?}))",
+                    err.kind(),
+                    err.to_string()
+                ),
+                Action::WriteError(err) => writeln!(
+                    code,
+                    "    .write_error(std::io::Error::new(std::io::ErrorKind::{:?}, {:?}))",
+                    err.kind(),
+                    err.to_string()
+                ),
+            };
+        }
+        code
+    }
+}
+
+fn byte_literal(data: &[u8]) -> String {
+    let mut s = String::with_capacity(data.len() + 3);
+    s.push_str("b\"");
+    for &c in data {
+        match c {
+            b'\r' => s.push_str("\\r"),
+            b'\n' => s.push_str("\\n"),
+            b'\t' => s.push_str("\\t"),
+            0 => s.push_str("\\0"),
+            b'\\' => s.push_str("\\\\"),
+            b'"' => s.push_str("\\\""),
+            0x20..=0x7e => s.push(c as char),
+            _ => {
+                let _ = write!(s, "\\x{:02x}", c);
+            }
+        }
+    }
+    s.push('"');
+    s
+}
+
+fn duration_expr(d: Duration) -> String {
+    if d.subsec_nanos() == 0 {
+        format!("std::time::Duration::from_secs({})", d.as_secs())
+    } else if d.subsec_nanos() % 1_000_000 == 0 {
+        format!("std::time::Duration::from_millis({})", d.as_millis())
+    } else if d.subsec_nanos() % 1_000 == 0 {
+        format!("std::time::Duration::from_micros({})", d.as_micros())
+    } else {
+        format!("std::time::Duration::from_nanos({})", d.as_nanos())
+    }
+}
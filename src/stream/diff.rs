@@ -0,0 +1,381 @@
+//! Diff of scripted scenario and observed operations of [`CheckedMockStream`].
+
+use std::convert::TryInto;
+use std::fmt;
+use std::io::{Error, ErrorKind};
+use std::time::Duration;
+
+use super::calls::CallCount;
+use super::dump::{describe, preview};
+use super::{Action, CheckedMockStream, MismatchPolicy};
+
+/// A divergence of observed operations from scripted scenario.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Divergence {
+    /// Written data mismatch expected write.
+    MismatchedWrite {
+        /// Action index.
+        action: usize,
+        /// Offset of the first mismatched byte.
+        offset: usize,
+        /// Expected data.
+        expected: Vec<u8>,
+        /// Written data.
+        written: Vec<u8>,
+    },
+    /// Write, when other action (or end of scenario, if action is `None`) was scripted.
+    UnexpectedWrite {
+        /// Action index.
+        action: Option<usize>,
+        /// Written data.
+        written: Vec<u8>,
+    },
+    /// Read, when other action was scripted.
+    UnexpectedRead {
+        /// Action index.
+        action: usize,
+    },
+    /// Written, but not flushed data (in flush-gated mode).
+    Unflushed {
+        /// Written data.
+        written: Vec<u8>,
+    },
+    /// Operation of action performed out of timing constraint
+    /// (see [`super::CheckedMockStreamBuilder::within`]).
+    Timing {
+        /// Action index.
+        action: usize,
+        /// Time between completion of the previous action and operation.
+        elapsed: Duration,
+        /// Required maximum time.
+        within: Option<Duration>,
+        /// Required minimum time.
+        after: Option<Duration>,
+    },
+    /// Count of operation calls out of expectation
+    /// (see [`super::CheckedMockStreamBuilder::expect_read_calls`]).
+    Calls {
+        /// Operation (`read`, `write` or `flush`).
+        operation: &'static str,
+        /// Count of calls.
+        calls: usize,
+        /// Expected count of calls.
+        expected: CallCount,
+    },
+    /// Scripted action, not performed by stream user.
+    Missing {
+        /// Action index.
+        action: usize,
+        /// Action description.
+        description: String,
+    },
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Divergence::MismatchedWrite {
+                action,
+                offset,
+                expected,
+                written,
+            } => write!(
+                f,
+                "action {}: written data mismatch at offset {}: expected {} at offset, written {}",
+                action,
+                offset,
+                preview(&expected[(*offset).min(expected.len())..]),
+                preview(&written[(*offset).min(written.len())..]),
+            ),
+            Divergence::UnexpectedWrite {
+                action: Some(action),
+                written,
+            } => write!(
+                f,
+                "action {}: unexpected write {}",
+                action,
+                preview(written)
+            ),
+            Divergence::UnexpectedWrite {
+                action: None,
+                written,
+            } => write!(f, "end: unexpected write {}", preview(written)),
+            Divergence::UnexpectedRead { action } => {
+                write!(f, "action {}: unexpected read", action)
+            }
+            Divergence::Unflushed { written } => {
+                write!(f, "unflushed write {}", preview(written))
+            }
+            Divergence::Timing {
+                action,
+                elapsed,
+                within,
+                after,
+            } => {
+                write!(
+                    f,
+                    "action {}: performed after {:?}, expected",
+                    action, elapsed
+                )?;
+                if let Some(after) = after {
+                    write!(f, " after {:?}", after)?;
+                }
+                if let Some(within) = within {
+                    if after.is_some() {
+                        write!(f, " and")?;
+                    }
+                    write!(f, " within {:?}", within)?;
+                }
+                Ok(())
+            }
+            Divergence::Calls {
+                operation,
+                calls,
+                expected,
+            } => write!(
+                f,
+                "{} called {} times, expected {}",
+                operation, calls, expected
+            ),
+            Divergence::Missing {
+                action,
+                description,
+            } => write!(f, "action {}: missing {}", action, description),
+        }
+    }
+}
+
+/// An error of written data mismatch, wrapped in `io::Error` with `InvalidInput` kind
+/// (can be downcasted for rendering of precise failures).
+///
+/// ```
+/// use netmock::stream::CheckedMockStreamBuilder;
+/// use netmock::MismatchError;
+/// use std::io::Write;
+///
+/// let mut stream = CheckedMockStreamBuilder::new().write(b"PING\r\n").build();
+/// let err = stream.write(b"PONG\r\n").unwrap_err();
+/// let mismatch = err.get_ref().and_then(|e| e.downcast_ref::<MismatchError>()).unwrap();
+/// assert_eq!((mismatch.action(), mismatch.offset()), (0, 1));
+/// assert_eq!(mismatch.label(), r#"W 6 bytes "PING\r\n""#);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MismatchError {
+    action: usize,
+    label: String,
+    offset: usize,
+    expected: Vec<u8>,
+    written: Vec<u8>,
+}
+
+impl MismatchError {
+    /// Gets an index of the expected write action.
+    pub fn action(&self) -> usize {
+        self.action
+    }
+
+    /// Gets a label of the expected write action (as in scenario dump).
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// Gets an offset of the first mismatched byte.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Gets an expected data.
+    pub fn expected(&self) -> &[u8] {
+        &self.expected
+    }
+
+    /// Gets a written data.
+    pub fn written(&self) -> &[u8] {
+        &self.written
+    }
+
+    fn to_divergence(&self) -> Divergence {
+        Divergence::MismatchedWrite {
+            action: self.action,
+            offset: self.offset,
+            expected: self.expected.clone(),
+            written: self.written.clone(),
+        }
+    }
+}
+
+impl fmt::Display for MismatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "mismatch written data: {}", self.to_divergence())
+    }
+}
+
+impl std::error::Error for MismatchError {}
+
+impl CheckedMockStream {
+    /// Check if all scripted actions are performed.
+    pub fn is_done(&self) -> bool {
+        if self.duplex {
+            (0..self.actions.len()).all(|i| self.consumed(i))
+        } else {
+            self.action >= self.actions.len()
+        }
+    }
+
+    /// Get divergences of observed operations from scripted scenario (including not performed actions).
+    pub fn diff(&self) -> Vec<Divergence> {
+        let mut diff = self.divergences.clone();
+        if !self.unflushed.is_empty() {
+            diff.push(Divergence::Unflushed {
+                written: self.unflushed.clone(),
+            });
+        }
+        for (i, action) in self.actions.iter().enumerate() {
+            if self.consumed(i) {
+                continue;
+            }
+            let description = match action {
+                Action::Read(data) if i == self.action && self.pos > 0 => format!(
+                    "R {} (partially readed {}/{})",
+                    preview(&data[self.pos..]),
+                    self.pos,
+                    data.len()
+                ),
+                action => describe(action),
+            };
+            diff.push(Divergence::Missing {
+                action: i,
+                description,
+            });
+        }
+        diff.extend(self.calls.check());
+        diff
+    }
+
+    /// Assert all scripted actions are performed without divergences.
+    ///
+    /// Panics with diff and scenario dump.
+    #[track_caller]
+    pub fn assert_done(&self) {
+        let diff = self.diff();
+        if !diff.is_empty() {
+            let text: Vec<String> = diff.iter().map(render).collect();
+            panic!(
+                "scenario diverged:\n{}\nscenario:\n{}",
+                text.join("\n"),
+                self.dump()
+            );
+        }
+    }
+
+    pub(super) fn unexpected_read(&mut self) {
+        let divergence = Divergence::UnexpectedRead {
+            action: self.action,
+        };
+        // polling loops may repeat reads
+        if self.divergences.last() != Some(&divergence) {
+            self.divergences.push(divergence);
+        }
+    }
+
+    pub(super) fn unexpected_write(&mut self, buf: &[u8]) {
+        let action = if self.action < self.actions.len() {
+            Some(self.action)
+        } else {
+            None
+        };
+        self.divergences.push(Divergence::UnexpectedWrite {
+            action,
+            written: buf.to_vec(),
+        });
+    }
+
+    // handle mismatched write of action by policy, returns error for caller (or None to continue)
+    pub(super) fn mismatched_write(&mut self, expected: Vec<u8>, buf: &[u8]) -> Option<Error> {
+        let err = MismatchError {
+            action: self.action,
+            label: describe(&self.actions[self.action]),
+            offset: mismatch_offset(&expected, buf),
+            expected,
+            written: buf.to_vec(),
+        };
+        let divergence = err.to_divergence();
+        match self.mismatch {
+            MismatchPolicy::Error => {
+                self.divergences.push(divergence);
+                Some(Error::new(ErrorKind::InvalidInput, err))
+            }
+            MismatchPolicy::Panic => {
+                panic!(
+                    "scenario diverged:\n{}\nscenario:\n{}",
+                    render(&divergence),
+                    self.dump()
+                );
+            }
+            MismatchPolicy::Continue => {
+                self.divergences.push(divergence);
+                None
+            }
+        }
+    }
+}
+
+// render divergence for panic message (with colored diff of mismatched write in `pretty` feature)
+fn render(divergence: &Divergence) -> String {
+    #[cfg(feature = "pretty")]
+    if let Divergence::MismatchedWrite {
+        offset,
+        expected,
+        written,
+        ..
+    } = divergence
+    {
+        return format!(
+            "  {}\n{}",
+            divergence,
+            super::pretty::diff(expected, written, *offset)
+        );
+    }
+    format!("  {}", divergence)
+}
+
+// block size for the first stage of mismatch search (compared with memcmp)
+const BLOCK_SIZE: usize = 4096;
+
+// offset of the first mismatched byte
+//
+// Search is staged for large payloads: equal blocks are skipped with memcmp, then mismatched
+// block is scanned by 128-bit words, and only the last word is scanned by bytes.
+pub(super) fn mismatch_offset(expected: &[u8], written: &[u8]) -> usize {
+    let len = expected.len().min(written.len());
+    let (expected, written) = (&expected[..len], &written[..len]);
+
+    let mut offset = expected
+        .chunks(BLOCK_SIZE)
+        .zip(written.chunks(BLOCK_SIZE))
+        .take_while(|(a, b)| a == b)
+        .count()
+        * BLOCK_SIZE;
+    if offset >= len {
+        return len;
+    }
+
+    let end = len.min(offset + BLOCK_SIZE);
+    for (a, b) in expected[offset..end]
+        .chunks_exact(16)
+        .zip(written[offset..end].chunks_exact(16))
+    {
+        let a = u128::from_le_bytes(a.try_into().unwrap());
+        let b = u128::from_le_bytes(b.try_into().unwrap());
+        if a != b {
+            return offset + ((a ^ b).trailing_zeros() / 8) as usize;
+        }
+        offset += 16;
+    }
+    offset
+        + expected[offset..end]
+            .iter()
+            .zip(&written[offset..end])
+            .position(|(a, b)| a != b)
+            .unwrap_or(end - offset)
+}
@@ -0,0 +1,103 @@
+//! Declarative scenario syntax ([`mock_stream!`](crate::mock_stream) macro).
+
+use std::time::Duration;
+
+use super::util::parse_duration;
+
+#[doc(hidden)]
+#[track_caller]
+pub fn duration(s: &str) -> Duration {
+    parse_duration(s).unwrap_or_else(|| panic!("mock_stream!: invalid duration: {}", s))
+}
+
+/// Build [`CheckedMockStream`](crate::stream::CheckedMockStream) from declarative scenario.
+///
+/// Every action is terminated by `;`:
+///
+/// - `read <data>;`, `write <data>;` (or `expect_write <data>;`) - data to read and expected write
+///   (byte string, `Vec<u8>` or any other [`Payload`](crate::stream::Payload) source).
+/// - `read_file <path>;` - file content to read.
+/// - `read_error <error>;`, `write_error <error>;` - errors, returned by read or write.
+/// - `wait <duration>;`, `wait_read <duration>;`, `wait_write <duration>;` - duration literal with unit
+///   (`ns`, `us`, `ms`, `s`, `m`) like `100ms` or `Duration` expression in parentheses.
+/// - `barrier;` - barrier of duplex mode.
+///
+/// Unknown action names are rejected at compile time. Each action is expanded by macro recursion,
+/// so long scenarios may require increase of `recursion_limit`.
+///
+/// ```
+/// use netmock::mock_stream;
+/// use std::io::{Read, Write};
+///
+/// let mut stream = mock_stream! {
+///     read b"+OK ready\r\n";
+///     wait 10ms;
+///     expect_write b"QUIT\r\n";
+///     read b"+OK bye\r\n";
+/// };
+/// let mut buf = [0; 11];
+/// stream.read_exact(&mut buf).unwrap();
+/// stream.write_all(b"QUIT\r\n").unwrap();
+/// ```
+///
+/// Misspelled action is a compile error:
+///
+/// ```compile_fail
+/// let stream = netmock::mock_stream! { raed b"+OK\r\n"; };
+/// ```
+#[macro_export]
+macro_rules! mock_stream {
+    ($($body:tt)*) => {
+        $crate::__mock_stream!(($crate::stream::CheckedMockStreamBuilder::new()) $($body)*).build()
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __mock_stream {
+    (($b:expr)) => {
+        $b
+    };
+    (($b:expr) read $data:expr; $($rest:tt)*) => {
+        $crate::__mock_stream!(($b.read($data)) $($rest)*)
+    };
+    (($b:expr) write $data:expr; $($rest:tt)*) => {
+        $crate::__mock_stream!(($b.write($data)) $($rest)*)
+    };
+    (($b:expr) expect_write $data:expr; $($rest:tt)*) => {
+        $crate::__mock_stream!(($b.write($data)) $($rest)*)
+    };
+    (($b:expr) read_file $path:expr; $($rest:tt)*) => {
+        $crate::__mock_stream!(($b.read_file($path)) $($rest)*)
+    };
+    (($b:expr) read_error $err:expr; $($rest:tt)*) => {
+        $crate::__mock_stream!(($b.read_error($err)) $($rest)*)
+    };
+    (($b:expr) write_error $err:expr; $($rest:tt)*) => {
+        $crate::__mock_stream!(($b.write_error($err)) $($rest)*)
+    };
+    (($b:expr) wait ($d:expr); $($rest:tt)*) => {
+        $crate::__mock_stream!(($b.wait($d)) $($rest)*)
+    };
+    (($b:expr) wait $d:literal; $($rest:tt)*) => {
+        $crate::__mock_stream!(($b.wait($crate::__private::duration(stringify!($d)))) $($rest)*)
+    };
+    (($b:expr) wait_read ($d:expr); $($rest:tt)*) => {
+        $crate::__mock_stream!(($b.wait_read($d)) $($rest)*)
+    };
+    (($b:expr) wait_read $d:literal; $($rest:tt)*) => {
+        $crate::__mock_stream!(($b.wait_read($crate::__private::duration(stringify!($d)))) $($rest)*)
+    };
+    (($b:expr) wait_write ($d:expr); $($rest:tt)*) => {
+        $crate::__mock_stream!(($b.wait_write($d)) $($rest)*)
+    };
+    (($b:expr) wait_write $d:literal; $($rest:tt)*) => {
+        $crate::__mock_stream!(($b.wait_write($crate::__private::duration(stringify!($d)))) $($rest)*)
+    };
+    (($b:expr) barrier; $($rest:tt)*) => {
+        $crate::__mock_stream!(($b.barrier()) $($rest)*)
+    };
+    (($b:expr) $action:ident $($rest:tt)*) => {
+        compile_error!(concat!("mock_stream!: unknown action `", stringify!($action), "`"))
+    };
+}
@@ -0,0 +1,180 @@
+//! Human-readable rendering of [`CheckedMockStream`] state for debugging of failed tests.
+
+use std::fmt::{self, Write as _};
+
+use super::util::{escape, format_duration};
+use super::{Action, CheckedMockStream, SimpleMockStream};
+
+// maximum payload preview length
+const PREVIEW_LEN: usize = 32;
+
+impl CheckedMockStream {
+    /// Render all actions with consumed/pending status and payload previews.
+    ///
+    /// ```text
+    ///     0 done     R 11 bytes "+OK ready\r\n"
+    /// >   1 partial  R 13 bytes "First\nSecond\n" (6/13)
+    ///     2 pending  W 6 bytes "QUIT\r\n"
+    /// ```
+    pub fn dump(&self) -> String {
+        let mut text = String::new();
+        if let Some(seed) = self.chaos_seed() {
+            let _ = writeln!(text, "chaos seed: {}", seed);
+        }
+        for (i, action) in self.actions.iter().enumerate() {
+            let next = i == self.action || (self.duplex && i == self.write_action);
+            let (marker, status) = if self.consumed(i) {
+                (' ', "done")
+            } else if !next {
+                (' ', "pending")
+            } else if i == self.action && self.pos > 0 {
+                ('>', "partial")
+            } else {
+                ('>', "next")
+            };
+            let _ = write!(
+                text,
+                "{} {:>3} {:<8} {}",
+                marker,
+                i,
+                status,
+                describe(action)
+            );
+            if i == self.action && self.pos > 0 {
+                if let Action::Read(data) = action {
+                    let _ = write!(text, " ({}/{})", self.pos, data.len());
+                }
+            }
+            text.push('\n');
+        }
+        if self.is_done() {
+            text.push_str(">     end\n");
+        }
+        text
+    }
+}
+
+/// Scenario checklist: progress summary, actions with status (see [`CheckedMockStream::dump`])
+/// and divergences.
+///
+/// ```text
+/// action 1/3, written 6 bytes
+///     0 done     W 6 bytes "PING\r\n"
+/// >   1 partial  R 7 bytes "+PONG\r\n" (3/7)
+///     2 pending  W 6 bytes "QUIT\r\n"
+/// ```
+impl fmt::Display for CheckedMockStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "action {}/{}, written {} bytes",
+            self.action.min(self.actions.len()),
+            self.actions.len(),
+            self.written_len()
+        )?;
+        f.write_str(&self.dump())?;
+        if !self.divergences.is_empty() {
+            writeln!(f, "divergences:")?;
+            for divergence in &self.divergences {
+                writeln!(f, "  {}", divergence)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// actions are rendered as descriptions with payload previews
+impl fmt::Debug for CheckedMockStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let actions: Vec<String> = self.actions.iter().map(describe).collect();
+        let mut s = f.debug_struct("CheckedMockStream");
+        s.field("action", &self.action);
+        if self.duplex {
+            s.field("write_action", &self.write_action);
+        }
+        s.field("pos", &self.pos)
+            .field("actions", &actions)
+            .field("written", &preview(self.written()))
+            .field("written_len", &self.written_len());
+        if self.flush_gated {
+            s.field("unflushed", &preview(&self.unflushed));
+        }
+        s.field("divergences", &self.divergences)
+            .field("shutdown", &self.shutdown)
+            .finish()
+    }
+}
+
+/// Progress summary (read and written bytes).
+impl fmt::Display for SimpleMockStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "read {}/{} bytes, written {}",
+            self.pos,
+            self.read.len(),
+            preview(&self.written)
+        )
+    }
+}
+
+// short action description with payload preview
+pub(super) fn describe(action: &Action) -> String {
+    match action {
+        Action::Read(data) => format!("R {}", preview(data)),
+        Action::Write(data) => format!("W {}", preview(data)),
+        Action::ReadFile(path) => format!("RFILE {}", path.display()),
+        Action::ReadEvery(data, d) => format!("REVERY {} {}", format_duration(*d), preview(data)),
+        Action::Wait(d) => format!("WAIT {}", format_duration(*d)),
+        Action::WaitRead(d) => format!("RWAIT {}", format_duration(*d)),
+        Action::WaitWrite(d) => format!("WWAIT {}", format_duration(*d)),
+        Action::WaitUntil(d) => format!("AT {}", format_duration(*d)),
+        Action::Barrier => "BARRIER".to_string(),
+        Action::PeerHalfClose => "HALFCLOSE".to_string(),
+        Action::PeerFullClose => "CLOSE".to_string(),
+        Action::SyncPoint(point) => format!("SYNC {} parties", point.parties()),
+        Action::ReadError(err) => format!("RERR {:?} {}", err.kind(), err),
+        Action::WriteError(err) => format!("WERR {:?} {}", err.kind(), err),
+    }
+}
+
+pub(super) fn preview(data: &[u8]) -> String {
+    if data.len() > PREVIEW_LEN {
+        format!(
+            "{} bytes \"{}\"...",
+            data.len(),
+            escape(&data[..PREVIEW_LEN])
+        )
+    } else {
+        format!("{} bytes \"{}\"", data.len(), escape(data))
+    }
+}
+
+// hexdump with ascii column, addresses are started from `start`
+pub(super) fn hexdump(data: &[u8], start: usize) -> String {
+    let mut s = String::new();
+    for (n, chunk) in data.chunks(16).enumerate() {
+        let _ = write!(s, "{:08x} ", start + n * 16);
+        for i in 0..16 {
+            if i == 8 {
+                s.push(' ');
+            }
+            match chunk.get(i) {
+                Some(b) => {
+                    let _ = write!(s, " {:02x}", b);
+                }
+                None => s.push_str("   "),
+            }
+        }
+        s.push_str("  |");
+        s.extend(chunk.iter().map(|&b| {
+            if b.is_ascii_graphic() || b == b' ' {
+                b as char
+            } else {
+                '.'
+            }
+        }));
+        s.push_str("|\n");
+    }
+    s
+}
@@ -0,0 +1,106 @@
+//! Full-duplex mode of [`CheckedMockStream`].
+//!
+//! In duplex mode reads and writes are independent queues (read actions and write actions of
+//! the scenario in order), so reader and writer may run concurrently. Barriers order the queues:
+//! a direction, reached the barrier, waits until the other direction reaches it too
+//! (async operation returns `Poll::Pending`, sync operation returns `WouldBlock` error, sync operation
+//! of [`super::SharedMockStream`] waits for operation of other handle).
+
+use std::io::{Error, ErrorKind};
+
+use super::{extend, Action, CheckedMockStream, CheckedMockStreamBuilder, PeerState, Step};
+
+impl CheckedMockStreamBuilder {
+    /// Enable full-duplex mode: reads and writes are consumed from independent queues.
+    ///
+    /// Generic waits delay both directions, use [`CheckedMockStreamBuilder::barrier`] for ordering.
+    pub fn duplex(mut self) -> Self {
+        self.duplex = true;
+        self
+    }
+
+    /// Queue a barrier: in duplex mode actions after barrier are not performed, until both
+    /// directions reach it (ignored in sequential mode).
+    pub fn barrier(mut self) -> Self {
+        self.actions.push_back(Action::Barrier);
+        self
+    }
+}
+
+// direction of action (`Some(true)` for read, `Some(false)` for write, `None` for both)
+fn direction(action: &Action) -> Option<bool> {
+    match action {
+        Action::Read(_)
+        | Action::ReadError(_)
+        | Action::ReadFile(_)
+        | Action::ReadEvery(..)
+        | Action::WaitRead(_) => Some(true),
+        Action::Write(_) | Action::WriteError(_) | Action::WaitWrite(_) => Some(false),
+        Action::Wait(_)
+        | Action::WaitUntil(_)
+        | Action::Barrier
+        | Action::PeerHalfClose
+        | Action::PeerFullClose
+        | Action::SyncPoint(_) => None,
+    }
+}
+
+pub(super) fn blocked_error() -> Error {
+    Error::new(ErrorKind::WouldBlock, "operation blocked by scenario")
+}
+
+impl CheckedMockStream {
+    // move current action over actions of the other direction, passed barriers and sync points
+    // and peer shutdowns
+    // (in sequential mode waits of the other direction are armed for the next operation of it
+    // and write ends heartbeat)
+    pub(super) fn skip_foreign(&mut self, read: bool) -> Option<Step<usize>> {
+        while let Some(action) = self.actions.get(self.action) {
+            match action {
+                Action::Barrier if self.duplex && !self.other_reached(read) => {
+                    return Some(Step::Blocked)
+                }
+                Action::Barrier => {}
+                action if self.duplex && direction(action) == Some(!read) => {}
+                Action::WaitWrite(wait) if read => extend(&mut self.write_deadline, *wait),
+                Action::WaitRead(wait) if !read => extend(&mut self.read_deadline, *wait),
+                Action::SyncPoint(point) => {
+                    let point = point.clone();
+                    if let Some(step) = self.sync_step(&point) {
+                        return Some(step);
+                    }
+                }
+                Action::PeerHalfClose => self.close_peer(PeerState::HalfClosed),
+                Action::PeerFullClose => self.close_peer(PeerState::Closed),
+                Action::ReadEvery(..) if !read => {
+                    self.end_heartbeat();
+                    continue;
+                }
+                _ => return None,
+            }
+            self.action += 1;
+        }
+        None
+    }
+
+    // other direction (`write_action` cursor) reached current action
+    fn other_reached(&self, read: bool) -> bool {
+        let mut other = self.write_action;
+        while other < self.action && direction(&self.actions[other]) == Some(read) {
+            other += 1;
+        }
+        other >= self.action
+    }
+
+    // action is performed
+    pub(super) fn consumed(&self, i: usize) -> bool {
+        if !self.duplex {
+            return i < self.action;
+        }
+        match direction(&self.actions[i]) {
+            Some(true) => i < self.action,
+            Some(false) => i < self.write_action,
+            None => i < self.action && i < self.write_action,
+        }
+    }
+}
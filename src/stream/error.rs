@@ -0,0 +1,189 @@
+//! Scripted errors, returned by the stream read and write.
+//!
+//! Operations return a copy of the queued error with the same kind, message and raw OS error code
+//! (every time, so builders are immutable and replayed actions after [`CheckedMockStream::reset`]
+//! or in streams, built from one builder, return equal errors). Errors, which need the inner error
+//! (for downcasting like with a real stream), are queued as a constructor with
+//! [`CheckedMockStreamBuilder::read_error_with`] or [`CheckedMockStreamBuilder::write_error_with`].
+//!
+//! Builder shorthands (like [`CheckedMockStreamBuilder::read_timeout`]) queue errors of common kinds.
+//!
+//! [`CheckedMockStream::reset`]: super::CheckedMockStream::reset
+
+use std::fmt;
+use std::io::{Error, ErrorKind};
+use std::sync::Arc;
+
+use super::{Action, CheckedMockStreamBuilder};
+
+// constructor of error with inner error
+type MakeError = Box<dyn Fn() -> Error + Send + Sync>;
+
+pub(super) struct ScriptedError {
+    kind: ErrorKind,
+    message: String,
+    raw_os_error: Option<i32>,
+    // without inner error (created from kind)
+    simple: bool,
+    make: Option<MakeError>,
+}
+
+impl ScriptedError {
+    pub(super) fn new(err: Error) -> Self {
+        ScriptedError {
+            kind: err.kind(),
+            message: err.to_string(),
+            raw_os_error: err.raw_os_error(),
+            simple: err.raw_os_error().is_none() && err.get_ref().is_none(),
+            make: None,
+        }
+    }
+
+    // kind and message are taken from the sample error
+    fn with<F: Fn() -> Error + Send + Sync + 'static>(make: F) -> Self {
+        let sample = make();
+        ScriptedError {
+            make: Some(Box::new(make)),
+            ..ScriptedError::new(sample)
+        }
+    }
+
+    pub(super) fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    // create a new error (or a copy)
+    pub(super) fn to_error(&self) -> Error {
+        if let Some(make) = &self.make {
+            return make();
+        }
+        match self.raw_os_error {
+            Some(code) => Error::from_raw_os_error(code),
+            None if self.simple => Error::from(self.kind),
+            None => Error::new(self.kind, self.message.clone()),
+        }
+    }
+}
+
+impl CheckedMockStreamBuilder {
+    /// Queue an error, created by `make` on every matching read (inner error is preserved).
+    ///
+    /// ```
+    /// # use netmock::stream::CheckedMockStreamBuilder;
+    /// use std::io::{Error, ErrorKind};
+    ///
+    /// let builder = CheckedMockStreamBuilder::new()
+    ///     .read_error_with(|| Error::new(ErrorKind::InvalidData, "bad frame"));
+    /// ```
+    pub fn read_error_with<F: Fn() -> Error + Send + Sync + 'static>(mut self, make: F) -> Self {
+        self.actions
+            .push_back(Action::ReadError(Arc::new(ScriptedError::with(make))));
+        self
+    }
+
+    /// Queue an error, created by `make` on every matching write (inner error is preserved).
+    pub fn write_error_with<F: Fn() -> Error + Send + Sync + 'static>(mut self, make: F) -> Self {
+        self.actions
+            .push_back(Action::WriteError(Arc::new(ScriptedError::with(make))));
+        self
+    }
+
+    /// Queue an error to be returned by `n` consecutive stream reads
+    /// (copies of the error with the same kind, message and raw OS error code).
+    pub fn read_error_times(mut self, err: Error, n: usize) -> Self {
+        let err = Arc::new(ScriptedError::new(err));
+        for _ in 0..n {
+            self.actions.push_back(Action::ReadError(err.clone()));
+        }
+        self
+    }
+
+    /// Queue an error to be returned by `n` consecutive stream writes
+    /// (copies of the error with the same kind, message and raw OS error code).
+    pub fn write_error_times(mut self, err: Error, n: usize) -> Self {
+        let err = Arc::new(ScriptedError::new(err));
+        for _ in 0..n {
+            self.actions.push_back(Action::WriteError(err.clone()));
+        }
+        self
+    }
+}
+
+// shorthands for common errors
+impl CheckedMockStreamBuilder {
+    /// Queue a timeout (`TimedOut`) error to be returned by the stream read
+    pub fn read_timeout(self) -> Self {
+        self.read_error(Error::from(ErrorKind::TimedOut))
+    }
+
+    /// Queue a connection reset (`ConnectionReset`) error to be returned by the stream read
+    pub fn read_connection_reset(self) -> Self {
+        self.read_error(Error::from(ErrorKind::ConnectionReset))
+    }
+
+    /// Queue a connection abort (`ConnectionAborted`) error to be returned by the stream read
+    pub fn read_connection_aborted(self) -> Self {
+        self.read_error(Error::from(ErrorKind::ConnectionAborted))
+    }
+
+    /// Queue an unexpected EOF (`UnexpectedEof`) error to be returned by the stream read
+    pub fn read_unexpected_eof(self) -> Self {
+        self.read_error(Error::from(ErrorKind::UnexpectedEof))
+    }
+
+    /// Queue a `WouldBlock` error to be returned by the stream read
+    pub fn read_would_block(self) -> Self {
+        self.read_error(Error::from(ErrorKind::WouldBlock))
+    }
+
+    /// Queue an interruption (`Interrupted`) error to be returned by the stream read
+    pub fn read_interrupted(self) -> Self {
+        self.read_error(Error::from(ErrorKind::Interrupted))
+    }
+
+    /// Queue a timeout (`TimedOut`) error to be returned by the stream write
+    pub fn write_timeout(self) -> Self {
+        self.write_error(Error::from(ErrorKind::TimedOut))
+    }
+
+    /// Queue a connection reset (`ConnectionReset`) error to be returned by the stream write
+    pub fn write_connection_reset(self) -> Self {
+        self.write_error(Error::from(ErrorKind::ConnectionReset))
+    }
+
+    /// Queue a connection abort (`ConnectionAborted`) error to be returned by the stream write
+    pub fn write_connection_aborted(self) -> Self {
+        self.write_error(Error::from(ErrorKind::ConnectionAborted))
+    }
+
+    /// Queue a broken pipe (`BrokenPipe`) error to be returned by the stream write
+    pub fn write_broken_pipe(self) -> Self {
+        self.write_error(Error::from(ErrorKind::BrokenPipe))
+    }
+
+    /// Queue a `WouldBlock` error to be returned by the stream write
+    pub fn write_would_block(self) -> Self {
+        self.write_error(Error::from(ErrorKind::WouldBlock))
+    }
+
+    /// Queue an interruption (`Interrupted`) error to be returned by the stream write
+    pub fn write_interrupted(self) -> Self {
+        self.write_error(Error::from(ErrorKind::Interrupted))
+    }
+}
+
+impl fmt::Display for ScriptedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl fmt::Debug for ScriptedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScriptedError")
+            .field("kind", &self.kind)
+            .field("message", &self.message)
+            .field("raw_os_error", &self.raw_os_error)
+            .finish()
+    }
+}
@@ -0,0 +1,300 @@
+//! Fault-injecting wrapper around a real stream.
+//!
+//! Forwards traffic between the code under test and a real upstream, but injects scripted faults
+//! at byte offsets of read or written data, so resilience behavior can be tested against live services.
+
+use std::borrow::Cow;
+use std::io::{self, Error, ErrorKind, Read, Write};
+use std::time::Duration;
+
+#[cfg(feature = "tokio")]
+use std::pin::Pin;
+
+#[cfg(feature = "tokio")]
+use std::task::{self, Poll};
+
+#[cfg(feature = "tokio")]
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+#[cfg(feature = "tokio")]
+use tokio::time::{sleep, Sleep};
+
+#[cfg(feature = "tokio")]
+use futures_core::{ready, Future};
+
+/// A fault, injected at byte offset of read or written data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// Delay transfer at offset.
+    Delay(Duration),
+    /// Truncate data at offset (read return EOF, written data is silently discarded).
+    Truncate,
+    /// Return `ConnectionReset` error at offset (and for all following operations in the same direction).
+    Reset,
+    /// Corrupt byte at offset (XOR with mask).
+    Corrupt(u8),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Closed {
+    Truncated,
+    Reset,
+}
+
+enum Step {
+    Delay(Duration),
+    Closed(Closed),
+    Forward(usize),
+}
+
+#[derive(Debug, Default)]
+struct Half {
+    pos: usize,
+    faults: Vec<(usize, Fault)>,
+    next: usize,
+    closed: Option<Closed>,
+    #[cfg(feature = "tokio")]
+    sleep: Option<Pin<Box<Sleep>>>,
+}
+
+impl Half {
+    fn add(&mut self, offset: usize, fault: Fault) {
+        let idx = self.faults.partition_point(|(o, _)| *o <= offset);
+        self.faults.insert(idx, (offset, fault));
+    }
+
+    // fire faults at current position and get maximum length for transfer
+    fn prepare(&mut self, len: usize) -> Step {
+        while let Some(&(offset, fault)) = self.faults.get(self.next) {
+            if self.closed.is_some() || offset > self.pos {
+                break;
+            }
+            self.next += 1;
+            match fault {
+                Fault::Delay(d) => return Step::Delay(d),
+                Fault::Truncate => self.closed = Some(Closed::Truncated),
+                Fault::Reset => self.closed = Some(Closed::Reset),
+                Fault::Corrupt(_) => {}
+            }
+        }
+        if let Some(closed) = self.closed {
+            return Step::Closed(closed);
+        }
+        // stop transfer at the next fault offset
+        let limit = self.faults[self.next..]
+            .iter()
+            .find(|(_, f)| !matches!(f, Fault::Corrupt(_)))
+            .map_or(len, |(offset, _)| offset - self.pos);
+        Step::Forward(len.min(limit))
+    }
+
+    // apply corruptions to data, started at current position
+    fn corrupt(&self, data: &mut [u8]) {
+        let end = self.pos + data.len();
+        for &(offset, fault) in &self.faults {
+            if let Fault::Corrupt(mask) = fault {
+                if offset >= self.pos && offset < end {
+                    data[offset - self.pos] ^= mask;
+                }
+            }
+        }
+    }
+
+    fn corrupted<'a>(&self, data: &'a [u8]) -> Cow<'a, [u8]> {
+        let end = self.pos + data.len();
+        if self.faults.iter().any(|&(offset, f)| {
+            matches!(f, Fault::Corrupt(_)) && offset >= self.pos && offset < end
+        }) {
+            let mut data = data.to_vec();
+            self.corrupt(&mut data);
+            Cow::Owned(data)
+        } else {
+            Cow::Borrowed(data)
+        }
+    }
+}
+
+fn reset_error() -> Error {
+    Error::new(ErrorKind::ConnectionReset, "injected connection reset")
+}
+
+/// A stream wrapper, which forwards to a real connection with scripted faults injection.
+///
+/// Fault offsets are counted from the start of the stream (separately for read and written data).
+#[derive(Debug)]
+pub struct FaultStream<T> {
+    inner: T,
+    read: Half,
+    write: Half,
+}
+
+impl<T> FaultStream<T> {
+    /// Creates a new fault-injecting wrapper around stream.
+    pub fn new(inner: T) -> Self {
+        FaultStream {
+            inner,
+            read: Half::default(),
+            write: Half::default(),
+        }
+    }
+
+    /// Inject fault at offset of read data.
+    pub fn on_read(mut self, offset: usize, fault: Fault) -> Self {
+        self.read.add(offset, fault);
+        self
+    }
+
+    /// Inject fault at offset of written data.
+    pub fn on_write(mut self, offset: usize, fault: Fault) -> Self {
+        self.write.add(offset, fault);
+        self
+    }
+
+    /// Gets a count of readed bytes.
+    pub fn readed(&self) -> usize {
+        self.read.pos
+    }
+
+    /// Gets a count of written bytes (including discarded by truncation).
+    pub fn written(&self) -> usize {
+        self.write.pos
+    }
+
+    /// Gets a reference to the underlying stream.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Gets a mutable reference to the underlying stream.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Consumes wrapper, returning the underlying stream.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: Read> Read for FaultStream<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let limit = loop {
+            match self.read.prepare(buf.len()) {
+                Step::Delay(d) => std::thread::sleep(d),
+                Step::Closed(Closed::Truncated) => return Ok(0),
+                Step::Closed(Closed::Reset) => return Err(reset_error()),
+                Step::Forward(limit) => break limit,
+            }
+        };
+        let n = self.inner.read(&mut buf[..limit])?;
+        self.read.corrupt(&mut buf[..n]);
+        self.read.pos += n;
+        Ok(n)
+    }
+}
+
+impl<T: Write> Write for FaultStream<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let limit = loop {
+            match self.write.prepare(buf.len()) {
+                Step::Delay(d) => std::thread::sleep(d),
+                Step::Closed(Closed::Truncated) => {
+                    self.write.pos += buf.len();
+                    return Ok(buf.len());
+                }
+                Step::Closed(Closed::Reset) => return Err(reset_error()),
+                Step::Forward(limit) => break limit,
+            }
+        };
+        let data = self.write.corrupted(&buf[..limit]);
+        let n = self.inner.write(&data)?;
+        self.write.pos += n;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(feature = "tokio")]
+fn poll_prepare(half: &mut Half, cx: &mut task::Context<'_>, len: usize) -> Poll<Step> {
+    loop {
+        if let Some(ref mut sleep) = half.sleep {
+            ready!(Pin::new(sleep).poll(cx));
+            half.sleep = None;
+        }
+        match half.prepare(len) {
+            Step::Delay(d) => half.sleep = Some(Box::pin(sleep(d))),
+            step => return Poll::Ready(step),
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<T: AsyncRead + Unpin> AsyncRead for FaultStream<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        if buf.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+        let this = &mut *self;
+        let limit = match ready!(poll_prepare(&mut this.read, cx, buf.remaining())) {
+            Step::Closed(Closed::Truncated) => return Poll::Ready(Ok(())),
+            Step::Closed(Closed::Reset) => return Poll::Ready(Err(reset_error())),
+            Step::Forward(limit) => limit,
+            Step::Delay(_) => unreachable!(),
+        };
+        let mut limited = ReadBuf::new(buf.initialize_unfilled_to(limit));
+        ready!(Pin::new(&mut this.inner).poll_read(cx, &mut limited))?;
+        let n = limited.filled().len();
+        this.read.corrupt(limited.filled_mut());
+        this.read.pos += n;
+        buf.advance(n);
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<T: AsyncWrite + Unpin> AsyncWrite for FaultStream<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+        let this = &mut *self;
+        let limit = match ready!(poll_prepare(&mut this.write, cx, buf.len())) {
+            Step::Closed(Closed::Truncated) => {
+                this.write.pos += buf.len();
+                return Poll::Ready(Ok(buf.len()));
+            }
+            Step::Closed(Closed::Reset) => return Poll::Ready(Err(reset_error())),
+            Step::Forward(limit) => limit,
+            Step::Delay(_) => unreachable!(),
+        };
+        let data = this.write.corrupted(&buf[..limit]);
+        let n = ready!(Pin::new(&mut this.inner).poll_write(cx, &data))?;
+        this.write.pos += n;
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
@@ -0,0 +1,70 @@
+//! File-backed read sources.
+//!
+//! [`CheckedMockStreamBuilder::read_file`] streams payload from file with buffered reads (file is opened
+//! on the first read), so large downloads can be tested without loading payload into memory.
+//! With `mmap` feature [`CheckedMockStreamBuilder::read_mmap`] maps file into memory instead.
+
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use super::{Action, CheckedMockStream, CheckedMockStreamBuilder, Step};
+
+impl CheckedMockStreamBuilder {
+    /// Queue a file content to be returned by the stream read (streamed from file on read).
+    ///
+    /// File open and read errors are returned by the stream read.
+    pub fn read_file<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.actions
+            .push_back(Action::ReadFile(Arc::from(path.as_ref())));
+        self
+    }
+
+    /// Queue a file content (mapped into memory) to be returned by the stream read.
+    #[cfg(feature = "mmap")]
+    pub fn read_mmap<P: AsRef<Path>>(self, path: P) -> io::Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: file must not be modified while mapped (test fixtures are read-only)
+        let mmap = unsafe { memmap2::Mmap::map(&file)? };
+        Ok(self.read(super::Payload::from(mmap)))
+    }
+}
+
+impl CheckedMockStream {
+    // read from file source of current action
+    pub(super) fn read_file_step(&mut self, path: Arc<Path>, buf: &mut [u8]) -> Step<usize> {
+        if self.file.is_none() {
+            let file = File::open(&path).and_then(|mut file| {
+                file.seek(SeekFrom::Start(self.pos as u64))?;
+                Ok(BufReader::new(file))
+            });
+            match file {
+                Ok(file) => self.file = Some(file),
+                Err(err) => {
+                    self.action += 1;
+                    self.pos = 0;
+                    return Step::Ready(Err(io::Error::new(
+                        err.kind(),
+                        format!("{}: {}", PathBuf::from(&*path).display(), err),
+                    )));
+                }
+            }
+        }
+        let file = self.file.as_mut().unwrap();
+        match file.read(buf) {
+            Ok(0) => {
+                // end of file, go to the next action
+                self.file = None;
+                self.action += 1;
+                self.pos = 0;
+                self.read_step(buf)
+            }
+            Ok(n) => {
+                self.pos += n;
+                Step::Ready(Ok(n))
+            }
+            Err(err) => Step::Ready(Err(err)),
+        }
+    }
+}
@@ -0,0 +1,97 @@
+//! Scenarios from request/response fixture files.
+//!
+//! A fixture pair is a request file (expected write) and a response file (scripted read).
+//! Multi-step exchanges are stored in directory as `<step>.request` and `<step>.response` files:
+//!
+//! ```text
+//! fixtures/smtp/
+//!     0.response     server greeting (step without request)
+//!     1.request      HELO
+//!     1.response
+//!     2.request      MAIL FROM
+//!     2.response
+//! ```
+//!
+//! Steps are ordered by numeric name (non-numeric names go after numeric in lexicographic order),
+//! request is written before the response of the same step. Other files are ignored.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Error, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use super::CheckedMockStreamBuilder;
+
+/// Extension of request (expected write) fixture file.
+pub const REQUEST_EXT: &str = "request";
+
+/// Extension of response (scripted read) fixture file.
+pub const RESPONSE_EXT: &str = "response";
+
+fn read_fixture(path: &Path) -> io::Result<Vec<u8>> {
+    fs::read(path).map_err(|e| Error::new(e.kind(), format!("{}: {}", path.display(), e)))
+}
+
+#[derive(Default)]
+struct Step {
+    request: Option<PathBuf>,
+    response: Option<PathBuf>,
+}
+
+impl CheckedMockStreamBuilder {
+    /// Create scenario from request (expected write) and response (scripted read) files.
+    pub fn from_fixture_pair<P: AsRef<Path>, Q: AsRef<Path>>(
+        request: P,
+        response: Q,
+    ) -> io::Result<Self> {
+        CheckedMockStreamBuilder::new().fixture_pair(request, response)
+    }
+
+    /// Queue request (expected write) and response (scripted read) from files.
+    pub fn fixture_pair<P: AsRef<Path>, Q: AsRef<Path>>(
+        self,
+        request: P,
+        response: Q,
+    ) -> io::Result<Self> {
+        let request = read_fixture(request.as_ref())?;
+        let response = read_fixture(response.as_ref())?;
+        Ok(self.write(request).read(response))
+    }
+
+    /// Create scenario from directory of `<step>.request` and `<step>.response` fixture files.
+    pub fn from_fixture_dir<P: AsRef<Path>>(dir: P) -> io::Result<Self> {
+        let dir = dir.as_ref();
+        let mut steps: BTreeMap<(u64, String), Step> = BTreeMap::new();
+        let entries = fs::read_dir(dir)
+            .map_err(|e| Error::new(e.kind(), format!("{}: {}", dir.display(), e)))?;
+        for entry in entries {
+            let path = entry?.path();
+            let (stem, ext) = match (path.file_stem(), path.extension()) {
+                (Some(stem), Some(ext)) => (stem.to_string_lossy(), ext),
+                _ => continue,
+            };
+            let key = (stem.parse().unwrap_or(u64::MAX), stem.to_string());
+            if ext == REQUEST_EXT {
+                steps.entry(key).or_default().request = Some(path);
+            } else if ext == RESPONSE_EXT {
+                steps.entry(key).or_default().response = Some(path);
+            }
+        }
+        if steps.is_empty() {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("{}: no fixture files", dir.display()),
+            ));
+        }
+        let mut builder = CheckedMockStreamBuilder::new();
+        for step in steps.values() {
+            if let Some(ref path) = step.request {
+                builder = builder.write(read_fixture(path)?);
+            }
+            if let Some(ref path) = step.response {
+                builder = builder.read(read_fixture(path)?);
+            }
+        }
+        Ok(builder)
+    }
+}
@@ -0,0 +1,62 @@
+//! Flush-gated writes of [`CheckedMockStream`].
+//!
+//! With [`CheckedMockStreamBuilder::flush_gated`] writes are buffered and verified against the
+//! scenario (and appended to written data) only on flush, so missing or misplaced flushes are detected.
+
+use super::peer::peer_closed_error;
+use super::{CheckedMockStream, CheckedMockStreamBuilder, Step};
+
+impl CheckedMockStreamBuilder {
+    /// Buffer writes until flush (unflushed data is reported by [`CheckedMockStream::assert_done`]).
+    pub fn flush_gated(mut self) -> Self {
+        self.flush_gated = true;
+        self
+    }
+}
+
+impl CheckedMockStream {
+    /// Gets a written, but not flushed data (in flush-gated mode).
+    pub fn unflushed(&self) -> &[u8] {
+        &self.unflushed
+    }
+
+    // buffer write until flush
+    pub(super) fn buffer_write(&mut self, buf: &[u8]) -> Option<Step<usize>> {
+        if !self.flush_gated {
+            return None;
+        }
+        if self.peer_closed_on_write() {
+            return Some(Step::Ready(Err(peer_closed_error())));
+        }
+        self.unflushed.extend_from_slice(buf);
+        Some(Step::Ready(Ok(buf.len())))
+    }
+
+    // verify buffered writes
+    pub(super) fn flush_step(&mut self) -> Step<()> {
+        while !self.unflushed.is_empty() {
+            let data = std::mem::take(&mut self.unflushed);
+            let step = self.verify_write(&data);
+            let n = match step {
+                Step::Ready(Ok(n)) => n,
+                _ => 0,
+            };
+            self.unflushed = data;
+            self.unflushed.drain(..n);
+            match step {
+                Step::Ready(Ok(0)) => {
+                    return Step::Ready(Err(std::io::Error::new(
+                        std::io::ErrorKind::WriteZero,
+                        "unexpected flushed data",
+                    )))
+                }
+                Step::Ready(Ok(_)) => {}
+                Step::Ready(Err(err)) => return Step::Ready(Err(err)),
+                Step::Wait(wait) => return Step::Wait(wait),
+                Step::Blocked => return Step::Blocked,
+                Step::Sync(point, round) => return Step::Sync(point, round),
+            }
+        }
+        Step::Ready(Ok(()))
+    }
+}
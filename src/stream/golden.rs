@@ -0,0 +1,80 @@
+//! Golden-file comparison for written data.
+//!
+//! Set `NETMOCK_BLESS=1` environment variable to (re)write golden files from actual written data.
+
+use std::fs;
+use std::path::Path;
+
+use super::diff::mismatch_offset;
+use super::util::escape;
+use super::{CheckedMockStream, SimpleMockStream};
+
+/// Environment variable, which enable golden files update.
+pub const BLESS_ENV: &str = "NETMOCK_BLESS";
+
+// maximum length of mismatch context in panic message
+const CONTEXT_LEN: usize = 32;
+
+impl SimpleMockStream {
+    /// Assert written data is equal to golden file content.
+    ///
+    /// With `NETMOCK_BLESS` environment variable golden file is updated instead.
+    #[track_caller]
+    pub fn assert_written_matches_file<P: AsRef<Path>>(&self, path: P) {
+        assert_matches_file(self.written(), path.as_ref());
+    }
+}
+
+impl CheckedMockStream {
+    /// Assert written data is equal to golden file content.
+    ///
+    /// With `NETMOCK_BLESS` environment variable golden file is updated instead.
+    #[track_caller]
+    pub fn assert_written_matches_file<P: AsRef<Path>>(&self, path: P) {
+        assert_matches_file(self.written(), path.as_ref());
+    }
+}
+
+fn bless() -> bool {
+    std::env::var_os(BLESS_ENV).is_some_and(|v| !v.is_empty() && v != "0")
+}
+
+#[track_caller]
+fn assert_matches_file(written: &[u8], path: &Path) {
+    if bless() {
+        if let Some(dir) = path.parent() {
+            if !dir.as_os_str().is_empty() {
+                fs::create_dir_all(dir)
+                    .unwrap_or_else(|e| panic!("create {}: {}", dir.display(), e));
+            }
+        }
+        fs::write(path, written).unwrap_or_else(|e| panic!("write {}: {}", path.display(), e));
+        return;
+    }
+    let golden = fs::read(path).unwrap_or_else(|e| {
+        panic!(
+            "read golden file {}: {} (run with {}=1 to create)",
+            path.display(),
+            e,
+            BLESS_ENV
+        )
+    });
+    if golden == written {
+        return;
+    }
+    let offset = mismatch_offset(&golden, written);
+    let context = |data: &[u8]| {
+        let end = data.len().min(offset + CONTEXT_LEN);
+        escape(&data[offset.min(end)..end])
+    };
+    panic!(
+        "written data mismatch golden file {} at offset {} (written {} bytes, golden {} bytes)\n  written: \"{}\"\n  golden:  \"{}\"\n(run with {}=1 to update)",
+        path.display(),
+        offset,
+        written.len(),
+        golden.len(),
+        context(written),
+        context(&golden),
+        BLESS_ENV
+    );
+}
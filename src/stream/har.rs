@@ -0,0 +1,183 @@
+//! Loader of HAR (HTTP Archive) entries into HTTP/1.1 [`CheckedMockStreamBuilder`] scenario.
+//!
+//! Every entry become a request write expectation and a scripted response read.
+//! HTTP/2 pseudo-headers and hop-by-hop headers are dropped, `Content-Length` is recalculated
+//! for the (decoded) body, as HAR stores decoded content.
+
+use std::fs;
+use std::io::{self, Error, ErrorKind};
+use std::path::Path;
+
+use base64::Engine;
+use serde::Deserialize;
+
+use super::CheckedMockStreamBuilder;
+
+#[derive(Deserialize)]
+struct Har {
+    log: Log,
+}
+
+#[derive(Deserialize)]
+struct Log {
+    entries: Vec<Entry>,
+}
+
+#[derive(Deserialize)]
+struct Entry {
+    request: Request,
+    response: Response,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Request {
+    method: String,
+    url: String,
+    #[serde(default)]
+    headers: Vec<Header>,
+    #[serde(default)]
+    post_data: Option<PostData>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Response {
+    status: u16,
+    #[serde(default)]
+    status_text: String,
+    #[serde(default)]
+    headers: Vec<Header>,
+    #[serde(default)]
+    content: Option<Content>,
+}
+
+#[derive(Deserialize)]
+struct Header {
+    name: String,
+    value: String,
+}
+
+#[derive(Deserialize)]
+struct PostData {
+    #[serde(default)]
+    text: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Content {
+    #[serde(default)]
+    text: Option<String>,
+    #[serde(default)]
+    encoding: Option<String>,
+}
+
+// headers, which not describe the stored (decoded) body or are not valid for HTTP/1.1
+const SKIP_HEADERS: &[&str] = &[
+    "content-length",
+    "content-encoding",
+    "transfer-encoding",
+    "connection",
+    "keep-alive",
+];
+
+impl CheckedMockStreamBuilder {
+    /// Load HTTP/1.1 scenario from HAR file.
+    pub fn from_har_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        CheckedMockStreamBuilder::from_har(&text)
+    }
+
+    /// Load HTTP/1.1 scenario from HAR (JSON) content.
+    pub fn from_har(json: &str) -> io::Result<Self> {
+        let har: Har =
+            serde_json::from_str(json).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        let mut builder = CheckedMockStreamBuilder::new();
+        for entry in har.log.entries {
+            builder = builder
+                .write(encode_request(&entry.request)?)
+                .read(encode_response(&entry.response)?);
+        }
+        Ok(builder)
+    }
+}
+
+fn encode_request(req: &Request) -> io::Result<Vec<u8>> {
+    let (authority, target) = split_url(&req.url)?;
+    let body = req
+        .post_data
+        .as_ref()
+        .and_then(|p| p.text.as_deref())
+        .unwrap_or("")
+        .as_bytes();
+
+    let mut head = format!("{} {} HTTP/1.1\r\n", req.method, target);
+    if !req
+        .headers
+        .iter()
+        .any(|h| h.name.eq_ignore_ascii_case("host") || h.name == ":authority")
+    {
+        head.push_str(&format!("Host: {}\r\n", authority));
+    }
+    for h in &req.headers {
+        if h.name == ":authority" {
+            head.push_str(&format!("Host: {}\r\n", h.value));
+        } else if keep_header(&h.name) {
+            head.push_str(&format!("{}: {}\r\n", h.name, h.value));
+        }
+    }
+    Ok(finish(head, body.to_vec()))
+}
+
+fn encode_response(resp: &Response) -> io::Result<Vec<u8>> {
+    let body = match &resp.content {
+        Some(Content {
+            text: Some(text),
+            encoding,
+        }) => {
+            if encoding.as_deref() == Some("base64") {
+                base64::engine::general_purpose::STANDARD
+                    .decode(text.trim())
+                    .map_err(|e| Error::new(ErrorKind::InvalidData, e))?
+            } else {
+                text.as_bytes().to_vec()
+            }
+        }
+        _ => Vec::new(),
+    };
+    let mut head = format!("HTTP/1.1 {} {}\r\n", resp.status, resp.status_text);
+    for h in &resp.headers {
+        if keep_header(&h.name) {
+            head.push_str(&format!("{}: {}\r\n", h.name, h.value));
+        }
+    }
+    Ok(finish(head, body))
+}
+
+fn finish(mut head: String, body: Vec<u8>) -> Vec<u8> {
+    if !body.is_empty() {
+        head.push_str(&format!("Content-Length: {}\r\n", body.len()));
+    }
+    head.push_str("\r\n");
+    let mut buf = head.into_bytes();
+    buf.extend(body);
+    buf
+}
+
+fn keep_header(name: &str) -> bool {
+    !name.starts_with(':') && !SKIP_HEADERS.iter().any(|s| name.eq_ignore_ascii_case(s))
+}
+
+// split url to authority and request target (path with query)
+fn split_url(url: &str) -> io::Result<(&str, String)> {
+    let rest = url
+        .split_once("://")
+        .map(|(_, rest)| rest)
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("invalid url: {}", url)))?;
+    let rest = rest.split('#').next().unwrap_or(rest);
+    Ok(match rest.find(['/', '?']) {
+        Some(pos) if rest.as_bytes()[pos] == b'?' => (&rest[..pos], format!("/{}", &rest[pos..])),
+        Some(pos) => (&rest[..pos], rest[pos..].to_string()),
+        None => (rest, "/".to_string()),
+    })
+}
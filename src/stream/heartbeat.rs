@@ -0,0 +1,95 @@
+//! Periodic heartbeat reads of [`CheckedMockStream`].
+//!
+//! [`CheckedMockStreamBuilder::read_every`] makes payload readable once per interval (like server
+//! pings), while the action is active. The action is active until the next write of the stream user
+//! (in duplex mode until the write cursor passes the action). Partially read payload is finished
+//! by the next reads, even if the action is ended.
+
+use std::time::Duration;
+
+use super::{Action, CheckedMockStream, CheckedMockStreamBuilder, Instant, Payload, Step};
+
+impl CheckedMockStreamBuilder {
+    /// Queue a payload to be returned by the stream read once per interval, until the next write.
+    ///
+    /// The first payload is readable one interval after the read reaches the action. Read before
+    /// the next tick waits (async read is timer-driven, sync read sleeps or returns `WouldBlock`
+    /// in non-blocking mode).
+    ///
+    /// ```
+    /// # use netmock::stream::CheckedMockStreamBuilder;
+    /// # use std::time::Duration;
+    /// let builder = CheckedMockStreamBuilder::new()
+    ///     .read_every(b"PING\r\n", Duration::from_secs(30))
+    ///     .write(b"QUIT\r\n");
+    /// ```
+    pub fn read_every<P: Into<Payload>>(mut self, value: P, interval: Duration) -> Self {
+        self.actions
+            .push_back(Action::ReadEvery(value.into(), interval));
+        self
+    }
+}
+
+impl CheckedMockStream {
+    // read payload of heartbeat action on tick (`None`, if the action is ended)
+    pub(super) fn read_every_step(
+        &mut self,
+        data: Payload,
+        interval: Duration,
+        buf: &mut [u8],
+    ) -> Option<Step<usize>> {
+        // writer passed the action, partially read payload is finished before the end
+        let ended = self.duplex && self.write_action > self.action;
+        if self.pos == 0 {
+            if ended {
+                self.end_heartbeat();
+                return None;
+            }
+            let now = Instant::now();
+            let tick = *self.heartbeat.get_or_insert(now + interval);
+            if tick > now {
+                return Some(Step::Wait(tick - now));
+            }
+        }
+        let len = std::cmp::min(data.len() - self.pos, buf.len());
+        let end = self.pos + len;
+        buf[..len].copy_from_slice(&data[self.pos..end]);
+        if end < data.len() {
+            self.pos = end;
+        } else if ended {
+            self.pos = 0;
+            self.end_heartbeat();
+        } else {
+            // the next tick is counted from the previous one (without drift)
+            self.heartbeat = self.heartbeat.map(|tick| tick + interval);
+            self.pos = 0;
+        }
+        Some(Step::Ready(Ok(len)))
+    }
+
+    // read the rest of heartbeat payload, ended by write in the middle
+    pub(super) fn read_heartbeat_rest(&mut self, buf: &mut [u8]) -> Option<Step<usize>> {
+        let (data, pos) = self.heartbeat_rest.as_mut()?;
+        let len = std::cmp::min(data.len() - *pos, buf.len());
+        let end = *pos + len;
+        buf[..len].copy_from_slice(&data[*pos..end]);
+        if end == data.len() {
+            self.heartbeat_rest = None;
+        } else {
+            *pos = end;
+        }
+        Some(Step::Ready(Ok(len)))
+    }
+
+    // go to the next action after heartbeat (the rest of partially read payload is kept)
+    pub(super) fn end_heartbeat(&mut self) {
+        if let Some(Action::ReadEvery(data, _)) = self.actions.get(self.action) {
+            if self.pos > 0 {
+                self.heartbeat_rest = Some((data.clone(), self.pos));
+            }
+        }
+        self.heartbeat = None;
+        self.action += 1;
+        self.pos = 0;
+    }
+}
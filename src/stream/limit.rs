@@ -0,0 +1,57 @@
+//! Memory limit of [`CheckedMockStream`].
+//!
+//! Scenarios, generated by fuzzers, can hold large fixtures or write unbounded data.
+//! With [`CheckedMockStreamBuilder::memory_limit`] stream operations fail with
+//! [`ErrorKind::OutOfMemory`] error instead of exhausting memory of the test process.
+
+use std::io::{Error, ErrorKind};
+
+use super::{Action, CheckedMockStream, CheckedMockStreamBuilder};
+
+impl CheckedMockStreamBuilder {
+    /// Limit memory, used by the stream (read and write payloads and retained written data).
+    ///
+    /// Read or write, which exceed the limit, return [`ErrorKind::OutOfMemory`] error.
+    /// Files, streamed with [`CheckedMockStreamBuilder::read_file`], are not counted.
+    pub fn memory_limit(mut self, bytes: usize) -> Self {
+        self.memory_limit = Some(bytes);
+        self
+    }
+}
+
+impl CheckedMockStream {
+    /// Gets a memory limit of the stream.
+    pub fn memory_limit(&self) -> Option<usize> {
+        self.memory_limit
+    }
+
+    // check limit before retaining additional written data
+    pub(super) fn check_memory(&self, additional: usize) -> Option<Error> {
+        let limit = self.memory_limit?;
+        let written = self.written.retained();
+        if self.fixtures + written + additional <= limit {
+            return None;
+        }
+        Some(Error::new(
+            ErrorKind::OutOfMemory,
+            format!(
+                "memory limit of {} bytes exceeded: fixtures {} bytes, written {} bytes, write {} bytes",
+                limit, self.fixtures, written, additional
+            ),
+        ))
+    }
+}
+
+// length of in-memory payloads (only calculated with memory limit)
+pub(super) fn fixtures_len(actions: &[Action], limit: Option<usize>) -> usize {
+    if limit.is_none() {
+        return 0;
+    }
+    actions
+        .iter()
+        .map(|action| match action {
+            Action::Read(data) | Action::Write(data) | Action::ReadEvery(data, _) => data.len(),
+            _ => 0,
+        })
+        .sum()
+}
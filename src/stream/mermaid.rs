@@ -0,0 +1,71 @@
+//! Export of recorded operations as Mermaid sequence diagram.
+//!
+//! ```text
+//! sequenceDiagram
+//!     participant C as client
+//!     participant S as stream
+//!     C->>S: 0.000ms write 6 bytes "PING\r\n"
+//!     S-->>C: 0.012ms read 7 bytes "+PONG\r\n" (took 0.005ms)
+//! ```
+//!
+//! Diagram can be rendered by GitHub/GitLab markdown (in `mermaid` code block) or Mermaid live editor.
+
+use std::fmt::Write as _;
+use std::time::Duration;
+
+use super::dump::preview;
+use super::recording::{RecordedOp, RecordingStream};
+
+impl<T> RecordingStream<T> {
+    /// Render recorded operations as Mermaid sequence diagram (client and stream) with
+    /// payload previews and timings (since recording start).
+    pub fn to_mermaid(&self) -> String {
+        let mut text = String::from(
+            "sequenceDiagram\n    participant C as client\n    participant S as stream\n",
+        );
+        for event in self.events() {
+            let (arrow, op) = match &event.op {
+                RecordedOp::Write(data) => ("C->>S", format!("write {}", preview(data))),
+                RecordedOp::WriteError(err) => {
+                    ("C-xS", format!("write error {:?}: {}", err.kind(), err))
+                }
+                RecordedOp::Read(data) => ("S-->>C", format!("read {}", preview(data))),
+                RecordedOp::ReadError(err) => {
+                    ("S--xC", format!("read error {:?}: {}", err.kind(), err))
+                }
+            };
+            let _ = write!(
+                text,
+                "    {}: {} {}",
+                arrow,
+                millis(event.at),
+                escape_text(&op)
+            );
+            if !event.elapsed.is_zero() {
+                let _ = write!(text, " (took {})", millis(event.elapsed));
+            }
+            text.push('\n');
+        }
+        text
+    }
+}
+
+fn millis(d: Duration) -> String {
+    format!("{:.3}ms", d.as_secs_f64() * 1000.0)
+}
+
+// escape characters with special meaning in message text (statement separator, entities and html)
+fn escape_text(s: &str) -> String {
+    let mut text = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '#' => text.push_str("#35;"),
+            ';' => text.push_str("#59;"),
+            '<' => text.push_str("#lt;"),
+            '>' => text.push_str("#gt;"),
+            '\n' => text.push(' '),
+            c => text.push(c),
+        }
+    }
+    text
+}
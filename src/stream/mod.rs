@@ -2,7 +2,11 @@
 #![warn(missing_docs)]
 
 use std::collections::VecDeque;
-use std::io::{self, Error, Read, Write};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, Error, Read, Write};
+use std::iter::FromIterator;
+use std::path::Path;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -18,15 +22,51 @@ use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
 #[cfg(feature = "tokio")]
 use tokio::time::{sleep_until, Instant, Sleep};
 
+#[cfg(not(feature = "tokio"))]
+use std::time::Instant;
+
 #[cfg(feature = "tokio")]
 use futures_core::{ready, Future};
 
+#[cfg(feature = "proptest")]
+pub use arbitrary::ScenarioParams;
+use calls::{Call, CallExpectations, Calls};
+pub use calls::{at_least, at_most, between, exactly, CallCount};
+use chaos::ChaosState;
+pub use chaos::{Chaos, CHAOS_SEED_ENV};
+pub use diff::{Divergence, MismatchError};
+use duplex::blocked_error;
+use error::ScriptedError;
+use limit::fixtures_len;
+use peer::{peer_closed_error, PeerState};
+pub use fault::{Fault, FaultStream};
+pub use fixture::{REQUEST_EXT, RESPONSE_EXT};
+pub use golden::BLESS_ENV;
+#[cfg(feature = "inline")]
+pub use payload::INLINE_CAPACITY;
+pub use payload::Payload;
+pub use progress::{CurrentAction, Progress};
+pub use recording::{RecordedEvent, RecordedOp, RecordingStream, DEFAULT_MIN_WAIT};
+pub use shared::SharedMockStream;
+use socket::{Socket, SocketConfig};
+pub use socket::{SocketCall, DEFAULT_TTL};
+pub use spec::{ActionKind, ActionSpec, PlannedAction};
+pub use state::StreamSnapshot;
+pub use sync_point::SyncPoint;
+use timing::{build_timings, Timing, TimingState};
+use utf8::Utf8State;
+#[cfg(feature = "insta")]
+pub use snapshot::snapshot;
+use written::Written;
+pub use written::{hash_bytes, WrittenMode};
+
 /// A fake stream for testing network applications backed by unchecked read/write buffers.
 #[derive(Clone, Debug)]
 pub struct SimpleMockStream {
     written: Vec<u8>,
     read: Vec<u8>,
     pos: usize,
+    socket: Socket,
 }
 
 impl SimpleMockStream {
@@ -41,6 +81,7 @@ impl SimpleMockStream {
             written: vec![],
             read: initial,
             pos: 0,
+            socket: Socket::default(),
         }
     }
 
@@ -50,6 +91,7 @@ impl SimpleMockStream {
             written: Vec::with_capacity(capacity),
             read: initial,
             pos: 0,
+            socket: Socket::default(),
         }
     }
 
@@ -74,6 +116,31 @@ impl SimpleMockStream {
         &self.written
     }
 
+    /// Gets a current position of written data (for [`SimpleMockStream::written_since`]).
+    pub fn mark(&self) -> usize {
+        self.written.len()
+    }
+
+    /// Gets a slice of bytes, written after the mark (empty if written buffer was reset after the mark).
+    pub fn written_since(&self, mark: usize) -> &[u8] {
+        self.written.get(mark..).unwrap_or_default()
+    }
+
+    /// Gets a capacity of written buffer.
+    pub fn written_capacity(&self) -> usize {
+        self.written.capacity()
+    }
+
+    /// Shrinks the capacity of written buffer as much as possible.
+    pub fn shrink_to_fit(&mut self) {
+        self.written.shrink_to_fit();
+    }
+
+    /// Clears written buffer and release its memory.
+    pub fn clear_capacity(&mut self) {
+        self.written = Vec::new();
+    }
+
     /// Gets a slice of bytes representing the all data that has been put to read.
     pub fn readed(&self) -> &[u8] {
         &self.read
@@ -85,7 +152,38 @@ impl SimpleMockStream {
     }
 }
 
+/// Creates a new mock stream with collected bytes to read.
+impl FromIterator<u8> for SimpleMockStream {
+    fn from_iter<I: IntoIterator<Item = u8>>(iter: I) -> Self {
+        SimpleMockStream::new(iter.into_iter().collect())
+    }
+}
+
+/// Creates a new mock stream with concatenated chunks to read.
+impl FromIterator<Vec<u8>> for SimpleMockStream {
+    fn from_iter<I: IntoIterator<Item = Vec<u8>>>(iter: I) -> Self {
+        SimpleMockStream::new(iter.into_iter().flatten().collect())
+    }
+}
+
+/// Appends bytes to read.
+impl Extend<u8> for SimpleMockStream {
+    fn extend<I: IntoIterator<Item = u8>>(&mut self, iter: I) {
+        self.read.extend(iter);
+    }
+}
+
+/// Appends chunks to read.
+impl Extend<Vec<u8>> for SimpleMockStream {
+    fn extend<I: IntoIterator<Item = Vec<u8>>>(&mut self, iter: I) {
+        for chunk in iter {
+            self.read.extend_from_slice(&chunk);
+        }
+    }
+}
+
 impl Read for SimpleMockStream {
+    #[allow(clippy::len_zero)]
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         if self.read.len() == self.pos || buf.len() == 0 {
             Ok(0)
@@ -150,18 +248,84 @@ impl AsyncWrite for SimpleMockStream {
 
 #[derive(Debug, Clone)]
 enum Action {
-    Read(Vec<u8>), // return on read
-    ReadError(Arc<Error>),
-    Write(Vec<u8>), // check write
-    WriteError(Arc<Error>),
+    Read(Payload), // return on read
+    ReadError(Arc<ScriptedError>),
+    Write(Payload), // check write
+    WriteError(Arc<ScriptedError>),
     Wait(Duration),
+    WaitRead(Duration),  // delay only read
+    WaitWrite(Duration), // delay only write
+    WaitUntil(Duration), // delay until offset from stream start
+    Barrier,             // synchronize read and write in duplex mode
+    PeerHalfClose,       // reads return EOF
+    PeerFullClose,       // reads return EOF, writes fail
+    SyncPoint(SyncPoint), // wait for other streams
+    ReadFile(Arc<Path>), // stream from file on read
+    ReadEvery(Payload, Duration), // return on read once per interval until write
+}
+
+/// Delivery mode of read actions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReadMode {
+    /// Read action can be delivered in multiple reads (stream semantics, default).
+    #[default]
+    Stream,
+    /// Every read action is delivered in exactly one read,
+    /// data, not fit into read buffer, is discarded (datagram semantics).
+    Message,
+    /// Every read action is delivered in exactly one read, read into too small buffer
+    /// returns `InvalidInput` error (and read action is not consumed).
+    MessageStrict,
+}
+
+/// Behavior of writes after the end of scenario.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExhaustedWrite {
+    /// Return `Ok(0)` and record divergence (default).
+    #[default]
+    Zero,
+    /// Return error of kind and record divergence.
+    Error(io::ErrorKind),
+    /// Accept and append to written data (without divergence).
+    Accept,
+    /// Block write (async write returns `Poll::Pending`, sync write returns `WouldBlock` error).
+    Block,
+}
+
+/// Behavior of writes, which mismatch expected data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MismatchPolicy {
+    /// Return `InvalidInput` error and record divergence (default).
+    #[default]
+    Error,
+    /// Panic immediately with divergence and scenario dump (at the call site of write).
+    Panic,
+    /// Record divergence and continue, as if the expected data is written
+    /// (divergences are reported by [`CheckedMockStream::assert_done`]).
+    Continue,
 }
 
 /// A builder for [`CheckedMockStream`]
 #[derive(Debug, Clone, Default)]
 pub struct CheckedMockStreamBuilder {
     actions: VecDeque<Action>,
-    writed: usize,
+    vars: Vec<(String, Vec<u8>)>,
+    chaos: Option<Chaos>,
+    written_mode: WrittenMode,
+    written_capacity: usize,
+    memory_limit: Option<usize>,
+    read_mode: ReadMode,
+    duplex: bool,
+    flush_gated: bool,
+    exhausted_write: ExhaustedWrite,
+    nonblocking: bool,
+    mismatch: MismatchPolicy,
+    utf8_writes: bool,
+    timings: Vec<(usize, Timing)>,
+    calls: CallExpectations,
+    socket: SocketConfig,
+    #[cfg(feature = "tokio")]
+    pending_reads: bool,
 }
 
 impl CheckedMockStreamBuilder {
@@ -170,28 +334,81 @@ impl CheckedMockStreamBuilder {
         CheckedMockStreamBuilder::default()
     }
 
+    /// Create a new empty [`CheckedMockStreamBuilder`] with preallocated actions queue
+    pub fn with_capacity(actions: usize) -> Self {
+        CheckedMockStreamBuilder {
+            actions: VecDeque::with_capacity(actions),
+            ..Default::default()
+        }
+    }
+
+    /// Reserve capacity for at least `additional` more actions
+    pub fn reserve(mut self, additional: usize) -> Self {
+        self.actions.reserve(additional);
+        self
+    }
+
+    /// Set initial capacity of written buffer (for [`CheckedMockStreamBuilder::build_cap`]
+    /// the maximum of hint and all wanted writes length is used)
+    pub fn written_capacity(mut self, capacity: usize) -> Self {
+        self.written_capacity = capacity;
+        self
+    }
+
     /// Queue an item to be returned by the stream read
-    pub fn read(mut self, value: Vec<u8>) -> Self {
-        self.actions.push_back(Action::Read(value));
+    pub fn read<P: Into<Payload>>(mut self, value: P) -> Self {
+        self.actions.push_back(Action::Read(value.into()));
         self
     }
 
     /// Queue an error to be returned by the stream read
     pub fn read_error(mut self, err: Error) -> Self {
-        self.actions.push_back(Action::ReadError(Arc::new(err)));
+        self.actions.push_back(Action::ReadError(Arc::new(ScriptedError::new(err))));
         self
     }
 
     /// Queue an item to be required to be written to the stream
-    pub fn write(mut self, want: Vec<u8>) -> Self {
-        self.writed += want.len();
-        self.actions.push_back(Action::Write(want));
+    pub fn write<P: Into<Payload>>(mut self, want: P) -> Self {
+        self.actions.push_back(Action::Write(want.into()));
         self
     }
 
+    /// Queue a formatted text to be returned by the stream read
+    ///
+    /// ```
+    /// # use netmock::stream::CheckedMockStreamBuilder;
+    /// use std::io::Read;
+    ///
+    /// let code = 200;
+    /// let mut stream = CheckedMockStreamBuilder::new()
+    ///     .read_fmt(format_args!("HTTP/1.1 {} OK\r\n", code))
+    ///     .build();
+    /// let mut text = String::new();
+    /// stream.read_to_string(&mut text).unwrap();
+    /// assert_eq!(text, "HTTP/1.1 200 OK\r\n");
+    /// ```
+    pub fn read_fmt(self, args: fmt::Arguments<'_>) -> Self {
+        self.read(fmt::format(args).into_bytes())
+    }
+
+    /// Queue a formatted text to be required to be written to the stream
+    pub fn write_fmt(self, args: fmt::Arguments<'_>) -> Self {
+        self.write(fmt::format(args).into_bytes())
+    }
+
+    /// Queue a text representation of value to be returned by the stream read
+    pub fn read_string<T: fmt::Display>(self, value: T) -> Self {
+        self.read(value.to_string().into_bytes())
+    }
+
+    /// Queue a text representation of value to be required to be written to the stream
+    pub fn write_string<T: fmt::Display>(self, value: T) -> Self {
+        self.write(value.to_string().into_bytes())
+    }
+
     /// Queue an error to be returned by the stream write
     pub fn write_error(mut self, err: Error) -> Self {
-        self.actions.push_back(Action::WriteError(Arc::new(err)));
+        self.actions.push_back(Action::WriteError(Arc::new(ScriptedError::new(err))));
         self
     }
 
@@ -201,27 +418,179 @@ impl CheckedMockStreamBuilder {
         self
     }
 
+    /// Set delivery mode of read actions (files are always streamed).
+    pub fn read_mode(mut self, mode: ReadMode) -> Self {
+        self.read_mode = mode;
+        self
+    }
+
+    /// Set behavior of writes after the end of scenario.
+    pub fn on_exhausted_write(mut self, policy: ExhaustedWrite) -> Self {
+        self.exhausted_write = policy;
+        self
+    }
+
+    /// Set behavior of writes, which mismatch expected data.
+    pub fn on_mismatch(mut self, policy: MismatchPolicy) -> Self {
+        self.mismatch = policy;
+        self
+    }
+
+    /// Sync operations return `WouldBlock` error (instead of sleep on the caller's thread) until the started wait is passed.
+    ///
+    /// Allows to test non-blocking (mio/poll-style) sync code without real sleeping.
+    pub fn nonblocking(mut self) -> Self {
+        self.nonblocking = true;
+        self
+    }
+
+    /// Async read returns `Poll::Pending` (instead of EOF), while the next action is write or write error,
+    /// and is woken, when the scenario advances (like a real socket, waiting for response).
+    ///
+    /// End of scenario is still returned as EOF. Sync reads are not affected.
+    #[cfg(feature = "tokio")]
+    pub fn pending_reads(mut self, pending: bool) -> Self {
+        self.pending_reads = pending;
+        self
+    }
+
+    /// Queue the stream read (but not write) to wait for a duration
+    ///
+    /// Write, reached the wait before read, is not delayed (the next read is delayed instead).
+    pub fn wait_read(mut self, duration: Duration) -> Self {
+        self.actions.push_back(Action::WaitRead(duration));
+        self
+    }
+
+    /// Queue the stream write (but not read) to wait for a duration
+    ///
+    /// Read, reached the wait before write, is not delayed (the next write is delayed instead).
+    pub fn wait_write(mut self, duration: Duration) -> Self {
+        self.actions.push_back(Action::WaitWrite(duration));
+        self
+    }
+
+    /// Queue the stream to wait until an offset from stream creation (or scenario reset) is elapsed
+    ///
+    /// Does not wait, if offset is already elapsed (see also [`CheckedMockStreamBuilder::at`]).
+    pub fn wait_until(mut self, offset: Duration) -> Self {
+        self.actions.push_back(Action::WaitUntil(offset));
+        self
+    }
+
+    /// Set storage mode of written data.
+    pub fn written_mode(mut self, mode: WrittenMode) -> Self {
+        self.written_mode = mode;
+        self
+    }
+
+    // actions for stream (with substituted placeholders and merged consecutive waits)
+    // and timing constraints of actions
+    fn into_actions(mut self) -> (Vec<Action>, Vec<Timing>) {
+        let timings = std::mem::take(&mut self.timings);
+        let mut actions: Vec<Action> = Vec::with_capacity(self.actions.len());
+        // index of built action for every builder action
+        let mut index = Vec::new();
+        for action in self.render() {
+            match (actions.last_mut(), action) {
+                (Some(Action::Wait(prev)), Action::Wait(wait))
+                | (Some(Action::WaitRead(prev)), Action::WaitRead(wait))
+                | (Some(Action::WaitWrite(prev)), Action::WaitWrite(wait)) => *prev += wait,
+                (_, action) => actions.push(action),
+            }
+            if !timings.is_empty() {
+                index.push(actions.len() - 1);
+            }
+        }
+        let timings = build_timings(&timings, &index, actions.len());
+        (actions, timings)
+    }
+
     /// Build the [`CheckedMockStream`]
+    ///
+    /// Consecutive waits are merged into a single wait action.
     pub fn build(self) -> CheckedMockStream {
-        CheckedMockStream {
-            actions: self.actions.into(),
-            written: Vec::new(),
-            action: 0,
-            pos: 0,
-            #[cfg(feature = "tokio")]
-            sleep: None,
-        }
+        let capacity = self.written_capacity;
+        self.build_with(|_| capacity)
     }
 
     /// Build the [`CheckedMockStream`] with preallocated writted buffer (for all wanted writes)
     pub fn build_cap(self) -> CheckedMockStream {
+        let capacity = self.written_capacity;
+        // do not preallocate over memory limit
+        let limit = self.memory_limit.unwrap_or(usize::MAX);
+        self.build_with(|actions| {
+            let writed: usize = actions
+                .iter()
+                .map(|action| match action {
+                    Action::Write(data) => data.len(),
+                    _ => 0,
+                })
+                .sum();
+            writed.max(capacity).min(limit)
+        })
+    }
+
+    fn build_with<F: FnOnce(&[Action]) -> usize>(self, capacity: F) -> CheckedMockStream {
+        let chaos = self.chaos.clone().map(ChaosState::new);
+        let mode = self.written_mode;
+        let memory_limit = self.memory_limit;
+        let read_mode = self.read_mode;
+        let duplex = self.duplex;
+        let flush_gated = self.flush_gated;
+        let exhausted_write = self.exhausted_write;
+        let nonblocking = self.nonblocking;
+        let mismatch = self.mismatch;
+        let utf8 = if self.utf8_writes {
+            Some(Utf8State::default())
+        } else {
+            None
+        };
+        #[cfg(feature = "tokio")]
+        let pending_reads = self.pending_reads;
+        let calls = self.calls;
+        let socket = self.socket;
+        let (actions, timings) = self.into_actions();
         CheckedMockStream {
-            actions: self.actions.into(),
-            written: Vec::with_capacity(self.writed),
+            fixtures: fixtures_len(&actions, memory_limit),
+            written: Written::new(mode, capacity(&actions)),
+            actions,
+            memory_limit,
+            read_mode,
+            duplex,
+            flush_gated,
+            exhausted_write,
+            nonblocking,
+            mismatch,
+            utf8,
+            timing: TimingState::new(timings),
+            calls: Calls::new(calls),
+            socket: Socket::new(socket),
+            unflushed: Vec::new(),
+            shutdown: false,
             action: 0,
+            write_action: 0,
             pos: 0,
+            divergences: Vec::new(),
+            file: None,
+            heartbeat: None,
+            heartbeat_rest: None,
+            started: Instant::now(),
+            peer: PeerState::Open,
+            sync_rounds: Vec::new(),
+            chaos,
+            read_deadline: None,
+            write_deadline: None,
+            #[cfg(feature = "tokio")]
+            read_timer: Timer::default(),
+            #[cfg(feature = "tokio")]
+            write_timer: Timer::default(),
             #[cfg(feature = "tokio")]
-            sleep: None,
+            pending_reads,
+            #[cfg(feature = "tokio")]
+            read_waker: None,
+            #[cfg(feature = "tokio")]
+            write_waker: None,
         }
     }
 }
@@ -229,14 +598,60 @@ impl CheckedMockStreamBuilder {
 /// A fake stream for testing network applications backed by read/write (checked) buffers.
 ///
 /// See [`CheckedMockStreamBuilder`] for more information.
-#[derive(Debug)]
+///
+/// # Cancel safety
+///
+/// Started wait is stored in the stream (as deadline of read or write direction), not in the
+/// operation future. If async read or write future is dropped during a wait (for example in
+/// `tokio::select!`), the next operation of the same direction resumes the remaining delay
+/// (wait is not restarted or skipped). Data is consumed only by completed operations.
 pub struct CheckedMockStream {
     actions: Vec<Action>,
-    written: Vec<u8>,
+    written: Written,
+    memory_limit: Option<usize>,
+    fixtures: usize,
+    read_mode: ReadMode,
+    duplex: bool,
+    flush_gated: bool,
+    exhausted_write: ExhaustedWrite,
+    nonblocking: bool,
+    mismatch: MismatchPolicy,
+    utf8: Option<Utf8State>,
+    timing: TimingState,
+    calls: Calls,
+    socket: Socket,
+    unflushed: Vec<u8>,
+    shutdown: bool,
     action: usize,
+    // write cursor in duplex mode (swapped with action in write step)
+    write_action: usize,
     pos: usize,
+    divergences: Vec<Divergence>,
+    file: Option<BufReader<File>>,
+    // the next tick of heartbeat action
+    heartbeat: Option<Instant>,
+    // unread rest of heartbeat payload, ended by write in the middle (payload and position)
+    heartbeat_rest: Option<(Payload, usize)>,
+    // start of scenario (for waits until offset)
+    started: Instant,
+    peer: PeerState,
+    // arrivals at sync points (action index and round)
+    sync_rounds: Vec<(usize, u64)>,
+    chaos: Option<ChaosState>,
+    // delay of the next read/write
+    read_deadline: Option<Instant>,
+    write_deadline: Option<Instant>,
     #[cfg(feature = "tokio")]
-    sleep: Option<Pin<Box<Sleep>>>,
+    read_timer: Timer,
+    #[cfg(feature = "tokio")]
+    write_timer: Timer,
+    #[cfg(feature = "tokio")]
+    pending_reads: bool,
+    // pending read/write, woken when the scenario advances
+    #[cfg(feature = "tokio")]
+    read_waker: Option<task::Waker>,
+    #[cfg(feature = "tokio")]
+    write_waker: Option<task::Waker>,
 }
 
 impl CheckedMockStream {
@@ -249,103 +664,602 @@ impl CheckedMockStream {
     /// Resets stream (but preserve already written).
     pub fn reset_actions(&mut self) {
         self.action = 0;
+        self.write_action = 0;
         self.pos = 0;
+        self.file = None;
+        self.heartbeat = None;
+        self.heartbeat_rest = None;
+        self.divergences.clear();
+        self.shutdown = false;
+        self.peer = PeerState::Open;
+        self.sync_rounds.clear();
+        self.calls.reset();
+        self.started = Instant::now();
+        self.clear_waits();
+        self.reset_timing();
+        #[cfg(feature = "tokio")]
+        self.wake();
+    }
+
+    /// Shutdown write direction (flush buffered writes, subsequent writes fail with `BrokenPipe`),
+    /// reads are not affected.
+    pub fn shutdown_write(&mut self) -> io::Result<()> {
+        self.flush_buffered()?;
+        self.shutdown = true;
+        Ok(())
+    }
+
+    /// Check if write direction is shutdown.
+    pub fn is_shutdown(&self) -> bool {
+        self.shutdown
+    }
+
+    /// Loads a new scenario from builder in place (but preserve already written and written mode).
+    pub fn set_actions(&mut self, builder: CheckedMockStreamBuilder) {
+        self.chaos = builder.chaos.clone().map(ChaosState::new);
+        self.memory_limit = builder.memory_limit;
+        self.read_mode = builder.read_mode;
+        self.duplex = builder.duplex;
+        self.flush_gated = builder.flush_gated;
+        self.exhausted_write = builder.exhausted_write;
+        self.nonblocking = builder.nonblocking;
+        self.mismatch = builder.mismatch;
+        self.utf8 = if builder.utf8_writes {
+            Some(Utf8State::default())
+        } else {
+            None
+        };
+        #[cfg(feature = "tokio")]
+        {
+            self.pending_reads = builder.pending_reads;
+        }
+        let calls = builder.calls;
+        self.socket = Socket::new(builder.socket);
+        let (actions, timings) = builder.into_actions();
+        self.actions = actions;
+        self.timing = TimingState::new(timings);
+        self.calls = Calls::new(calls);
+        self.fixtures = fixtures_len(&self.actions, self.memory_limit);
+        self.reset_actions();
+    }
+
+    /// Replaces stream with a new one, built from builder (including written buffer).
+    ///
+    /// Call-count expectations of the replaced stream are dropped without check (like in
+    /// [`CheckedMockStream::set_actions`]).
+    pub fn reload(&mut self, builder: CheckedMockStreamBuilder) {
+        self.calls.dismiss();
+        *self = builder.build();
     }
 
     /// Seek to action for stream.
     pub fn seek_action(&mut self, action: usize) {
         self.action = action;
+        self.write_action = action;
+        self.peer = PeerState::after(&self.actions[..action.min(self.actions.len())]);
+        self.sync_rounds.retain(|&(i, _)| i < action);
         self.pos = 0;
+        self.file = None;
+        self.heartbeat = None;
+        self.heartbeat_rest = None;
+        self.clear_waits();
+        self.reset_timing();
+        #[cfg(feature = "tokio")]
+        self.wake();
     }
 
     /// Resets written buffer.
     pub fn reset_written(&mut self) {
         self.written.clear();
+        self.unflushed.clear();
+        if let Some(ref mut utf8) = self.utf8 {
+            *utf8 = Utf8State::default();
+        }
     }
 
     /// Gets a slice of bytes representing the data that has been written.
+    ///
+    /// In [`WrittenMode::Segmented`] mode contiguous data is built on demand.
     pub fn written(&self) -> &[u8] {
-        &self.written
+        self.written.as_slice()
+    }
+
+    /// Gets a current position of written data (for [`CheckedMockStream::written_since`]).
+    pub fn mark(&self) -> usize {
+        self.written.len()
+    }
+
+    /// Gets a slice of bytes, written after the mark (empty if written buffer was reset after the mark).
+    ///
+    /// In [`WrittenMode::LastBytes`] and [`WrittenMode::LastWrites`] modes only retained data is returned.
+    pub fn written_since(&self, mark: usize) -> &[u8] {
+        self.written.since(mark)
+    }
+
+    /// Gets a capacity of written buffer.
+    pub fn written_capacity(&self) -> usize {
+        self.written.capacity()
+    }
+
+    /// Shrinks the capacity of written buffer (and actions) as much as possible.
+    pub fn shrink_to_fit(&mut self) {
+        self.actions.shrink_to_fit();
+        self.written.shrink_to_fit();
+    }
+
+    /// Clears written buffer and release its memory.
+    pub fn clear_capacity(&mut self) {
+        self.written.clear_capacity();
+    }
+
+    /// Gets a length of written data (including dropped in [`WrittenMode::LastBytes`],
+    /// [`WrittenMode::LastWrites`], [`WrittenMode::Discard`] and [`WrittenMode::Hash`] modes).
+    pub fn written_len(&self) -> usize {
+        self.written.len()
+    }
+
+    /// Gets a hash of written data (only in [`WrittenMode::Hash`] mode).
+    pub fn written_hash(&self) -> Option<u64> {
+        self.written.hash()
+    }
+
+    /// Gets an iterator over written data segments (without concatenation).
+    pub fn written_segments(&self) -> impl Iterator<Item = &[u8]> {
+        self.written.segments()
+    }
+
+    /// Gets a copy of written data.
+    pub fn written_to_vec(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        for segment in self.written.segments() {
+            data.extend_from_slice(segment);
+        }
+        data
     }
 }
 
-impl Read for CheckedMockStream {
-    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        if self.action >= self.actions.len() || buf.len() == 0 {
-            return Ok(0);
+// result of a stream operation step
+enum Step<T> {
+    Ready(io::Result<T>),
+    // wait before the next step
+    Wait(Duration),
+    // wait for the other direction (at barrier) or blocked by scenario
+    Blocked,
+    // wait for other streams at sync point (until round is passed)
+    Sync(SyncPoint, u64),
+}
+
+// run steps of sync operation until ready (blocked step returns `WouldBlock` error)
+fn run_step<T, F: FnMut() -> (Step<T>, bool)>(mut step: F) -> io::Result<T> {
+    loop {
+        let (step, nonblocking) = step();
+        match step {
+            Step::Ready(result) => return result,
+            Step::Wait(wait) => sync_wait(nonblocking, wait)?,
+            Step::Blocked => return Err(blocked_error()),
+            Step::Sync(point, round) => point.wait(round, nonblocking)?,
         }
-        match &self.actions[self.action] {
-            Action::ReadError(err) => {
-                self.action += 1;
-                Err(Error::new(err.kind(), err.to_string()))
-            },
-            Action::Read(data) => {
-                let len = std::cmp::min(data.len() - self.pos, buf.len());
-                let end = len + self.pos;
-                buf[..len].copy_from_slice(&data[self.pos..end]);
-                if end == data.len() {
-                    self.action += 1;
-                    self.pos = 0;
-                } else {
-                    self.pos = end;
-                }
-                Ok(len)
+    }
+}
+
+// wait in sync operation (sleep on the caller's thread or WouldBlock in non-blocking mode)
+fn sync_wait(nonblocking: bool, wait: Duration) -> io::Result<()> {
+    if nonblocking {
+        return Err(Error::new(
+            io::ErrorKind::WouldBlock,
+            format!("wait in progress: {:?} remaining", wait),
+        ));
+    }
+    std::thread::sleep(wait);
+    Ok(())
+}
+
+// remaining time before deadline (passed deadline is cleared)
+fn remaining(deadline: &mut Option<Instant>) -> Option<Duration> {
+    let wait = deadline.map(|d| d.saturating_duration_since(Instant::now()))?;
+    if wait.is_zero() {
+        *deadline = None;
+        return None;
+    }
+    Some(wait)
+}
+
+// extend deadline (or start a new one) by duration
+fn extend(deadline: &mut Option<Instant>, wait: Duration) {
+    let now = Instant::now();
+    *deadline = Some(deadline.map_or(now, |d| d.max(now)) + wait);
+}
+
+// move deadline to instant, if it's earlier
+fn postpone(deadline: &mut Option<Instant>, until: Instant) {
+    *deadline = Some(deadline.map_or(until, |d| d.max(until)));
+}
+
+impl CheckedMockStream {
+    // waits are applied to both directions (in duplex mode every direction passes wait itself)
+    fn start_wait(&mut self, wait: Duration, read: bool) -> Step<usize> {
+        self.action += 1;
+        if !self.duplex || read {
+            extend(&mut self.read_deadline, wait);
+        }
+        if !self.duplex || !read {
+            extend(&mut self.write_deadline, wait);
+        }
+        Step::Wait(wait)
+    }
+
+    // current action is moved from action by operation
+    fn advanced(&mut self, action: usize) {
+        if self.action != action {
+            self.advance_timing();
+            self.arrive_sync();
+        }
+    }
+
+    // wait until offset from scenario start (applied to directions like a generic wait, but
+    // pending waits are not extended, deadline is the latest of them)
+    fn wait_until(&mut self, offset: Duration, read: bool) -> Step<usize> {
+        let until = self.started + offset;
+        self.action += 1;
+        if !self.duplex || read {
+            postpone(&mut self.read_deadline, until);
+        }
+        if !self.duplex || !read {
+            postpone(&mut self.write_deadline, until);
+        }
+        let deadline = if read {
+            self.read_deadline
+        } else {
+            self.write_deadline
+        };
+        Step::Wait(deadline.map_or(Duration::ZERO, |d| {
+            d.saturating_duration_since(Instant::now())
+        }))
+    }
+
+    // drop started waits
+    fn clear_waits(&mut self) {
+        self.read_deadline = None;
+        self.write_deadline = None;
+        #[cfg(feature = "tokio")]
+        {
+            self.read_timer.armed = false;
+            self.write_timer.armed = false;
+        }
+    }
+
+    fn read_step(&mut self, buf: &mut [u8]) -> Step<usize> {
+        let action = self.action;
+        let step = self.read_next(buf);
+        self.advanced(action);
+        step
+    }
+
+    fn read_next(&mut self, buf: &mut [u8]) -> Step<usize> {
+        // zero-length read never waits
+        if buf.is_empty() {
+            return Step::Ready(Ok(0));
+        }
+        if let Some(step) = self.read_heartbeat_rest(buf) {
+            return step;
+        }
+        if let Some(wait) = remaining(&mut self.read_deadline) {
+            return Step::Wait(wait);
+        }
+        // buffer size is limited by chaos once per read
+        let mut limit = None;
+        loop {
+            if let Some(step) = self.skip_foreign(true) {
+                return step;
             }
-            Action::Wait(wait) => {
-                std::thread::sleep(*wait);
-                self.action += 1;
-                self.read(buf)
+            if self.peer > PeerState::Open {
+                return Step::Ready(Ok(0));
+            }
+            let len = match limit {
+                Some(len) => len,
+                None => match self.chaos_read(buf.len()) {
+                    Ok(len) => *limit.insert(len),
+                    Err(step) => return step,
+                },
+            };
+            let buf = &mut buf[..len];
+            if self.action >= self.actions.len() {
+                return Step::Ready(Ok(0));
             }
-            _ => Ok(0),
+            if let Some(err) = self.check_memory(0) {
+                return Step::Ready(Err(err));
+            }
+            self.check_timing(true);
+            return match &self.actions[self.action] {
+                Action::ReadError(err) => {
+                    self.action += 1;
+                    Step::Ready(Err(err.to_error()))
+                }
+                Action::Read(data) => {
+                    let len = std::cmp::min(data.len() - self.pos, buf.len());
+                    let end = len + self.pos;
+                    let message = match self.read_mode {
+                        ReadMode::Stream => false,
+                        ReadMode::Message => true,
+                        ReadMode::MessageStrict if len < data.len() - self.pos => {
+                            return Step::Ready(Err(Error::new(
+                                io::ErrorKind::InvalidInput,
+                                format!(
+                                    "read buffer too small for message: {} bytes, message {} bytes",
+                                    buf.len(),
+                                    data.len() - self.pos
+                                ),
+                            )));
+                        }
+                        ReadMode::MessageStrict => true,
+                    };
+                    buf[..len].copy_from_slice(&data[self.pos..end]);
+                    if end == data.len() || message {
+                        self.action += 1;
+                        self.pos = 0;
+                    } else {
+                        self.pos = end;
+                    }
+                    Step::Ready(Ok(len))
+                }
+                Action::ReadFile(path) => {
+                    let path = path.clone();
+                    self.read_file_step(path, buf)
+                }
+                Action::ReadEvery(data, interval) => {
+                    let (data, interval) = (data.clone(), *interval);
+                    match self.read_every_step(data, interval, buf) {
+                        Some(step) => step,
+                        // heartbeat is ended, read the next action
+                        None => continue,
+                    }
+                }
+                Action::Wait(wait) => {
+                    let wait = *wait;
+                    self.start_wait(wait, true)
+                }
+                Action::WaitRead(wait) => {
+                    let wait = *wait;
+                    self.action += 1;
+                    extend(&mut self.read_deadline, wait);
+                    Step::Wait(wait)
+                }
+                Action::WaitUntil(offset) => {
+                    let offset = *offset;
+                    self.wait_until(offset, true)
+                }
+                _ => {
+                    self.unexpected_read();
+                    Step::Ready(Ok(0))
+                }
+            };
         }
     }
-}
 
-impl Write for CheckedMockStream {
-    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        if self.action >= self.actions.len() || buf.len() == 0 {
-            return Ok(0);
+    fn write_step(&mut self, buf: &[u8]) -> Step<usize> {
+        if self.shutdown {
+            return Step::Ready(Err(Error::new(
+                io::ErrorKind::BrokenPipe,
+                "write after shutdown",
+            )));
+        }
+        if let Some(err) = self.check_utf8(buf) {
+            return Step::Ready(Err(err));
         }
+        let step = match self.buffer_write(buf) {
+            Some(step) => step,
+            None => self.verify_write(buf),
+        };
+        if let Step::Ready(Ok(len)) = step {
+            self.accept_utf8(&buf[..len]);
+        }
+        step
+    }
+
+    // verify write against the scenario
+    fn verify_write(&mut self, buf: &[u8]) -> Step<usize> {
+        if !self.duplex {
+            let action = self.action;
+            let step = self.write_next(buf);
+            self.advanced(action);
+            return step;
+        }
+        // write cursor is used as current action
+        std::mem::swap(&mut self.action, &mut self.write_action);
+        let action = self.action;
+        let step = self.write_next(buf);
+        self.advanced(action);
+        std::mem::swap(&mut self.action, &mut self.write_action);
+        step
+    }
+
+    fn write_next(&mut self, buf: &[u8]) -> Step<usize> {
+        // zero-length write never waits
+        if buf.is_empty() {
+            return Step::Ready(Ok(0));
+        }
+        if let Some(wait) = remaining(&mut self.write_deadline) {
+            return Step::Wait(wait);
+        }
+        if let Some(step) = self.skip_foreign(false) {
+            return step;
+        }
+        if self.peer == PeerState::Closed {
+            return Step::Ready(Err(peer_closed_error()));
+        }
+        if let Some(step) = self.chaos_write(buf.len()) {
+            return step;
+        }
+        if self.action >= self.actions.len() {
+            return self.exhausted_write(buf);
+        }
+        self.check_timing(false);
         match &self.actions[self.action] {
             Action::WriteError(err) => {
                 self.action += 1;
-                Err(Error::new(err.kind(), err.to_string()))
-            },
+                Step::Ready(Err(err.to_error()))
+            }
             Action::Write(data) => {
-                if data == buf {
-                    match self.written.write(buf) {
-                        Ok(written) => {
-                            self.action += 1;
-                            Ok(written)
-                        }
-                        Err(err) => Err(err),
-                    }
-                } else if data.len() < buf.len() && data == &buf[..data.len()] {
-                    match self.written.write(&buf[..data.len()]) {
-                        Ok(written) => {
-                            self.action += 1;
-                            Ok(written)
-                        }
-                        Err(err) => Err(err),
-                    }
+                let data: &[u8] = data;
+                // single memcmp of expected prefix
+                let len = if buf.starts_with(data) {
+                    data.len()
                 } else {
-                    Err(Error::new(
-                        io::ErrorKind::InvalidInput,
-                        "mismatch written data",
-                    ))
+                    let len = data.len().min(buf.len());
+                    if let Some(err) = self.mismatched_write(data.to_vec(), buf) {
+                        return Step::Ready(Err(err));
+                    }
+                    len
+                };
+                if let Some(err) = self.check_memory(len) {
+                    return Step::Ready(Err(err));
                 }
+                self.written.push(&buf[..len]);
+                self.action += 1;
+                Step::Ready(Ok(len))
             }
             Action::Wait(wait) => {
-                std::thread::sleep(*wait);
+                let wait = *wait;
+                self.start_wait(wait, false)
+            }
+            Action::WaitWrite(wait) => {
+                let wait = *wait;
                 self.action += 1;
-                self.write(buf)
+                extend(&mut self.write_deadline, wait);
+                Step::Wait(wait)
+            }
+            Action::WaitUntil(offset) => {
+                let offset = *offset;
+                self.wait_until(offset, false)
+            }
+            _ => {
+                self.unexpected_write(buf);
+                Step::Ready(Ok(0))
+            }
+        }
+    }
+
+    // write after the end of scenario
+    fn exhausted_write(&mut self, buf: &[u8]) -> Step<usize> {
+        match self.exhausted_write {
+            ExhaustedWrite::Zero => {
+                self.unexpected_write(buf);
+                Step::Ready(Ok(0))
             }
-            _ => Ok(0),
+            ExhaustedWrite::Error(kind) => {
+                self.unexpected_write(buf);
+                Step::Ready(Err(Error::new(
+                    kind,
+                    format!(
+                        "unexpected write after end of scenario: {}",
+                        dump::preview(buf)
+                    ),
+                )))
+            }
+            ExhaustedWrite::Accept => {
+                if let Some(err) = self.check_memory(buf.len()) {
+                    return Step::Ready(Err(err));
+                }
+                self.written.push(buf);
+                Step::Ready(Ok(buf.len()))
+            }
+            ExhaustedWrite::Block => Step::Blocked,
         }
     }
+}
+
+impl Read for CheckedMockStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        run_step(|| {
+            let step = self.call_step(Call::Read, |stream| stream.read_step(buf));
+            (step, self.nonblocking)
+        })
+    }
+}
+
+impl Write for CheckedMockStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        run_step(|| {
+            let step = self.call_step(Call::Write, |stream| stream.write_step(buf));
+            (step, self.nonblocking)
+        })
+    }
 
     fn flush(&mut self) -> io::Result<()> {
-        self.written.flush()
+        run_step(|| {
+            let step = self.call_step(Call::Flush, CheckedMockStream::flush_step);
+            (step, self.nonblocking)
+        })
+    }
+}
+
+impl CheckedMockStream {
+    // flush implied by shutdown (not counted as a call)
+    fn flush_buffered(&mut self) -> io::Result<()> {
+        run_step(|| (self.flush_step(), self.nonblocking))
+    }
+}
+
+// wait timer of a single direction
+#[cfg(feature = "tokio")]
+#[derive(Debug, Default)]
+struct Timer {
+    sleep: Option<Pin<Box<Sleep>>>,
+    armed: bool,
+}
+
+#[cfg(feature = "tokio")]
+impl Timer {
+    fn poll(&mut self, cx: &mut task::Context<'_>) -> Poll<()> {
+        if self.armed {
+            if let Some(ref mut sleep) = self.sleep {
+                ready!(sleep.as_mut().poll(cx));
+            }
+            self.armed = false;
+        }
+        Poll::Ready(())
+    }
+
+    // arm timer (reuse allocated timer, if exist)
+    fn start(&mut self, wait: Duration) {
+        let deadline = Instant::now() + wait;
+        match self.sleep {
+            Some(ref mut sleep) => sleep.as_mut().reset(deadline),
+            None => self.sleep = Some(Box::pin(sleep_until(deadline))),
+        }
+        self.armed = true;
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl CheckedMockStream {
+    // read must wait for the scenario advance
+    fn read_blocked(&self) -> bool {
+        let next = self.actions[self.action.min(self.actions.len())..]
+            .iter()
+            .find(|action| !matches!(action, Action::WaitWrite(_)));
+        self.pending_reads
+            && !self.duplex
+            && self.peer == PeerState::Open
+            && matches!(next, Some(Action::Write(_) | Action::WriteError(_)))
+    }
+
+    // scenario is changed, wake all pending operations
+    fn wake(&mut self) {
+        self.wake_reader();
+        self.wake_writer();
+    }
+
+    fn wake_reader(&mut self) {
+        if let Some(waker) = self.read_waker.take() {
+            waker.wake();
+        }
+    }
+
+    fn wake_writer(&mut self) {
+        if let Some(waker) = self.write_waker.take() {
+            waker.wake();
+        }
     }
 }
 
@@ -356,44 +1270,32 @@ impl AsyncRead for CheckedMockStream {
         cx: &mut task::Context<'_>,
         buf: &mut ReadBuf<'_>,
     ) -> Poll<io::Result<()>> {
-        if let Some(ref mut sleep) = self.sleep {
-            ready!(Pin::new(sleep).poll(cx));
-            self.sleep = None;
-        }
-
-        if self.action >= self.actions.len() || buf.remaining() == 0 {
-            return Poll::Ready(Ok(()));
+        if self.chaos_pending(cx, true) {
+            return Poll::Pending;
         }
-        let result: io::Result<()>;
-        match &self.actions[self.action] {
-            Action::ReadError(err) => {
-                result = Err(Error::new(err.kind(), err.to_string()));
+        loop {
+            ready!(self.read_timer.poll(cx));
+            if self.read_blocked() {
+                self.read_waker = Some(cx.waker().clone());
+                return Poll::Pending;
             }
-            Action::Read(data) => {
-                let len = std::cmp::min(data.len() - self.pos, buf.remaining());
-                let end = len + self.pos;
-                // buf[..len].copy_from_slice(&data[self.pos..end]);
-                buf.put_slice(&data[self.pos..end]);
-                if end == data.len() {
-                    self.action += 1;
-                    self.pos = 0;
-                } else {
-                    self.pos = end;
+            let unfilled = buf.initialize_unfilled();
+            let step = self.call_step(Call::Read, |stream| stream.read_step(unfilled));
+            self.wake_writer();
+            match step {
+                Step::Ready(result) => return Poll::Ready(result.map(|len| buf.advance(len))),
+                Step::Wait(wait) => self.read_timer.start(wait),
+                Step::Blocked => {
+                    self.read_waker = Some(cx.waker().clone());
+                    return Poll::Pending;
+                }
+                Step::Sync(point, round) => {
+                    if !point.register(round, cx.waker()) {
+                        return Poll::Pending;
+                    }
                 }
-                return Poll::Ready(Ok(()));
-            }
-            Action::Wait(wait) => {
-                self.sleep = Some(Box::pin(sleep_until(Instant::now() + *wait)));
-                cx.waker().wake_by_ref();
-                self.action += 1;
-
-                return Poll::Pending;
             }
-            _ => return Poll::Ready(Ok(())),
         }
-
-        self.action += 1;
-        Poll::Ready(result)
     }
 }
 
@@ -404,67 +1306,122 @@ impl AsyncWrite for CheckedMockStream {
         cx: &mut task::Context<'_>,
         buf: &[u8],
     ) -> Poll<io::Result<usize>> {
-        if let Some(ref mut sleep) = self.sleep {
-            ready!(Pin::new(sleep).poll(cx));
-            self.sleep = None;
-        }
-
-        if self.action >= self.actions.len() || buf.len() == 0 {
-            return Poll::Ready(Ok(0));
+        if self.chaos_pending(cx, false) {
+            return Poll::Pending;
         }
-        let result: io::Result<usize>;
-        match &self.actions[self.action] {
-            Action::WriteError(err) => {
-                result = Err(Error::new(err.kind(), err.to_string()))
-            }
-            Action::Write(data) => {
-                let len: usize;
-                if data == buf {
-                    len = buf.len();
-                } else if data.len() < buf.len() && data == &buf[..data.len()] {
-                    len = data.len();
-                } else {
-                    return Poll::Ready(Err(Error::new(
-                        io::ErrorKind::InvalidInput,
-                        "mismatch written data",
-                    )));
+        loop {
+            ready!(self.write_timer.poll(cx));
+            let step = self.call_step(Call::Write, |stream| stream.write_step(buf));
+            self.wake_reader();
+            match step {
+                Step::Ready(result) => return Poll::Ready(result),
+                Step::Wait(wait) => self.write_timer.start(wait),
+                Step::Blocked => {
+                    self.write_waker = Some(cx.waker().clone());
+                    return Poll::Pending;
                 }
-
-                match self.written.write_all(&buf[..len]) {
-                    Ok(_) => {
-                        result = Ok(len);
-                    }
-                    Err(err) => {
-                        return Poll::Ready(Err(err))
+                Step::Sync(point, round) => {
+                    if !point.register(round, cx.waker()) {
+                        return Poll::Pending;
                     }
                 }
             }
-            Action::Wait(wait) => {
-                self.sleep = Some(Box::pin(sleep_until(Instant::now() + *wait)));
-                cx.waker().wake_by_ref();
-
-                self.action += 1;
-
-                return Poll::Pending;
-            }
-            _ => {
-                return Poll::Ready(Ok(0))
-            }
         }
+    }
 
-        self.action += 1;
-        Poll::Ready(result)
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
+        self.poll_flush_buffered(cx, true)
     }
 
-    fn poll_flush(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<io::Result<()>> {
+    fn poll_shutdown(
+        mut self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        ready!(self.poll_flush_buffered(cx, false))?;
+        self.shutdown = true;
         Poll::Ready(Ok(()))
     }
+}
 
-    fn poll_shutdown(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<io::Result<()>> {
-        Poll::Ready(Ok(()))
+#[cfg(feature = "tokio")]
+impl CheckedMockStream {
+    // poll flush (not counted as a call, if implied by shutdown)
+    fn poll_flush_buffered(
+        &mut self,
+        cx: &mut task::Context<'_>,
+        counted: bool,
+    ) -> Poll<io::Result<()>> {
+        loop {
+            ready!(self.write_timer.poll(cx));
+            let step = if counted {
+                self.call_step(Call::Flush, CheckedMockStream::flush_step)
+            } else {
+                self.flush_step()
+            };
+            self.wake_reader();
+            match step {
+                Step::Ready(result) => return Poll::Ready(result),
+                Step::Wait(wait) => self.write_timer.start(wait),
+                Step::Blocked => {
+                    self.write_waker = Some(cx.waker().clone());
+                    return Poll::Pending;
+                }
+                Step::Sync(point, round) => {
+                    if !point.register(round, cx.waker()) {
+                        return Poll::Pending;
+                    }
+                }
+            }
+        }
     }
 }
 
+#[cfg(feature = "proptest")]
+mod arbitrary;
+pub(crate) mod assert;
+mod calls;
+mod chaos;
+mod codegen;
+mod diff;
+mod dump;
+pub(crate) mod dsl;
+mod duplex;
+mod error;
+mod fault;
+mod file;
+mod fixture;
+mod flush;
+mod golden;
+mod heartbeat;
+#[cfg(feature = "har")]
+mod har;
+mod limit;
+mod mermaid;
+#[cfg(feature = "pcap")]
+mod pcap;
+mod payload;
+mod peer;
+#[cfg(feature = "pretty")]
+mod pretty;
+mod progress;
+mod recording;
+#[cfg(feature = "serde")]
+mod serialize;
+mod shared;
+mod socket;
+mod spec;
+#[cfg(feature = "insta")]
+mod snapshot;
+mod splice;
+mod state;
+mod sync_point;
+mod template;
+mod timing;
+mod transcript;
+mod utf8;
+mod util;
+mod written;
+
 #[cfg(test)]
 mod tests_sync;
 
@@ -0,0 +1,175 @@
+//! Payload storage of scenario read/write actions.
+//!
+//! Payloads are shared (behind `Arc`), so cloning of builders with large fixtures is cheap.
+//! Static payloads (like byte string literals) are stored by reference (without allocation and copy).
+//! With `inline` feature small payloads are stored inline (without heap allocation).
+
+use std::borrow::Cow;
+use std::fmt;
+use std::ops::{Deref, Range};
+use std::sync::Arc;
+
+#[cfg(feature = "bytes")]
+use bytes::Bytes;
+
+#[cfg(feature = "mmap")]
+use memmap2::Mmap;
+
+/// Payload of scenario read/write action.
+///
+/// Created from `Vec<u8>`, `Arc<[u8]>`, `&'static [u8]`, `Cow<'static, [u8]>` (or without copy from `bytes::Bytes` with `bytes` feature
+/// and `memmap2::Mmap` with `mmap` feature). Clone and slice are cheap (without data copy).
+#[derive(Clone)]
+pub struct Payload(Repr);
+
+/// Maximum length of inline payload (with `inline` feature).
+#[cfg(feature = "inline")]
+pub const INLINE_CAPACITY: usize = 32;
+
+#[derive(Clone)]
+enum Repr {
+    #[cfg(feature = "inline")]
+    Inline([u8; INLINE_CAPACITY], u8),
+    Static(&'static [u8]),
+    Shared(Arc<[u8]>, Range<usize>),
+    #[cfg(feature = "bytes")]
+    Bytes(Bytes),
+    #[cfg(feature = "mmap")]
+    Mmap(Arc<Mmap>, Range<usize>),
+}
+
+impl Payload {
+    /// Get payload data.
+    pub fn as_slice(&self) -> &[u8] {
+        match &self.0 {
+            #[cfg(feature = "inline")]
+            Repr::Inline(data, len) => &data[..*len as usize],
+            Repr::Static(data) => data,
+            Repr::Shared(data, range) => &data[range.clone()],
+            #[cfg(feature = "bytes")]
+            Repr::Bytes(data) => data,
+            #[cfg(feature = "mmap")]
+            Repr::Mmap(data, range) => &data[range.clone()],
+        }
+    }
+
+    /// Get part of payload (without copy).
+    pub fn slice(&self, range: Range<usize>) -> Payload {
+        assert!(
+            range.start <= range.end && range.end <= self.len(),
+            "payload slice {:?} out of range for length {}",
+            range,
+            self.len()
+        );
+        let shift = |r: &Range<usize>| r.start + range.start..r.start + range.end;
+        match &self.0 {
+            #[cfg(feature = "inline")]
+            Repr::Inline(data, _) => Payload::inline(&data[range]).unwrap(),
+            Repr::Static(data) => Payload(Repr::Static(&data[range])),
+            Repr::Shared(data, r) => Payload(Repr::Shared(data.clone(), shift(r))),
+            #[cfg(feature = "bytes")]
+            Repr::Bytes(data) => Payload(Repr::Bytes(data.slice(range))),
+            #[cfg(feature = "mmap")]
+            Repr::Mmap(data, r) => Payload(Repr::Mmap(data.clone(), shift(r))),
+        }
+    }
+}
+
+#[cfg(feature = "inline")]
+impl Payload {
+    fn inline(data: &[u8]) -> Option<Payload> {
+        if data.len() > INLINE_CAPACITY {
+            return None;
+        }
+        let mut buf = [0; INLINE_CAPACITY];
+        buf[..data.len()].copy_from_slice(data);
+        Some(Payload(Repr::Inline(buf, data.len() as u8)))
+    }
+}
+
+impl Deref for Payload {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl AsRef<[u8]> for Payload {
+    fn as_ref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl PartialEq for Payload {
+    fn eq(&self, other: &Payload) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl Eq for Payload {}
+
+impl fmt::Debug for Payload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_slice(), f)
+    }
+}
+
+impl From<Vec<u8>> for Payload {
+    fn from(data: Vec<u8>) -> Self {
+        #[cfg(feature = "inline")]
+        if let Some(payload) = Payload::inline(&data) {
+            return payload;
+        }
+        Payload::from(Arc::<[u8]>::from(data))
+    }
+}
+
+impl From<Arc<[u8]>> for Payload {
+    fn from(data: Arc<[u8]>) -> Self {
+        let len = data.len();
+        Payload(Repr::Shared(data, 0..len))
+    }
+}
+
+impl From<&'static [u8]> for Payload {
+    fn from(data: &'static [u8]) -> Self {
+        Payload(Repr::Static(data))
+    }
+}
+
+impl<const N: usize> From<&'static [u8; N]> for Payload {
+    fn from(data: &'static [u8; N]) -> Self {
+        Payload(Repr::Static(data))
+    }
+}
+
+impl From<Cow<'static, [u8]>> for Payload {
+    fn from(data: Cow<'static, [u8]>) -> Self {
+        match data {
+            Cow::Borrowed(data) => Payload::from(data),
+            Cow::Owned(data) => Payload::from(data),
+        }
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl From<Bytes> for Payload {
+    fn from(data: Bytes) -> Self {
+        Payload(Repr::Bytes(data))
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl From<Mmap> for Payload {
+    fn from(data: Mmap) -> Self {
+        let len = data.len();
+        Payload(Repr::Mmap(Arc::new(data), 0..len))
+    }
+}
+
+impl From<Payload> for Vec<u8> {
+    fn from(payload: Payload) -> Self {
+        payload.as_slice().to_vec()
+    }
+}
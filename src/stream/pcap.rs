@@ -0,0 +1,188 @@
+//! Loader of captured TCP conversation (classic pcap file) into [`CheckedMockStreamBuilder`].
+//!
+//! Supported link types: Ethernet (with VLAN tags), Linux cooked capture (SLL), raw IP and BSD loopback.
+//! Server to client payloads become reads, client to server payloads become write expectations.
+//! Retransmitted segments are dropped (by TCP sequence number), out-of-order segments are not reordered.
+
+use std::fs;
+use std::io::{self, Error, ErrorKind};
+use std::path::Path;
+
+use super::CheckedMockStreamBuilder;
+
+const LINKTYPE_NULL: u32 = 0;
+const LINKTYPE_ETHERNET: u32 = 1;
+const LINKTYPE_RAW: u32 = 101;
+const LINKTYPE_LINUX_SLL: u32 = 113;
+const LINKTYPE_IPV4: u32 = 228;
+const LINKTYPE_IPV6: u32 = 229;
+
+impl CheckedMockStreamBuilder {
+    /// Load TCP conversation between `client_port` and `server_port` from pcap file.
+    pub fn from_pcap_file<P: AsRef<Path>>(
+        path: P,
+        client_port: u16,
+        server_port: u16,
+    ) -> io::Result<Self> {
+        let data = fs::read(path)?;
+        CheckedMockStreamBuilder::from_pcap(&data, client_port, server_port)
+    }
+
+    /// Load TCP conversation between `client_port` and `server_port` from pcap file content.
+    pub fn from_pcap(data: &[u8], client_port: u16, server_port: u16) -> io::Result<Self> {
+        let mut builder = CheckedMockStreamBuilder::new();
+        let mut client_seq: Option<u32> = None;
+        let mut server_seq: Option<u32> = None;
+        for segment in parse_pcap(data)? {
+            let (to_server, next_seq) =
+                if segment.src_port == client_port && segment.dst_port == server_port {
+                    (true, &mut client_seq)
+                } else if segment.src_port == server_port && segment.dst_port == client_port {
+                    (false, &mut server_seq)
+                } else {
+                    continue;
+                };
+            if segment.syn {
+                *next_seq = Some(segment.seq.wrapping_add(1));
+                continue;
+            }
+            if segment.payload.is_empty() {
+                continue;
+            }
+            let mut payload = segment.payload;
+            if let Some(expected) = *next_seq {
+                let offset = expected.wrapping_sub(segment.seq);
+                if (offset as i32) < 0 {
+                    // gap (lost segment in capture), accept as is
+                } else if offset as usize >= payload.len() {
+                    continue; // retransmission
+                } else {
+                    payload = &payload[offset as usize..];
+                }
+            }
+            *next_seq = Some(segment.seq.wrapping_add(segment.payload.len() as u32));
+            builder = if to_server {
+                builder.write(payload.to_vec())
+            } else {
+                builder.read(payload.to_vec())
+            };
+        }
+        Ok(builder)
+    }
+}
+
+struct Segment<'a> {
+    src_port: u16,
+    dst_port: u16,
+    seq: u32,
+    syn: bool,
+    payload: &'a [u8],
+}
+
+fn invalid(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
+
+fn parse_pcap(data: &[u8]) -> io::Result<Vec<Segment<'_>>> {
+    if data.len() < 24 {
+        return Err(invalid("pcap: header truncated"));
+    }
+    let big_endian = match data[..4] {
+        [0xd4, 0xc3, 0xb2, 0xa1] | [0x4d, 0x3c, 0xb2, 0xa1] => false,
+        [0xa1, 0xb2, 0xc3, 0xd4] | [0xa1, 0xb2, 0x3c, 0x4d] => true,
+        [0x0a, 0x0d, 0x0d, 0x0a] => return Err(invalid("pcap: pcapng format is not supported")),
+        _ => return Err(invalid("pcap: invalid magic")),
+    };
+    let u32_at = |buf: &[u8], pos: usize| {
+        let b = [buf[pos], buf[pos + 1], buf[pos + 2], buf[pos + 3]];
+        if big_endian {
+            u32::from_be_bytes(b)
+        } else {
+            u32::from_le_bytes(b)
+        }
+    };
+    let linktype = u32_at(data, 20) & 0xffff;
+
+    let mut segments = Vec::new();
+    let mut pos = 24;
+    while pos < data.len() {
+        if data.len() < pos + 16 {
+            return Err(invalid("pcap: record header truncated"));
+        }
+        let caplen = u32_at(data, pos + 8) as usize;
+        let start = pos + 16;
+        let packet = data
+            .get(start..start + caplen)
+            .ok_or_else(|| invalid("pcap: record truncated"))?;
+        pos = start + caplen;
+        if let Some(ip) = link_payload(linktype, packet)? {
+            if let Some(segment) = parse_ip(ip) {
+                segments.push(segment);
+            }
+        }
+    }
+    Ok(segments)
+}
+
+// strip link layer header, returns IP packet (None for non-IP frames)
+fn link_payload(linktype: u32, packet: &[u8]) -> io::Result<Option<&[u8]>> {
+    let ip = match linktype {
+        LINKTYPE_ETHERNET => {
+            let mut offset = 12;
+            loop {
+                let ethertype = match packet.get(offset..offset + 2) {
+                    Some(b) => u16::from_be_bytes([b[0], b[1]]),
+                    None => return Ok(None),
+                };
+                match ethertype {
+                    0x8100 | 0x88a8 => offset += 4, // VLAN
+                    0x0800 | 0x86dd => break,
+                    _ => return Ok(None),
+                }
+            }
+            &packet[offset + 2..]
+        }
+        LINKTYPE_LINUX_SLL => packet.get(16..).unwrap_or_default(),
+        LINKTYPE_NULL => packet.get(4..).unwrap_or_default(),
+        LINKTYPE_RAW | LINKTYPE_IPV4 | LINKTYPE_IPV6 => packet,
+        _ => return Err(invalid("pcap: unsupported link type")),
+    };
+    Ok(Some(ip))
+}
+
+fn parse_ip(ip: &[u8]) -> Option<Segment<'_>> {
+    let version = ip.first()? >> 4;
+    let tcp = match version {
+        4 => {
+            let ihl = ((ip[0] & 0x0f) as usize) * 4;
+            let total = u16::from_be_bytes([*ip.get(2)?, *ip.get(3)?]) as usize;
+            if *ip.get(9)? != 6 {
+                return None;
+            }
+            // total length may be 0 with TSO, use captured length
+            let end = if total == 0 {
+                ip.len()
+            } else {
+                total.min(ip.len())
+            };
+            ip.get(ihl..end)?
+        }
+        6 => {
+            let payload_len = u16::from_be_bytes([*ip.get(4)?, *ip.get(5)?]) as usize;
+            if *ip.get(6)? != 6 {
+                return None; // extension headers are not supported
+            }
+            let end = (40 + payload_len).min(ip.len());
+            ip.get(40..end)?
+        }
+        _ => return None,
+    };
+    let data_offset = ((*tcp.get(12)? >> 4) as usize) * 4;
+    Some(Segment {
+        src_port: u16::from_be_bytes([tcp[0], tcp[1]]),
+        dst_port: u16::from_be_bytes([tcp[2], tcp[3]]),
+        seq: u32::from_be_bytes([tcp[4], tcp[5], tcp[6], tcp[7]]),
+        syn: tcp[13] & 0x02 != 0,
+        payload: tcp.get(data_offset..)?,
+    })
+}
@@ -0,0 +1,90 @@
+//! Peer shutdown simulation (asymmetric TCP close).
+//!
+//! After [`CheckedMockStreamBuilder::peer_half_close`] is reached, reads return EOF (read actions
+//! before it are delivered first), while writes are still verified against the scenario.
+//! After [`CheckedMockStreamBuilder::peer_full_close`] is reached, writes also fail with `BrokenPipe`.
+
+use std::io::{Error, ErrorKind};
+
+use super::{Action, CheckedMockStream, CheckedMockStreamBuilder};
+
+// shutdown state of the peer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub(super) enum PeerState {
+    #[default]
+    Open,
+    // peer shutdown write direction (reads return EOF)
+    HalfClosed,
+    // peer closed connection (writes fail)
+    Closed,
+}
+
+impl PeerState {
+    // state after passed actions
+    pub(super) fn after(actions: &[Action]) -> Self {
+        actions
+            .iter()
+            .filter_map(|action| match action {
+                Action::PeerHalfClose => Some(PeerState::HalfClosed),
+                Action::PeerFullClose => Some(PeerState::Closed),
+                _ => None,
+            })
+            .max()
+            .unwrap_or_default()
+    }
+}
+
+impl CheckedMockStreamBuilder {
+    /// Queue the peer to shutdown its write direction: reads return EOF, writes are verified as before.
+    pub fn peer_half_close(mut self) -> Self {
+        self.actions.push_back(Action::PeerHalfClose);
+        self
+    }
+
+    /// Queue the peer to close connection: reads return EOF, writes fail with `BrokenPipe`.
+    pub fn peer_full_close(mut self) -> Self {
+        self.actions.push_back(Action::PeerFullClose);
+        self
+    }
+}
+
+impl CheckedMockStream {
+    /// Check if the peer shutdown its write direction (reads return EOF).
+    pub fn is_peer_half_closed(&self) -> bool {
+        self.peer >= PeerState::HalfClosed
+    }
+
+    /// Check if the peer closed connection (writes fail with `BrokenPipe`).
+    pub fn is_peer_closed(&self) -> bool {
+        self.peer == PeerState::Closed
+    }
+
+    // pass shutdown action of the peer
+    pub(super) fn close_peer(&mut self, state: PeerState) {
+        self.peer = self.peer.max(state);
+    }
+
+    // pass shutdown actions of the peer at write cursor, returns `true` if the peer closed connection
+    // (for buffered writes, which don't advance the scenario)
+    pub(super) fn peer_closed_on_write(&mut self) -> bool {
+        let cursor = if self.duplex {
+            &mut self.write_action
+        } else {
+            &mut self.action
+        };
+        while let Some(action) = self.actions.get(*cursor) {
+            let state = match action {
+                Action::PeerHalfClose => PeerState::HalfClosed,
+                Action::PeerFullClose => PeerState::Closed,
+                _ => break,
+            };
+            self.peer = self.peer.max(state);
+            *cursor += 1;
+        }
+        self.peer == PeerState::Closed
+    }
+}
+
+pub(super) fn peer_closed_error() -> Error {
+    Error::new(ErrorKind::BrokenPipe, "connection closed by peer")
+}
@@ -0,0 +1,106 @@
+//! Colored diff of expected and written data (`pretty` feature).
+//!
+//! Text view is a line diff of escaped data, hex view shows rows around the first mismatch
+//! with mismatched bytes highlighted. Colors are disabled with `NO_COLOR` environment variable.
+
+use std::fmt::Write as _;
+
+use similar::{ChangeTag, TextDiff};
+
+use super::util::escape;
+
+// rows of hex view before mismatch row
+const HEX_BEFORE: usize = 1;
+
+// rows of hex view
+const HEX_ROWS: usize = 4;
+
+struct Colors {
+    expected: &'static str,
+    written: &'static str,
+    changed: &'static str,
+    reset: &'static str,
+}
+
+fn colors() -> Colors {
+    if std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+        Colors {
+            expected: "",
+            written: "",
+            changed: "",
+            reset: "",
+        }
+    } else {
+        Colors {
+            expected: "\x1b[31m",
+            written: "\x1b[32m",
+            changed: "\x1b[7m",
+            reset: "\x1b[0m",
+        }
+    }
+}
+
+// split data to escaped lines (line terminators are kept)
+fn escaped_lines(data: &[u8]) -> Vec<String> {
+    data.split_inclusive(|&b| b == b'\n').map(escape).collect()
+}
+
+/// Render colored text and hex diff of expected and written data, mismatched at offset.
+pub(super) fn diff(expected: &[u8], written: &[u8], offset: usize) -> String {
+    let c = colors();
+    let mut s = format!(
+        "{}- expected{}\n{}+ written{}\n",
+        c.expected, c.reset, c.written, c.reset
+    );
+
+    let old = escaped_lines(expected);
+    let new = escaped_lines(written);
+    let old: Vec<&str> = old.iter().map(String::as_str).collect();
+    let new: Vec<&str> = new.iter().map(String::as_str).collect();
+    for change in TextDiff::from_slices(&old, &new).iter_all_changes() {
+        let (sign, color) = match change.tag() {
+            ChangeTag::Equal => (' ', ""),
+            ChangeTag::Delete => ('-', c.expected),
+            ChangeTag::Insert => ('+', c.written),
+        };
+        let reset = if color.is_empty() { "" } else { c.reset };
+        let _ = writeln!(s, "{}{} \"{}\"{}", color, sign, change.value(), reset);
+    }
+
+    s.push_str("hex:\n");
+    let start = (offset / 16).saturating_sub(HEX_BEFORE);
+    let rows = (expected.len().max(written.len()) + 15) / 16;
+    for row in start..rows.min(start + HEX_ROWS) {
+        hex_row(&mut s, '-', c.expected, expected, written, row, &c);
+        hex_row(&mut s, '+', c.written, written, expected, row, &c);
+    }
+    s
+}
+
+// hex row of data, bytes different from other data are highlighted
+fn hex_row(
+    s: &mut String,
+    sign: char,
+    color: &str,
+    data: &[u8],
+    other: &[u8],
+    row: usize,
+    c: &Colors,
+) {
+    let _ = write!(s, "{}{}{:08x} ", color, sign, row * 16);
+    for i in row * 16..row * 16 + 16 {
+        if i % 16 == 8 {
+            s.push(' ');
+        }
+        match data.get(i) {
+            Some(b) if other.get(i) != Some(b) => {
+                let _ = write!(s, " {}{:02x}{}{}", c.changed, b, c.reset, color);
+            }
+            Some(b) => {
+                let _ = write!(s, " {:02x}", b);
+            }
+            None => s.push_str("   "),
+        }
+    }
+    let _ = writeln!(s, "{}", c.reset);
+}
@@ -0,0 +1,157 @@
+//! Introspection of [`CheckedMockStream`] position in scenario.
+//!
+//! Tests and helper frameworks can make mid-scenario assertions about where the protocol
+//! conversation stands, without rendering of the whole scenario (see [`CheckedMockStream::dump`]).
+
+use std::io::ErrorKind;
+use std::path::Path;
+use std::time::Duration;
+
+use super::{Action, CheckedMockStream};
+
+/// A progress of [`CheckedMockStream`] in scenario.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    action: usize,
+    total: usize,
+    pos: usize,
+}
+
+impl Progress {
+    /// Gets an index of the current action.
+    pub fn action(&self) -> usize {
+        self.action
+    }
+
+    /// Gets a count of scenario actions.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Gets a position in the current action (count of readed bytes of partially read action).
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    /// Returns `true`, if all actions are passed.
+    pub fn is_done(&self) -> bool {
+        self.action >= self.total
+    }
+}
+
+/// A typed view of the current action of [`CheckedMockStream`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CurrentAction<'a> {
+    /// Data to read.
+    ExpectingRead {
+        /// Remaining (not readed) data of action.
+        remaining: &'a [u8],
+    },
+    /// File content to read.
+    ExpectingReadFile {
+        /// Path of file.
+        path: &'a Path,
+    },
+    /// Read error.
+    ExpectingReadError {
+        /// Error kind.
+        kind: ErrorKind,
+    },
+    /// Data to read once per interval (until write).
+    ExpectingHeartbeat {
+        /// Heartbeat data.
+        payload: &'a [u8],
+        /// Interval.
+        interval: Duration,
+    },
+    /// Expected write.
+    ExpectingWrite {
+        /// Expected data.
+        expected: &'a [u8],
+    },
+    /// Write error.
+    ExpectingWriteError {
+        /// Error kind.
+        kind: ErrorKind,
+    },
+    /// Wait before the next read or write.
+    Waiting {
+        /// Wait duration.
+        duration: Duration,
+        /// Direction of wait (`Some(true)` for read, `Some(false)` for write, `None` for both).
+        read: Option<bool>,
+    },
+    /// Wait until offset from stream creation.
+    WaitingUntil {
+        /// Offset from stream creation.
+        offset: Duration,
+    },
+    /// Barrier of duplex mode.
+    Barrier,
+    /// Synchronization point of several streams.
+    SyncPoint {
+        /// Count of parties.
+        parties: usize,
+        /// Count of streams, waiting at the point.
+        arrived: usize,
+    },
+    /// Shutdown of the peer.
+    PeerClose {
+        /// Connection is closed (or only write direction of the peer, if `false`).
+        full: bool,
+    },
+    /// All actions are passed.
+    Done,
+}
+
+impl CheckedMockStream {
+    /// Gets a progress in scenario (read cursor in duplex mode).
+    pub fn progress(&self) -> Progress {
+        Progress {
+            action: self.action.min(self.actions.len()),
+            total: self.actions.len(),
+            pos: self.pos,
+        }
+    }
+
+    /// Gets a typed view of the current action (read cursor in duplex mode).
+    pub fn current_action(&self) -> CurrentAction<'_> {
+        let action = match self.actions.get(self.action) {
+            Some(action) => action,
+            None => return CurrentAction::Done,
+        };
+        match action {
+            Action::Read(data) => CurrentAction::ExpectingRead {
+                remaining: &data[self.pos.min(data.len())..],
+            },
+            Action::ReadFile(path) => CurrentAction::ExpectingReadFile { path },
+            Action::ReadEvery(data, interval) => CurrentAction::ExpectingHeartbeat {
+                payload: data,
+                interval: *interval,
+            },
+            Action::ReadError(err) => CurrentAction::ExpectingReadError { kind: err.kind() },
+            Action::Write(data) => CurrentAction::ExpectingWrite { expected: data },
+            Action::WriteError(err) => CurrentAction::ExpectingWriteError { kind: err.kind() },
+            Action::Wait(duration) => CurrentAction::Waiting {
+                duration: *duration,
+                read: None,
+            },
+            Action::WaitRead(duration) => CurrentAction::Waiting {
+                duration: *duration,
+                read: Some(true),
+            },
+            Action::WaitWrite(duration) => CurrentAction::Waiting {
+                duration: *duration,
+                read: Some(false),
+            },
+            Action::WaitUntil(offset) => CurrentAction::WaitingUntil { offset: *offset },
+            Action::Barrier => CurrentAction::Barrier,
+            Action::PeerHalfClose => CurrentAction::PeerClose { full: false },
+            Action::PeerFullClose => CurrentAction::PeerClose { full: true },
+            Action::SyncPoint(point) => CurrentAction::SyncPoint {
+                parties: point.parties(),
+                arrived: point.arrived(),
+            },
+        }
+    }
+}
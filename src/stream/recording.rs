@@ -0,0 +1,254 @@
+//! Recording wrapper around a real stream.
+//!
+//! Record a session once against a live service and export it as [`CheckedMockStreamBuilder`]
+//! scenario (for example with [`CheckedMockStreamBuilder::to_transcript`]) for replay in tests.
+
+use std::io::{self, Error, ErrorKind, Read, Write};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "tokio")]
+use std::pin::Pin;
+
+#[cfg(feature = "tokio")]
+use std::task::{self, Poll};
+
+#[cfg(feature = "tokio")]
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use super::CheckedMockStreamBuilder;
+
+/// Minimal read duration, recorded as wait by [`RecordingStream::to_builder`].
+pub const DEFAULT_MIN_WAIT: Duration = Duration::from_millis(1);
+
+/// A recorded stream operation.
+#[derive(Debug, Clone)]
+pub enum RecordedOp {
+    /// Readed data.
+    Read(Vec<u8>),
+    /// Read error.
+    ReadError(Arc<Error>),
+    /// Written data.
+    Write(Vec<u8>),
+    /// Write error.
+    WriteError(Arc<Error>),
+}
+
+/// A recorded stream operation with timing.
+#[derive(Debug, Clone)]
+pub struct RecordedEvent {
+    /// Operation start (since recording start).
+    pub at: Duration,
+    /// Operation duration (for async including time from first pending poll).
+    pub elapsed: Duration,
+    /// Operation.
+    pub op: RecordedOp,
+}
+
+/// A stream wrapper, which transparently proxies to a real connection and records all traffic and timing.
+///
+/// Recorded session can be exported as [`CheckedMockStreamBuilder`] for replay.
+#[derive(Debug)]
+pub struct RecordingStream<T> {
+    inner: T,
+    start: Instant,
+    events: Vec<RecordedEvent>,
+    #[cfg(feature = "tokio")]
+    read_started: Option<Instant>,
+    #[cfg(feature = "tokio")]
+    write_started: Option<Instant>,
+}
+
+impl<T> RecordingStream<T> {
+    /// Creates a new recording wrapper around stream.
+    pub fn new(inner: T) -> Self {
+        RecordingStream {
+            inner,
+            start: Instant::now(),
+            events: Vec::new(),
+            #[cfg(feature = "tokio")]
+            read_started: None,
+            #[cfg(feature = "tokio")]
+            write_started: None,
+        }
+    }
+
+    /// Gets a reference to the underlying stream.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Gets a mutable reference to the underlying stream.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Consumes wrapper, returning the underlying stream.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// Gets a recorded events.
+    pub fn events(&self) -> &[RecordedEvent] {
+        &self.events
+    }
+
+    /// Export recorded session as scenario (reads longer than [`DEFAULT_MIN_WAIT`] are preceded by wait).
+    pub fn to_builder(&self) -> CheckedMockStreamBuilder {
+        self.to_builder_min_wait(DEFAULT_MIN_WAIT)
+    }
+
+    /// Export recorded session as scenario (reads longer than `min_wait` are preceded by wait).
+    ///
+    /// Consecutive reads (without wait between) are merged, writes are preserved as is.
+    pub fn to_builder_min_wait(&self, min_wait: Duration) -> CheckedMockStreamBuilder {
+        let mut builder = CheckedMockStreamBuilder::new();
+        let mut pending_read: Vec<u8> = Vec::new();
+        for event in &self.events {
+            if let RecordedOp::Read(data) = &event.op {
+                if event.elapsed >= min_wait {
+                    builder = flush_read(builder, &mut pending_read).wait(event.elapsed);
+                }
+                pending_read.extend_from_slice(data);
+                continue;
+            }
+            builder = flush_read(builder, &mut pending_read);
+            builder = match &event.op {
+                RecordedOp::ReadError(err) => builder.read_error(clone_error(err)),
+                RecordedOp::Write(data) => builder.write(data.clone()),
+                RecordedOp::WriteError(err) => builder.write_error(clone_error(err)),
+                RecordedOp::Read(_) => unreachable!(),
+            };
+        }
+        flush_read(builder, &mut pending_read)
+    }
+
+    fn record_read(&mut self, started: Instant, result: Result<&[u8], &Error>) {
+        let op = match result {
+            Ok([]) => return, // EOF
+            Ok(data) => RecordedOp::Read(data.to_vec()),
+            Err(err) if skip_error(err) => return,
+            Err(err) => RecordedOp::ReadError(Arc::new(clone_error(err))),
+        };
+        self.push(started, op);
+    }
+
+    fn record_write(&mut self, started: Instant, result: Result<&[u8], &Error>) {
+        let op = match result {
+            Ok([]) => return,
+            Ok(data) => RecordedOp::Write(data.to_vec()),
+            Err(err) if skip_error(err) => return,
+            Err(err) => RecordedOp::WriteError(Arc::new(clone_error(err))),
+        };
+        self.push(started, op);
+    }
+
+    fn push(&mut self, started: Instant, op: RecordedOp) {
+        self.events.push(RecordedEvent {
+            at: started.duration_since(self.start),
+            elapsed: started.elapsed(),
+            op,
+        });
+    }
+}
+
+fn flush_read(
+    builder: CheckedMockStreamBuilder,
+    pending: &mut Vec<u8>,
+) -> CheckedMockStreamBuilder {
+    if pending.is_empty() {
+        builder
+    } else {
+        builder.read(std::mem::take(pending))
+    }
+}
+
+// non-blocking and interrupted errors are retried by caller, not a part of session
+fn skip_error(err: &Error) -> bool {
+    matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::Interrupted)
+}
+
+fn clone_error(err: &Error) -> Error {
+    Error::new(err.kind(), err.to_string())
+}
+
+impl<T: Read> Read for RecordingStream<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let started = Instant::now();
+        let result = self.inner.read(buf);
+        match &result {
+            Ok(n) => self.record_read(started, Ok(&buf[..*n])),
+            Err(err) => self.record_read(started, Err(err)),
+        }
+        result
+    }
+}
+
+impl<T: Write> Write for RecordingStream<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let started = Instant::now();
+        let result = self.inner.write(buf);
+        match &result {
+            Ok(n) => self.record_write(started, Ok(&buf[..*n])),
+            Err(err) => self.record_write(started, Err(err)),
+        }
+        result
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<T: AsyncRead + Unpin> AsyncRead for RecordingStream<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let started = *self.read_started.get_or_insert_with(Instant::now);
+        let filled = buf.filled().len();
+        match Pin::new(&mut self.inner).poll_read(cx, buf) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(result) => {
+                self.read_started = None;
+                match &result {
+                    Ok(()) => self.record_read(started, Ok(&buf.filled()[filled..])),
+                    Err(err) => self.record_read(started, Err(err)),
+                }
+                Poll::Ready(result)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<T: AsyncWrite + Unpin> AsyncWrite for RecordingStream<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let started = *self.write_started.get_or_insert_with(Instant::now);
+        match Pin::new(&mut self.inner).poll_write(cx, buf) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(result) => {
+                self.write_started = None;
+                match &result {
+                    Ok(n) => self.record_write(started, Ok(&buf[..*n])),
+                    Err(err) => self.record_write(started, Err(err)),
+                }
+                Poll::Ready(result)
+            }
+        }
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
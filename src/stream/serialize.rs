@@ -0,0 +1,433 @@
+//! Serde support for [`CheckedMockStreamBuilder`] (scenario fixture files).
+//!
+//! Scenario is a list of actions with optional settings, for example in YAML:
+//!
+//! ```yaml
+//! duplex: true
+//! read_mode: message
+//! vars:
+//!   user: admin
+//! actions:
+//!   - read: "+OK ready\r\n"
+//!   - write: { hex: "515549540d0a" }
+//!   - wait: 100ms
+//!   - wait_read: 10ms
+//!   - wait_until: 1s
+//!   - read: { base64: "K09LDQo=" }
+//!   - read_error: { kind: TimedOut, message: "read timeout" }
+//!   - write_error: BrokenPipe
+//!   - read_file: fixtures/large.bin
+//!   - read_every: { data: "PING\r\n", interval: 30s }
+//!   - write: "PING\r\n"
+//!     within: 1s
+//!   - barrier: true
+//!   - peer_half_close: true
+//! ```
+//!
+//! Payload is a text string, `{ hex: ... }` or `{ base64: ... }`.
+//! Wait duration is a milliseconds number or a string with unit (`ns`, `us`, `ms`, `s`, `m`).
+//! Unknown error kind is loaded as `Other` (with kind name as message, if message is not set).
+//! Settings are `duplex`, `flush_gated`, `nonblocking`, `utf8_writes` (flags), `read_mode`
+//! (`stream`, `message` or `message_strict`), `memory_limit` and `vars` (see
+//! [`CheckedMockStreamBuilder::bind`]). Scenario with sync points (shared with other streams),
+//! chaos, written mode, exhausted write or mismatch policy, call-count expectations or socket
+//! metadata can't be serialized.
+
+use std::collections::BTreeMap;
+use std::io::{Error, ErrorKind};
+use std::time::Duration;
+
+use base64::Engine;
+use serde::de::{self, Deserializer};
+use serde::ser::{self, Serializer};
+use serde::{Deserialize, Serialize};
+
+use super::error::ScriptedError;
+use super::util::{error_kind_from_str, format_duration, parse_duration};
+use super::timing::Timing;
+use super::{Action, CheckedMockStreamBuilder, ReadMode};
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum PayloadDef {
+    Text(String),
+    Hex { hex: String },
+    Base64 { base64: String },
+}
+
+impl PayloadDef {
+    fn new(data: &[u8]) -> Self {
+        match std::str::from_utf8(data) {
+            Ok(s) => PayloadDef::Text(s.to_string()),
+            Err(_) => PayloadDef::Hex {
+                hex: encode_hex(data),
+            },
+        }
+    }
+
+    fn into_bytes<E: de::Error>(self) -> Result<Vec<u8>, E> {
+        match self {
+            PayloadDef::Text(s) => Ok(s.into_bytes()),
+            PayloadDef::Hex { hex } => decode_hex(&hex).map_err(E::custom),
+            PayloadDef::Base64 { base64 } => base64::engine::general_purpose::STANDARD
+                .decode(base64.trim())
+                .map_err(E::custom),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum ErrorDef {
+    Kind(String),
+    Full {
+        kind: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        message: Option<String>,
+    },
+}
+
+impl ErrorDef {
+    fn new(err: &ScriptedError) -> Self {
+        ErrorDef::Full {
+            kind: format!("{:?}", err.kind()),
+            message: Some(err.to_string()),
+        }
+    }
+
+    // unknown kind (like not listed or unstable) is loaded as `Other` (message is preserved)
+    fn into_error(self) -> Error {
+        let (name, message) = match self {
+            ErrorDef::Kind(kind) => (kind, None),
+            ErrorDef::Full { kind, message } => (kind, message),
+        };
+        match (error_kind_from_str(&name), message) {
+            (Some(kind), Some(message)) => Error::new(kind, message),
+            (Some(kind), None) => Error::from(kind),
+            (None, Some(message)) => Error::new(ErrorKind::Other, message),
+            (None, None) => Error::new(ErrorKind::Other, name),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum DurationDef {
+    Millis(u64),
+    Text(String),
+}
+
+#[derive(Serialize, Deserialize)]
+struct ReadEveryDef {
+    data: PayloadDef,
+    interval: DurationDef,
+}
+
+// a single key map (`{ read: ... }`), as externally tagged enums are not maps in all formats
+#[derive(Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ActionDef {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    read: Option<PayloadDef>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    read_error: Option<ErrorDef>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    write: Option<PayloadDef>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    write_error: Option<ErrorDef>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    wait: Option<DurationDef>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    wait_read: Option<DurationDef>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    wait_write: Option<DurationDef>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    wait_until: Option<DurationDef>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    read_file: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    read_every: Option<ReadEveryDef>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    barrier: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    peer_half_close: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    peer_full_close: Option<bool>,
+    // timing constraints of action
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    within: Option<DurationDef>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    after: Option<DurationDef>,
+}
+
+impl ActionDef {
+    fn new(action: &Action, timing: Option<&Timing>) -> Self {
+        let mut def = ActionDef::default();
+        if let Some(timing) = timing {
+            def.within = timing.within.map(|d| DurationDef::Text(format_duration(d)));
+            def.after = timing.after.map(|d| DurationDef::Text(format_duration(d)));
+        }
+        match action {
+            Action::Read(data) => def.read = Some(PayloadDef::new(data)),
+            Action::ReadError(err) => def.read_error = Some(ErrorDef::new(err)),
+            Action::Write(data) => def.write = Some(PayloadDef::new(data)),
+            Action::WriteError(err) => def.write_error = Some(ErrorDef::new(err)),
+            Action::Wait(d) => def.wait = Some(DurationDef::Text(format_duration(*d))),
+            Action::WaitRead(d) => def.wait_read = Some(DurationDef::Text(format_duration(*d))),
+            Action::WaitWrite(d) => def.wait_write = Some(DurationDef::Text(format_duration(*d))),
+            Action::WaitUntil(d) => def.wait_until = Some(DurationDef::Text(format_duration(*d))),
+            Action::ReadFile(path) => def.read_file = Some(path.display().to_string()),
+            Action::ReadEvery(data, d) => {
+                def.read_every = Some(ReadEveryDef {
+                    data: PayloadDef::new(data),
+                    interval: DurationDef::Text(format_duration(*d)),
+                })
+            }
+            Action::Barrier => def.barrier = Some(true),
+            Action::PeerHalfClose => def.peer_half_close = Some(true),
+            Action::PeerFullClose => def.peer_full_close = Some(true),
+            // rejected by serialization of scenario
+            Action::SyncPoint(_) => {}
+        }
+        def
+    }
+
+    fn apply<E: de::Error>(
+        self,
+        builder: CheckedMockStreamBuilder,
+    ) -> Result<CheckedMockStreamBuilder, E> {
+        let set = [
+            self.read.is_some(),
+            self.read_error.is_some(),
+            self.write.is_some(),
+            self.write_error.is_some(),
+            self.wait.is_some(),
+            self.wait_read.is_some(),
+            self.wait_write.is_some(),
+            self.wait_until.is_some(),
+            self.read_file.is_some(),
+            self.read_every.is_some(),
+            self.barrier.is_some(),
+            self.peer_half_close.is_some(),
+            self.peer_full_close.is_some(),
+        ];
+        if set.iter().filter(|&&set| set).count() != 1 {
+            return Err(E::custom(
+                "action must have exactly one of read, read_error, write, write_error, wait, \
+                 wait_read, wait_write, wait_until, read_file, read_every, barrier, \
+                 peer_half_close, peer_full_close",
+            ));
+        }
+        let builder = if let Some(data) = self.read {
+            builder.read(data.into_bytes()?)
+        } else if let Some(err) = self.read_error {
+            builder.read_error(err.into_error())
+        } else if let Some(data) = self.write {
+            builder.write(data.into_bytes()?)
+        } else if let Some(err) = self.write_error {
+            builder.write_error(err.into_error())
+        } else if let Some(d) = self.wait {
+            builder.wait(d.into_duration()?)
+        } else if let Some(d) = self.wait_read {
+            builder.wait_read(d.into_duration()?)
+        } else if let Some(d) = self.wait_write {
+            builder.wait_write(d.into_duration()?)
+        } else if let Some(d) = self.wait_until {
+            builder.wait_until(d.into_duration()?)
+        } else if let Some(path) = self.read_file {
+            builder.read_file(path)
+        } else if let Some(every) = self.read_every {
+            builder.read_every(every.data.into_bytes()?, every.interval.into_duration()?)
+        } else if let Some(flag) = self.barrier {
+            check_flag("barrier", flag)?;
+            builder.barrier()
+        } else if let Some(flag) = self.peer_half_close {
+            check_flag("peer_half_close", flag)?;
+            builder.peer_half_close()
+        } else if let Some(flag) = self.peer_full_close {
+            check_flag("peer_full_close", flag)?;
+            builder.peer_full_close()
+        } else {
+            unreachable!()
+        };
+        let builder = match self.within {
+            Some(d) => builder.within(d.into_duration()?),
+            None => builder,
+        };
+        Ok(match self.after {
+            Some(d) => builder.after(d.into_duration()?),
+            None => builder,
+        })
+    }
+}
+
+// flag actions (`barrier: true`) can't be disabled
+fn check_flag<E: de::Error>(name: &str, flag: bool) -> Result<(), E> {
+    if flag {
+        Ok(())
+    } else {
+        Err(E::custom(format!("{} must be true", name)))
+    }
+}
+
+impl DurationDef {
+    fn into_duration<E: de::Error>(self) -> Result<Duration, E> {
+        match self {
+            DurationDef::Millis(ms) => Ok(Duration::from_millis(ms)),
+            DurationDef::Text(s) => {
+                parse_duration(&s).ok_or_else(|| E::custom(format!("invalid duration: {}", s)))
+            }
+        }
+    }
+}
+
+// settings are omitted, if default
+#[derive(Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ScenarioDef {
+    #[serde(default, skip_serializing_if = "is_false")]
+    duplex: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    flush_gated: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    nonblocking: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    utf8_writes: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    read_mode: Option<ReadModeDef>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    memory_limit: Option<usize>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    vars: BTreeMap<String, PayloadDef>,
+    actions: Vec<ActionDef>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ReadModeDef {
+    Stream,
+    Message,
+    MessageStrict,
+}
+
+fn is_false(v: &bool) -> bool {
+    !*v
+}
+
+impl CheckedMockStreamBuilder {
+    // settings, which are not representable in scenario file
+    fn unserializable(&self) -> Option<&'static str> {
+        if self
+            .actions
+            .iter()
+            .any(|action| matches!(action, Action::SyncPoint(_)))
+        {
+            return Some("sync point");
+        }
+        #[cfg(feature = "tokio")]
+        {
+            if self.pending_reads {
+                return Some("pending reads");
+            }
+        }
+        if self.chaos.is_some() {
+            Some("chaos")
+        } else if self.written_mode != Default::default() {
+            Some("written mode")
+        } else if self.exhausted_write != Default::default() {
+            Some("exhausted write policy")
+        } else if self.mismatch != Default::default() {
+            Some("mismatch policy")
+        } else if !self.calls.is_empty() {
+            Some("call-count expectation")
+        } else if self.socket != Default::default() {
+            Some("socket metadata")
+        } else {
+            None
+        }
+    }
+}
+
+impl Serialize for CheckedMockStreamBuilder {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if let Some(setting) = self.unserializable() {
+            return Err(ser::Error::custom(format!(
+                "{} can't be serialized",
+                setting
+            )));
+        }
+        let actions = self
+            .actions
+            .iter()
+            .enumerate()
+            .map(|(i, action)| {
+                let timing = self.timings.iter().find(|(t, _)| *t == i);
+                ActionDef::new(action, timing.map(|(_, timing)| timing))
+            })
+            .collect();
+        let read_mode = match self.read_mode {
+            ReadMode::Stream => None,
+            ReadMode::Message => Some(ReadModeDef::Message),
+            ReadMode::MessageStrict => Some(ReadModeDef::MessageStrict),
+        };
+        ScenarioDef {
+            duplex: self.duplex,
+            flush_gated: self.flush_gated,
+            nonblocking: self.nonblocking,
+            utf8_writes: self.utf8_writes,
+            read_mode,
+            memory_limit: self.memory_limit,
+            vars: self
+                .vars
+                .iter()
+                .map(|(name, value)| (name.clone(), PayloadDef::new(value)))
+                .collect(),
+            actions,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for CheckedMockStreamBuilder {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let scenario = ScenarioDef::deserialize(deserializer)?;
+        let mut builder = CheckedMockStreamBuilder::new();
+        builder.duplex = scenario.duplex;
+        builder.flush_gated = scenario.flush_gated;
+        builder.nonblocking = scenario.nonblocking;
+        builder.utf8_writes = scenario.utf8_writes;
+        builder.read_mode = match scenario.read_mode {
+            None | Some(ReadModeDef::Stream) => ReadMode::Stream,
+            Some(ReadModeDef::Message) => ReadMode::Message,
+            Some(ReadModeDef::MessageStrict) => ReadMode::MessageStrict,
+        };
+        builder.memory_limit = scenario.memory_limit;
+        for (name, value) in scenario.vars {
+            builder = builder.bind(&name, value.into_bytes()?);
+        }
+        for action in scenario.actions {
+            builder = action.apply(builder)?;
+        }
+        Ok(builder)
+    }
+}
+
+fn encode_hex(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn decode_hex(s: &str) -> Result<Vec<u8>, String> {
+    let digits: Vec<u8> = s.bytes().filter(|c| !c.is_ascii_whitespace()).collect();
+    if digits.len() % 2 != 0 {
+        return Err(format!("odd hex length: {}", s));
+    }
+    digits
+        .chunks(2)
+        .map(|pair| {
+            std::str::from_utf8(pair)
+                .ok()
+                .and_then(|p| u8::from_str_radix(p, 16).ok())
+                .ok_or_else(|| format!("invalid hex: {}", s))
+        })
+        .collect()
+}
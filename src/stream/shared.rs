@@ -0,0 +1,218 @@
+//! Thread-safe shared handle of [`CheckedMockStream`].
+//!
+//! [`SharedMockStream`] can be cloned and moved into the code under test, while the test keeps
+//! another handle for inspection of written data and scenario state.
+
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::SocketAddr;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+
+#[cfg(feature = "tokio")]
+use std::pin::Pin;
+
+#[cfg(feature = "tokio")]
+use std::task::{self, Poll};
+
+#[cfg(feature = "tokio")]
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use super::{run_step, Call, CheckedMockStream, Divergence, SocketCall, Step};
+
+/// A cloneable thread-safe handle of [`CheckedMockStream`] (stream is guarded by mutex).
+///
+/// Sync read and write wait without holding the lock, so other handles are not blocked by scripted waits
+/// (and sync points). In duplex mode sync operation, blocked by barrier, waits until operation of other
+/// handle advances the scenario (or returns `WouldBlock` error in non-blocking mode).
+#[derive(Debug, Clone)]
+pub struct SharedMockStream(Arc<Shared>);
+
+#[derive(Debug)]
+struct Shared {
+    stream: Mutex<CheckedMockStream>,
+    // notified after operation steps
+    advanced: Condvar,
+}
+
+impl SharedMockStream {
+    /// Creates a new shared handle of stream.
+    pub fn new(stream: CheckedMockStream) -> Self {
+        SharedMockStream(Arc::new(Shared {
+            stream: Mutex::new(stream),
+            advanced: Condvar::new(),
+        }))
+    }
+
+    /// Locks the stream for access to the full API (a panic in other thread does not poison lock).
+    pub fn lock(&self) -> MutexGuard<'_, CheckedMockStream> {
+        self.0.stream.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // perform step of sync operation, blocked step waits for step of other handle
+    // (if not in non-blocking mode)
+    fn step<T, F>(&self, mut f: F) -> (Step<T>, bool)
+    where
+        F: FnMut(&mut CheckedMockStream) -> Step<T>,
+    {
+        let mut stream = self.lock();
+        loop {
+            let step = f(&mut stream);
+            self.0.advanced.notify_all();
+            if !matches!(step, Step::Blocked) || stream.nonblocking {
+                return (step, stream.nonblocking);
+            }
+            stream = self
+                .0
+                .advanced
+                .wait(stream)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    // perform poll of async operation (sync operations of other handles are notified)
+    #[cfg(feature = "tokio")]
+    fn poll<T, F>(&self, f: F) -> Poll<T>
+    where
+        F: FnOnce(Pin<&mut CheckedMockStream>) -> Poll<T>,
+    {
+        let poll = f(Pin::new(&mut *self.lock()));
+        self.0.advanced.notify_all();
+        poll
+    }
+
+    /// Returns the configured address of the remote peer (see [`CheckedMockStream::peer_addr`]).
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.lock().peer_addr()
+    }
+
+    /// Returns the configured local address (see [`CheckedMockStream::local_addr`]).
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.lock().local_addr()
+    }
+
+    /// Gets the value of `TCP_NODELAY` option.
+    pub fn nodelay(&self) -> io::Result<bool> {
+        self.lock().nodelay()
+    }
+
+    /// Sets the value of `TCP_NODELAY` option (call is recorded).
+    pub fn set_nodelay(&self, nodelay: bool) -> io::Result<()> {
+        self.lock().set_nodelay(nodelay)
+    }
+
+    /// Gets the value of `IP_TTL` option.
+    pub fn ttl(&self) -> io::Result<u32> {
+        self.lock().ttl()
+    }
+
+    /// Sets the value of `IP_TTL` option (call is recorded).
+    pub fn set_ttl(&self, ttl: u32) -> io::Result<()> {
+        self.lock().set_ttl(ttl)
+    }
+
+    /// Gets recorded calls of socket option setters.
+    pub fn socket_calls(&self) -> Vec<SocketCall> {
+        self.lock().socket_calls()
+    }
+
+    /// Gets a copy of written data.
+    pub fn written(&self) -> Vec<u8> {
+        self.lock().written_to_vec()
+    }
+
+    /// Gets a length of written data.
+    pub fn written_len(&self) -> usize {
+        self.lock().written_len()
+    }
+
+    /// Check if all scripted actions are performed.
+    pub fn is_done(&self) -> bool {
+        self.lock().is_done()
+    }
+
+    /// Get divergences of observed operations from scripted scenario.
+    pub fn diff(&self) -> Vec<Divergence> {
+        self.lock().diff()
+    }
+
+    /// Render all actions with consumed/pending status.
+    pub fn dump(&self) -> String {
+        self.lock().dump()
+    }
+
+    /// Assert all scripted actions are performed without divergences.
+    #[track_caller]
+    pub fn assert_done(&self) {
+        self.lock().assert_done()
+    }
+}
+
+/// Scenario checklist of the stream (see [`CheckedMockStream`] `Display`).
+impl fmt::Display for SharedMockStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&*self.lock(), f)
+    }
+}
+
+impl From<CheckedMockStream> for SharedMockStream {
+    fn from(stream: CheckedMockStream) -> Self {
+        SharedMockStream::new(stream)
+    }
+}
+
+impl CheckedMockStream {
+    /// Converts stream into thread-safe shared handle.
+    pub fn into_shared(self) -> SharedMockStream {
+        SharedMockStream::new(self)
+    }
+}
+
+impl Read for SharedMockStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        run_step(|| {
+            self.step(|stream| stream.call_step(Call::Read, |stream| stream.read_step(buf)))
+        })
+    }
+}
+
+impl Write for SharedMockStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        run_step(|| {
+            self.step(|stream| stream.call_step(Call::Write, |stream| stream.write_step(buf)))
+        })
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        run_step(|| self.step(|stream| stream.call_step(Call::Flush, |stream| stream.flush_step())))
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl AsyncRead for SharedMockStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        self.poll(|stream| stream.poll_read(cx, buf))
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl AsyncWrite for SharedMockStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.poll(|stream| stream.poll_write(cx, buf))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
+        self.poll(|stream| stream.poll_flush(cx))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
+        self.poll(|stream| stream.poll_shutdown(cx))
+    }
+}
@@ -0,0 +1,59 @@
+//! Stable rendering of written data for `insta` snapshots.
+//!
+//! ```text
+//! 00000000  53 45 54 20 6b 65 79 0d  0a                       |SET key..|
+//! --
+//! SET key\r
+//! ```
+
+use std::fmt::Write as _;
+
+use super::dump::hexdump;
+use super::{CheckedMockStream, SimpleMockStream};
+
+/// Render data as hexdump and lossy text in a stable format suitable for `insta::assert_snapshot!`.
+pub fn snapshot(data: &[u8]) -> String {
+    let mut s = hexdump(data, 0);
+    s.push_str("--\n");
+    for c in String::from_utf8_lossy(data).chars() {
+        match c {
+            '\n' | '\t' => s.push(c),
+            '\r' => s.push_str("\\r"),
+            c if c.is_control() => {
+                let _ = write!(s, "\\x{:02x}", c as u32);
+            }
+            c => s.push(c),
+        }
+    }
+    s
+}
+
+impl SimpleMockStream {
+    /// Render written data for snapshot (see [`snapshot`]).
+    pub fn written_snapshot(&self) -> String {
+        snapshot(self.written())
+    }
+}
+
+impl CheckedMockStream {
+    /// Render written data for snapshot (see [`snapshot`]).
+    pub fn written_snapshot(&self) -> String {
+        snapshot(self.written())
+    }
+}
+
+/// Assert written data of mock stream with `insta` snapshot.
+///
+/// ```ignore
+/// netmock::assert_written_snapshot!(stream);
+/// netmock::assert_written_snapshot!(stream, @"...");
+/// ```
+#[macro_export]
+macro_rules! assert_written_snapshot {
+    ($stream:expr) => {
+        $crate::__private::insta::assert_snapshot!($stream.written_snapshot())
+    };
+    ($stream:expr, @$snapshot:literal) => {
+        $crate::__private::insta::assert_snapshot!($stream.written_snapshot(), @$snapshot)
+    };
+}
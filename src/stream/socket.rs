@@ -0,0 +1,287 @@
+//! Socket metadata of mock streams (addresses and options).
+//!
+//! [`SimpleMockStream`] and [`CheckedMockStream`] provide `peer_addr`, `local_addr`, `nodelay`
+//! and `ttl` methods in the shape of [`std::net::TcpStream`] API, so code under test, which queries
+//! or sets them, can run against mock streams. Addresses and initial options are configured with
+//! stream (or builder) methods, setter calls are recorded as [`SocketCall`] for assertions.
+//!
+//! ```
+//! use netmock::stream::{CheckedMockStreamBuilder, SocketCall};
+//!
+//! let stream = CheckedMockStreamBuilder::new()
+//!     .peer_addr("10.0.0.1:6379".parse().unwrap())
+//!     .build();
+//! assert_eq!(stream.peer_addr().unwrap().port(), 6379);
+//! stream.set_nodelay(true).unwrap();
+//! assert!(stream.nodelay().unwrap());
+//! assert_eq!(stream.socket_calls(), vec![SocketCall::SetNodelay(true)]);
+//! ```
+
+use std::io::{self, Error, ErrorKind};
+use std::net::SocketAddr;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use super::{CheckedMockStream, CheckedMockStreamBuilder, SimpleMockStream};
+
+/// Default time-to-live of mock socket (as on Linux).
+pub const DEFAULT_TTL: u32 = 64;
+
+/// A recorded call of socket option setter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SocketCall {
+    /// `set_nodelay` call.
+    SetNodelay(bool),
+    /// `set_ttl` call.
+    SetTtl(u32),
+}
+
+// configured metadata of socket
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct SocketConfig {
+    peer_addr: Option<SocketAddr>,
+    local_addr: Option<SocketAddr>,
+    nodelay: bool,
+    ttl: u32,
+}
+
+impl Default for SocketConfig {
+    fn default() -> Self {
+        SocketConfig {
+            peer_addr: None,
+            local_addr: None,
+            nodelay: false,
+            ttl: DEFAULT_TTL,
+        }
+    }
+}
+
+// socket metadata of stream (options are set by shared reference, like in `TcpStream`)
+#[derive(Debug)]
+pub(super) struct Socket {
+    config: SocketConfig,
+    options: Mutex<Options>,
+}
+
+#[derive(Debug, Clone)]
+struct Options {
+    nodelay: bool,
+    ttl: u32,
+    calls: Vec<SocketCall>,
+}
+
+impl Socket {
+    pub(super) fn new(config: SocketConfig) -> Self {
+        Socket {
+            config,
+            options: Mutex::new(Options {
+                nodelay: config.nodelay,
+                ttl: config.ttl,
+                calls: Vec::new(),
+            }),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Options> {
+        self.options.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub(super) fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.config
+            .peer_addr
+            .ok_or_else(|| not_configured("peer address"))
+    }
+
+    pub(super) fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.config
+            .local_addr
+            .ok_or_else(|| not_configured("local address"))
+    }
+
+    pub(super) fn nodelay(&self) -> io::Result<bool> {
+        Ok(self.lock().nodelay)
+    }
+
+    pub(super) fn set_nodelay(&self, nodelay: bool) -> io::Result<()> {
+        let mut options = self.lock();
+        options.nodelay = nodelay;
+        options.calls.push(SocketCall::SetNodelay(nodelay));
+        Ok(())
+    }
+
+    pub(super) fn ttl(&self) -> io::Result<u32> {
+        Ok(self.lock().ttl)
+    }
+
+    // ttl is validated like in `TcpStream` (0 is rejected by OS)
+    pub(super) fn set_ttl(&self, ttl: u32) -> io::Result<()> {
+        if ttl == 0 || ttl > 255 {
+            return Err(Error::new(ErrorKind::InvalidInput, "invalid ttl"));
+        }
+        let mut options = self.lock();
+        options.ttl = ttl;
+        options.calls.push(SocketCall::SetTtl(ttl));
+        Ok(())
+    }
+
+    pub(super) fn calls(&self) -> Vec<SocketCall> {
+        self.lock().calls.clone()
+    }
+}
+
+impl Default for Socket {
+    fn default() -> Self {
+        Socket::new(SocketConfig::default())
+    }
+}
+
+impl Clone for Socket {
+    fn clone(&self) -> Self {
+        Socket {
+            config: self.config,
+            options: Mutex::new(self.lock().clone()),
+        }
+    }
+}
+
+fn not_configured(what: &str) -> Error {
+    Error::new(
+        ErrorKind::NotConnected,
+        format!("{} of mock stream is not configured", what),
+    )
+}
+
+impl SimpleMockStream {
+    /// Sets address of the remote peer.
+    pub fn with_peer_addr(mut self, addr: SocketAddr) -> Self {
+        self.socket = Socket::new(SocketConfig {
+            peer_addr: Some(addr),
+            ..self.socket.config
+        });
+        self
+    }
+
+    /// Sets local address.
+    pub fn with_local_addr(mut self, addr: SocketAddr) -> Self {
+        self.socket = Socket::new(SocketConfig {
+            local_addr: Some(addr),
+            ..self.socket.config
+        });
+        self
+    }
+
+    /// Sets initial value of `TCP_NODELAY` option (`false` by default).
+    pub fn with_nodelay(mut self, nodelay: bool) -> Self {
+        self.socket = Socket::new(SocketConfig {
+            nodelay,
+            ..self.socket.config
+        });
+        self
+    }
+
+    /// Sets initial value of `IP_TTL` option ([`DEFAULT_TTL`] by default).
+    pub fn with_ttl(mut self, ttl: u32) -> Self {
+        self.socket = Socket::new(SocketConfig {
+            ttl,
+            ..self.socket.config
+        });
+        self
+    }
+
+    /// Returns the configured address of the remote peer (`NotConnected` error, if not configured).
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.peer_addr()
+    }
+
+    /// Returns the configured local address (`NotConnected` error, if not configured).
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    /// Gets the value of `TCP_NODELAY` option.
+    pub fn nodelay(&self) -> io::Result<bool> {
+        self.socket.nodelay()
+    }
+
+    /// Sets the value of `TCP_NODELAY` option (call is recorded).
+    pub fn set_nodelay(&self, nodelay: bool) -> io::Result<()> {
+        self.socket.set_nodelay(nodelay)
+    }
+
+    /// Gets the value of `IP_TTL` option.
+    pub fn ttl(&self) -> io::Result<u32> {
+        self.socket.ttl()
+    }
+
+    /// Sets the value of `IP_TTL` option (call is recorded, `InvalidInput` error, if out of 1..=255).
+    pub fn set_ttl(&self, ttl: u32) -> io::Result<()> {
+        self.socket.set_ttl(ttl)
+    }
+
+    /// Gets recorded calls of socket option setters.
+    pub fn socket_calls(&self) -> Vec<SocketCall> {
+        self.socket.calls()
+    }
+}
+
+impl CheckedMockStreamBuilder {
+    /// Set address of the remote peer.
+    pub fn peer_addr(mut self, addr: SocketAddr) -> Self {
+        self.socket.peer_addr = Some(addr);
+        self
+    }
+
+    /// Set local address.
+    pub fn local_addr(mut self, addr: SocketAddr) -> Self {
+        self.socket.local_addr = Some(addr);
+        self
+    }
+
+    /// Set initial value of `TCP_NODELAY` option (`false` by default).
+    pub fn nodelay(mut self, nodelay: bool) -> Self {
+        self.socket.nodelay = nodelay;
+        self
+    }
+
+    /// Set initial value of `IP_TTL` option ([`DEFAULT_TTL`] by default).
+    pub fn ttl(mut self, ttl: u32) -> Self {
+        self.socket.ttl = ttl;
+        self
+    }
+}
+
+impl CheckedMockStream {
+    /// Returns the configured address of the remote peer (`NotConnected` error, if not configured).
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.peer_addr()
+    }
+
+    /// Returns the configured local address (`NotConnected` error, if not configured).
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    /// Gets the value of `TCP_NODELAY` option.
+    pub fn nodelay(&self) -> io::Result<bool> {
+        self.socket.nodelay()
+    }
+
+    /// Sets the value of `TCP_NODELAY` option (call is recorded).
+    pub fn set_nodelay(&self, nodelay: bool) -> io::Result<()> {
+        self.socket.set_nodelay(nodelay)
+    }
+
+    /// Gets the value of `IP_TTL` option.
+    pub fn ttl(&self) -> io::Result<u32> {
+        self.socket.ttl()
+    }
+
+    /// Sets the value of `IP_TTL` option (call is recorded, `InvalidInput` error, if out of 1..=255).
+    pub fn set_ttl(&self, ttl: u32) -> io::Result<()> {
+        self.socket.set_ttl(ttl)
+    }
+
+    /// Gets recorded calls of socket option setters.
+    pub fn socket_calls(&self) -> Vec<SocketCall> {
+        self.socket.calls()
+    }
+}
@@ -0,0 +1,202 @@
+//! Programmatic scenario construction from action specifications.
+//!
+//! Scenario generators (fuzzers or other tools) can produce a sequence of [`ActionSpec`] and build
+//! the stream with [`CheckedMockStream::from_actions`] instead of chaining builder calls.
+//! Planned actions of builder can be inspected with [`CheckedMockStreamBuilder::planned`].
+
+use std::io::Error;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use super::{Action, CheckedMockStream, CheckedMockStreamBuilder, Payload, SyncPoint};
+
+/// A specification of scenario action (see [`CheckedMockStreamBuilder`] methods with the same names).
+#[derive(Debug)]
+pub enum ActionSpec {
+    /// Data to be returned by read.
+    Read(Payload),
+    /// File content to be returned by read.
+    ReadFile(PathBuf),
+    /// Error to be returned by read.
+    ReadError(Error),
+    /// Data to be returned by read once per interval (until write).
+    ReadEvery(Payload, Duration),
+    /// Data required to be written.
+    Write(Payload),
+    /// Error to be returned by write.
+    WriteError(Error),
+    /// Wait before the next read and write.
+    Wait(Duration),
+    /// Wait before the next read.
+    WaitRead(Duration),
+    /// Wait before the next write.
+    WaitWrite(Duration),
+    /// Wait until offset from stream creation.
+    WaitUntil(Duration),
+    /// Barrier of duplex mode.
+    Barrier,
+    /// Shutdown of the peer write direction.
+    PeerHalfClose,
+    /// Close of connection by the peer.
+    PeerFullClose,
+    /// Synchronization point of several streams.
+    SyncPoint(SyncPoint),
+}
+
+/// A kind of scenario action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ActionKind {
+    /// Data to be returned by read.
+    Read,
+    /// File content to be returned by read.
+    ReadFile,
+    /// Error to be returned by read.
+    ReadError,
+    /// Data to be returned by read once per interval (until write).
+    ReadEvery,
+    /// Data required to be written.
+    Write,
+    /// Error to be returned by write.
+    WriteError,
+    /// Wait before the next read and write.
+    Wait,
+    /// Wait before the next read.
+    WaitRead,
+    /// Wait before the next write.
+    WaitWrite,
+    /// Wait until offset from stream creation.
+    WaitUntil,
+    /// Barrier of duplex mode.
+    Barrier,
+    /// Shutdown of the peer write direction.
+    PeerHalfClose,
+    /// Close of connection by the peer.
+    PeerFullClose,
+    /// Synchronization point of several streams.
+    SyncPoint,
+}
+
+/// A planned action of [`CheckedMockStreamBuilder`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlannedAction {
+    kind: ActionKind,
+    payload_len: Option<usize>,
+}
+
+impl PlannedAction {
+    fn new(action: &Action) -> Self {
+        let (kind, payload_len) = match action {
+            Action::Read(data) => (ActionKind::Read, Some(data.len())),
+            Action::ReadFile(_) => (ActionKind::ReadFile, None),
+            Action::ReadError(_) => (ActionKind::ReadError, None),
+            Action::ReadEvery(data, _) => (ActionKind::ReadEvery, Some(data.len())),
+            Action::Write(data) => (ActionKind::Write, Some(data.len())),
+            Action::WriteError(_) => (ActionKind::WriteError, None),
+            Action::Wait(_) => (ActionKind::Wait, None),
+            Action::WaitRead(_) => (ActionKind::WaitRead, None),
+            Action::WaitWrite(_) => (ActionKind::WaitWrite, None),
+            Action::WaitUntil(_) => (ActionKind::WaitUntil, None),
+            Action::Barrier => (ActionKind::Barrier, None),
+            Action::PeerHalfClose => (ActionKind::PeerHalfClose, None),
+            Action::PeerFullClose => (ActionKind::PeerFullClose, None),
+            Action::SyncPoint(_) => (ActionKind::SyncPoint, None),
+        };
+        PlannedAction { kind, payload_len }
+    }
+
+    /// Gets an action kind.
+    pub fn kind(&self) -> ActionKind {
+        self.kind
+    }
+
+    /// Gets a payload length of read or write action.
+    pub fn payload_len(&self) -> Option<usize> {
+        self.payload_len
+    }
+}
+
+impl CheckedMockStreamBuilder {
+    /// Gets a count of planned actions (consecutive waits are counted separately,
+    /// while merged in built stream).
+    pub fn len(&self) -> usize {
+        self.actions.len()
+    }
+
+    /// Returns `true`, if no actions are planned.
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    /// Iterate over planned actions.
+    pub fn planned(&self) -> impl Iterator<Item = PlannedAction> + '_ {
+        self.actions.iter().map(PlannedAction::new)
+    }
+
+    /// Queue an action by specification.
+    pub fn action(self, spec: ActionSpec) -> Self {
+        match spec {
+            ActionSpec::Read(data) => self.read(data),
+            ActionSpec::ReadFile(path) => self.read_file(path),
+            ActionSpec::ReadError(err) => self.read_error(err),
+            ActionSpec::ReadEvery(data, interval) => self.read_every(data, interval),
+            ActionSpec::Write(data) => self.write(data),
+            ActionSpec::WriteError(err) => self.write_error(err),
+            ActionSpec::Wait(d) => self.wait(d),
+            ActionSpec::WaitRead(d) => self.wait_read(d),
+            ActionSpec::WaitWrite(d) => self.wait_write(d),
+            ActionSpec::WaitUntil(d) => self.wait_until(d),
+            ActionSpec::Barrier => self.barrier(),
+            ActionSpec::PeerHalfClose => self.peer_half_close(),
+            ActionSpec::PeerFullClose => self.peer_full_close(),
+            ActionSpec::SyncPoint(point) => self.sync_point(&point),
+        }
+    }
+
+    /// Queue an action, which becomes available only after an offset from stream creation
+    /// (operations before wait, async operations return `Poll::Pending`).
+    ///
+    /// Allows to model servers, which respond on their own schedule rather than strictly reactively.
+    /// Preceding waits are not added to the offset (the action is available at the later of them).
+    ///
+    /// ```
+    /// use netmock::stream::{ActionSpec, CheckedMockStreamBuilder};
+    /// use std::time::Duration;
+    ///
+    /// let builder = CheckedMockStreamBuilder::new()
+    ///     .write(b"SUBSCRIBE news\r\n")
+    ///     .at(Duration::from_secs(60), ActionSpec::Read(b"+news 1\r\n".into()))
+    ///     .at(Duration::from_secs(120), ActionSpec::Read(b"+news 2\r\n".into()));
+    /// ```
+    pub fn at(self, offset: Duration, spec: ActionSpec) -> Self {
+        self.wait_until(offset).action(spec)
+    }
+
+    /// Queue actions by specifications.
+    pub fn actions<I: IntoIterator<Item = ActionSpec>>(self, specs: I) -> Self {
+        specs
+            .into_iter()
+            .fold(self, |builder, spec| builder.action(spec))
+    }
+}
+
+impl CheckedMockStream {
+    /// Creates a new stream with empty scenario.
+    pub fn empty() -> CheckedMockStream {
+        CheckedMockStreamBuilder::new().build()
+    }
+
+    /// Creates a new stream from action specifications (with default options).
+    ///
+    /// ```
+    /// use netmock::stream::{ActionSpec, CheckedMockStream};
+    ///
+    /// let stream = CheckedMockStream::from_actions(vec![
+    ///     ActionSpec::Write(b"PING\r\n".into()),
+    ///     ActionSpec::Read(b"+PONG\r\n".into()),
+    /// ]);
+    /// assert!(!stream.is_done());
+    /// ```
+    pub fn from_actions<I: IntoIterator<Item = ActionSpec>>(specs: I) -> CheckedMockStream {
+        CheckedMockStreamBuilder::new().actions(specs).build()
+    }
+}
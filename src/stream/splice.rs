@@ -0,0 +1,124 @@
+//! Editing of scenarios: concatenation, trimming and splicing.
+
+use std::collections::VecDeque;
+use std::ops::{Bound, RangeBounds};
+
+use super::{Action, CheckedMockStreamBuilder, Payload};
+
+fn bounds<R: RangeBounds<usize>>(range: &R, len: usize) -> (usize, usize) {
+    let start = match range.start_bound() {
+        Bound::Included(&n) => n,
+        Bound::Excluded(&n) => n + 1,
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&n) => n + 1,
+        Bound::Excluded(&n) => n,
+        Bound::Unbounded => len,
+    };
+    let end = end.min(len);
+    (start.min(end), end)
+}
+
+impl CheckedMockStreamBuilder {
+    /// Append actions of other scenario (placeholder bindings of other scenario are also merged).
+    pub fn concat(mut self, mut other: CheckedMockStreamBuilder) -> Self {
+        let len = self.actions.len();
+        other.remap_timings(|i| Some(i + len));
+        self.timings.append(&mut other.timings);
+        self.actions.extend(other.actions);
+        self.merge_vars(other.vars)
+    }
+
+    /// Keep only actions in range (by action index).
+    pub fn trim<R: RangeBounds<usize>>(mut self, range: R) -> Self {
+        let (start, end) = bounds(&range, self.actions.len());
+        self.actions.truncate(end);
+        self.actions.drain(..start);
+        self.remap_timings(|i| (start..end).contains(&i).then(|| i - start));
+        self
+    }
+
+    /// Keep only data in range of byte offsets (read and written data are counted together,
+    /// in scenario order). Payloads on range borders are cut, errors and waits inside range are kept.
+    pub fn trim_bytes<R: RangeBounds<usize>>(mut self, range: R) -> Self {
+        let total = self.actions.iter().map(payload_len).sum();
+        let (start, end) = bounds(&range, total);
+        let mut offset = 0;
+        let mut actions = VecDeque::with_capacity(self.actions.len());
+        // index of kept action for every action
+        let mut index = Vec::with_capacity(self.actions.len());
+        for action in std::mem::take(&mut self.actions) {
+            index.push(None);
+            let len = payload_len(&action);
+            let (from, to) = (offset, offset + len);
+            offset = to;
+            if len == 0 {
+                // errors and waits are kept inside range (and on the borders)
+                if from >= start && from <= end && start < end {
+                    *index.last_mut().unwrap() = Some(actions.len());
+                    actions.push_back(action);
+                }
+                continue;
+            }
+            if to <= start || from >= end {
+                continue;
+            }
+            let cut = |data: Payload| {
+                let lo = start.saturating_sub(from);
+                let hi = len - to.saturating_sub(end);
+                if lo == 0 && hi == len {
+                    data
+                } else {
+                    data.slice(lo..hi)
+                }
+            };
+            *index.last_mut().unwrap() = Some(actions.len());
+            actions.push_back(match action {
+                Action::Read(data) => Action::Read(cut(data)),
+                Action::Write(data) => Action::Write(cut(data)),
+                action => action,
+            });
+        }
+        self.actions = actions;
+        self.remap_timings(|i| index[i]);
+        self
+    }
+
+    /// Replace actions in range (by action index) with actions of other scenario
+    /// (placeholder bindings of other scenario are also merged).
+    pub fn splice<R: RangeBounds<usize>>(
+        mut self,
+        range: R,
+        mut other: CheckedMockStreamBuilder,
+    ) -> Self {
+        let (start, end) = bounds(&range, self.actions.len());
+        let len = other.actions.len();
+        self.remap_timings(|i| match i {
+            i if i < start => Some(i),
+            i if i >= end => Some(i - (end - start) + len),
+            _ => None,
+        });
+        other.remap_timings(|i| Some(i + start));
+        self.timings.append(&mut other.timings);
+        let tail = self.actions.split_off(end);
+        self.actions.truncate(start);
+        self.actions.extend(other.actions);
+        self.actions.extend(tail);
+        self.merge_vars(other.vars)
+    }
+
+    fn merge_vars(mut self, vars: Vec<(String, Vec<u8>)>) -> Self {
+        for (name, value) in vars {
+            self = self.bind(&name, value);
+        }
+        self
+    }
+}
+
+fn payload_len(action: &Action) -> usize {
+    match action {
+        Action::Read(data) | Action::Write(data) => data.len(),
+        _ => 0,
+    }
+}
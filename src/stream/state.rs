@@ -0,0 +1,93 @@
+//! Snapshot and restore of [`CheckedMockStream`] state.
+//!
+//! Test can branch at a protocol point: take a snapshot, run one continuation, restore and run
+//! an alternative continuation.
+
+use super::utf8::Utf8State;
+use super::{CheckedMockStream, PeerState};
+
+/// A snapshot of [`CheckedMockStream`] position in scenario and written data.
+///
+/// Written data, dropped in [`super::WrittenMode::LastBytes`] and [`super::WrittenMode::LastWrites`]
+/// modes after snapshot, can't be restored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamSnapshot {
+    action: usize,
+    write_action: usize,
+    pos: usize,
+    written_len: usize,
+    written_hash: u64,
+    divergences: usize,
+    peer: PeerState,
+    shutdown: bool,
+    // buffered writes in flush-gated mode
+    unflushed: Vec<u8>,
+    // counts of read, write and flush calls
+    calls: (usize, usize, usize),
+    utf8: Option<Utf8State>,
+}
+
+impl StreamSnapshot {
+    /// Gets an index of the current action.
+    pub fn action(&self) -> usize {
+        self.action
+    }
+
+    /// Gets a position in the current action.
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    /// Gets a length of written data.
+    pub fn written_len(&self) -> usize {
+        self.written_len
+    }
+}
+
+impl CheckedMockStream {
+    /// Capture the current action, position in action, written length, shutdown state,
+    /// unflushed data (in flush-gated mode) and counts of calls.
+    pub fn snapshot(&self) -> StreamSnapshot {
+        StreamSnapshot {
+            action: self.action,
+            write_action: self.write_action,
+            pos: self.pos,
+            written_len: self.written.len(),
+            written_hash: self.written.raw_hash(),
+            divergences: self.divergences.len(),
+            peer: self.peer,
+            shutdown: self.shutdown,
+            unflushed: self.unflushed.clone(),
+            calls: self.calls(),
+            utf8: self.utf8.clone(),
+        }
+    }
+
+    /// Restore state, captured by [`CheckedMockStream::snapshot`] (written data after snapshot and
+    /// divergences are dropped, shutdown state, unflushed data and counts of calls are restored).
+    pub fn restore(&mut self, snapshot: &StreamSnapshot) {
+        self.action = snapshot.action;
+        self.write_action = snapshot.write_action;
+        self.pos = snapshot.pos;
+        self.peer = snapshot.peer;
+        self.shutdown = snapshot.shutdown;
+        self.unflushed.clone_from(&snapshot.unflushed);
+        let (read, write, flush) = snapshot.calls;
+        self.calls.read = read;
+        self.calls.write = write;
+        self.calls.flush = flush;
+        self.utf8.clone_from(&snapshot.utf8);
+        let passed = snapshot.action.min(snapshot.write_action);
+        self.sync_rounds.retain(|&(i, _)| i < passed);
+        self.file = None;
+        self.heartbeat = None;
+        self.heartbeat_rest = None;
+        self.clear_waits();
+        self.reset_timing();
+        self.written
+            .truncate(snapshot.written_len, snapshot.written_hash);
+        self.divergences.truncate(snapshot.divergences);
+        #[cfg(feature = "tokio")]
+        self.wake();
+    }
+}
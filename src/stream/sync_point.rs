@@ -0,0 +1,185 @@
+//! Synchronization points of several streams (for multi-connection tests).
+//!
+//! A [`SyncPoint`] is shared by scenarios of several streams (like primary and failover connections)
+//! with [`CheckedMockStreamBuilder::sync_point`]. A stream reaches the point, when the previous action
+//! is completed (or by the first operation), and does not progress until all parties reach it
+//! (sync operation blocks the thread or returns `WouldBlock` error in non-blocking mode, async
+//! operation returns `Poll::Pending`). A point is reusable, like [`std::sync::Barrier`].
+
+use std::fmt;
+use std::io::{self, Error, ErrorKind};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+
+#[cfg(feature = "tokio")]
+use std::task::Waker;
+
+use super::{Action, CheckedMockStream, CheckedMockStreamBuilder, Step};
+
+/// A synchronization point of several streams.
+///
+/// ```
+/// use netmock::stream::{CheckedMockStreamBuilder, SyncPoint};
+///
+/// // failover connection is answered only after primary connection is reset
+/// let reset = SyncPoint::new(2);
+/// let primary = CheckedMockStreamBuilder::new()
+///     .write(b"PING\r\n")
+///     .read_error(std::io::ErrorKind::ConnectionReset.into())
+///     .sync_point(&reset)
+///     .build();
+/// let failover = CheckedMockStreamBuilder::new()
+///     .write(b"PING\r\n")
+///     .sync_point(&reset)
+///     .read(b"+PONG\r\n")
+///     .build();
+/// ```
+#[derive(Clone)]
+pub struct SyncPoint(Arc<Shared>);
+
+struct Shared {
+    parties: usize,
+    state: Mutex<State>,
+    cond: Condvar,
+}
+
+#[derive(Default)]
+struct State {
+    arrived: usize,
+    // count of passed rounds
+    generation: u64,
+    #[cfg(feature = "tokio")]
+    wakers: Vec<Waker>,
+}
+
+impl SyncPoint {
+    /// Creates a new synchronization point of `parties` streams (the last arrived releases all).
+    pub fn new(parties: usize) -> Self {
+        SyncPoint(Arc::new(Shared {
+            parties,
+            state: Mutex::new(State::default()),
+            cond: Condvar::new(),
+        }))
+    }
+
+    /// Gets a count of parties.
+    pub fn parties(&self) -> usize {
+        self.0.parties
+    }
+
+    /// Gets a count of streams, waiting at the point.
+    pub fn arrived(&self) -> usize {
+        self.lock().arrived
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.0.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // arrive at the point, returns round to wait for
+    fn arrive(&self) -> u64 {
+        let mut state = self.lock();
+        let generation = state.generation;
+        state.arrived += 1;
+        if state.arrived >= self.0.parties {
+            state.arrived = 0;
+            state.generation += 1;
+            self.0.cond.notify_all();
+            #[cfg(feature = "tokio")]
+            for waker in state.wakers.drain(..) {
+                waker.wake();
+            }
+        }
+        generation
+    }
+
+    fn passed(&self, generation: u64) -> bool {
+        self.lock().generation > generation
+    }
+
+    // block until round is passed (or return `WouldBlock` error in non-blocking mode)
+    pub(super) fn wait(&self, generation: u64, nonblocking: bool) -> io::Result<()> {
+        let mut state = self.lock();
+        while state.generation <= generation {
+            if nonblocking {
+                return Err(Error::new(
+                    ErrorKind::WouldBlock,
+                    format!(
+                        "waiting at sync point: {}/{} arrived",
+                        state.arrived, self.0.parties
+                    ),
+                ));
+            }
+            state = self
+                .0
+                .cond
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        Ok(())
+    }
+
+    // register waker of pending operation, returns `true` if round is already passed
+    #[cfg(feature = "tokio")]
+    pub(super) fn register(&self, generation: u64, waker: &Waker) -> bool {
+        let mut state = self.lock();
+        if state.generation > generation {
+            return true;
+        }
+        if !state.wakers.iter().any(|w| w.will_wake(waker)) {
+            state.wakers.push(waker.clone());
+        }
+        false
+    }
+}
+
+impl fmt::Debug for SyncPoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.lock();
+        f.debug_struct("SyncPoint")
+            .field("parties", &self.0.parties)
+            .field("arrived", &state.arrived)
+            .field("generation", &state.generation)
+            .finish()
+    }
+}
+
+impl CheckedMockStreamBuilder {
+    /// Queue a synchronization point, shared with scenarios of other streams:
+    /// the stream does not progress until all parties reach the point.
+    pub fn sync_point(mut self, point: &SyncPoint) -> Self {
+        self.actions.push_back(Action::SyncPoint(point.clone()));
+        self
+    }
+}
+
+impl CheckedMockStream {
+    // arrive at sync point, if it is the current action (passed immediately, if the last party)
+    pub(super) fn arrive_sync(&mut self) {
+        while let Some(Action::SyncPoint(point)) = self.actions.get(self.action) {
+            let point = point.clone();
+            if self.sync_step(&point).is_some() {
+                break;
+            }
+            self.action += 1;
+        }
+    }
+
+    // arrive at sync point of action (once per action, in duplex mode for both directions),
+    // returns step to wait, until all parties arrive
+    pub(super) fn sync_step(&mut self, point: &SyncPoint) -> Option<Step<usize>> {
+        let action = self.action;
+        let generation = match self.sync_rounds.iter().find(|(i, _)| *i == action) {
+            Some(&(_, generation)) => generation,
+            None => {
+                let generation = point.arrive();
+                self.sync_rounds.push((action, generation));
+                generation
+            }
+        };
+        if point.passed(generation) {
+            None
+        } else {
+            Some(Step::Sync(point.clone(), generation))
+        }
+    }
+}
@@ -0,0 +1,70 @@
+//! Parameterized scenario templates.
+//!
+//! Read and write payloads can contain `{name}` placeholders, which are replaced by values bound with
+//! [`CheckedMockStreamBuilder::bind`] at build time. Placeholders without bound value are kept as is,
+//! so payloads with braces (like JSON) are not affected.
+
+use std::collections::VecDeque;
+
+use super::{Action, CheckedMockStreamBuilder, Payload};
+
+impl CheckedMockStreamBuilder {
+    /// Bind value for `{name}` placeholder in read and write payloads (replaced at build time).
+    pub fn bind<V: AsRef<[u8]>>(mut self, name: &str, value: V) -> Self {
+        let value = value.as_ref().to_vec();
+        match self.vars.iter_mut().find(|(n, _)| n == name) {
+            Some((_, v)) => *v = value,
+            None => self.vars.push((name.to_string(), value)),
+        }
+        self
+    }
+
+    // actions with substituted placeholders
+    pub(super) fn render(self) -> VecDeque<Action> {
+        if self.vars.is_empty() {
+            return self.actions;
+        }
+        let vars = self.vars;
+        self.actions
+            .into_iter()
+            .map(|action| match action {
+                Action::Read(data) => Action::Read(substitute(data, &vars)),
+                Action::Write(data) => Action::Write(substitute(data, &vars)),
+                Action::ReadEvery(data, interval) => {
+                    Action::ReadEvery(substitute(data, &vars), interval)
+                }
+                action => action,
+            })
+            .collect()
+    }
+}
+
+fn substitute(data: Payload, vars: &[(String, Vec<u8>)]) -> Payload {
+    if !data.contains(&b'{') {
+        return data;
+    }
+    let mut buf = Vec::with_capacity(data.len());
+    let mut rest = &data[..];
+    while let Some(start) = rest.iter().position(|&c| c == b'{') {
+        buf.extend_from_slice(&rest[..start]);
+        rest = &rest[start..];
+        let value = rest[1..].iter().position(|&c| c == b'}').and_then(|end| {
+            let name = &rest[1..end + 1];
+            vars.iter()
+                .find(|(n, _)| n.as_bytes() == name)
+                .map(|(_, v)| (v, end + 2))
+        });
+        match value {
+            Some((value, len)) => {
+                buf.extend_from_slice(value);
+                rest = &rest[len..];
+            }
+            None => {
+                buf.push(b'{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    buf.extend_from_slice(rest);
+    buf.into()
+}
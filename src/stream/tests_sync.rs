@@ -4,6 +4,13 @@ use super::CheckedMockStreamBuilder;
 
 use super::SimpleMockStream;
 
+use super::CurrentAction;
+
+use super::{
+    hash_bytes, Chaos, Divergence, ExhaustedWrite, Fault, FaultStream, MismatchPolicy, ReadMode,
+    RecordingStream, WrittenMode,
+};
+
 use std::io::Error;
 use std::{
     io::{Read, Write},
@@ -87,7 +94,7 @@ fn checked_mockstream() {
 
     let result = stream.write_all(b"Success\n");
     assert!(result.is_err());
-    assert_eq!(stream.written(), &vec![]);
+    assert_eq!(stream.written(), &Vec::<u8>::new());
 
     let written = stream.write(b"Success\n").unwrap();
     assert_eq!(written, 0);
@@ -105,7 +112,7 @@ fn checked_mockstream() {
 
     let result = stream.write_all(b"Missmatch");
     assert!(result.is_err());
-    assert_eq!(stream.written(), &vec![]);
+    assert_eq!(stream.written(), &Vec::<u8>::new());
 
     stream.seek_action(3);
     let result = stream.write_all(b"Success\n");
@@ -198,7 +205,7 @@ fn checked_mockstream_error() {
 
     let result = stream.write_all(b"Success\n");
     assert!(result.is_err());
-    assert_eq!(stream.written(), &vec![]);
+    assert_eq!(stream.written(), &Vec::<u8>::new());
 
     buf.clear();
     let readed = stream.read_to_end(&mut buf).unwrap();
@@ -210,7 +217,7 @@ fn checked_mockstream_error() {
 
     let result = stream.write_all(b"Error\n");
     assert!(result.is_err());
-    assert_eq!(stream.written(), &vec![]);
+    assert_eq!(stream.written(), &Vec::<u8>::new());
 
     let result = stream.write_all(b"Success\n");
     assert!(result.is_ok(), "{}", result.err().unwrap());
@@ -227,3 +234,2215 @@ fn checked_mockstream_error() {
     assert_eq!(&buf, b"Third\n");
     assert_eq!(readed, 6);
 }
+
+#[cfg(feature = "serde")]
+#[test]
+fn checked_mockstream_serde() {
+    let yaml = r#"
+actions:
+  - read: "+OK ready\r\n"
+  - write: { hex: "51 55 49 54 0d 0a" }
+  - wait: 10ms
+  - read: { base64: "K09LDQo=" }
+  - read_error: { kind: TimedOut, message: "read timeout" }
+  - write_error: broken_pipe
+  - wait: 5
+"#;
+    let builder: CheckedMockStreamBuilder = serde_yaml::from_str(yaml).unwrap();
+    let mut stream = builder.clone().build();
+
+    let mut buf = [0; 11];
+    stream.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"+OK ready\r\n");
+    stream.write_all(b"QUIT\r\n").unwrap();
+    let mut buf = [0; 5];
+    stream.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"+OK\r\n");
+    let err = stream.read(&mut buf).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+    assert_eq!(err.to_string(), "read timeout");
+    let err = stream.write(b"data").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::BrokenPipe);
+
+    let json = serde_json::to_string(&builder).unwrap();
+    assert_eq!(
+        json,
+        r#"{"actions":[{"read":"+OK ready\r\n"},{"write":"QUIT\r\n"},{"wait":"10ms"},{"read":"+OK\r\n"},{"read_error":{"kind":"TimedOut","message":"read timeout"}},{"write_error":{"kind":"BrokenPipe","message":"broken pipe"}},{"wait":"5ms"}]}"#
+    );
+    let restored: CheckedMockStreamBuilder = serde_json::from_str(&json).unwrap();
+    assert_eq!(serde_json::to_string(&restored).unwrap(), json);
+
+    let binary = CheckedMockStreamBuilder::new().read(vec![0xff, 0x00]);
+    assert_eq!(
+        serde_json::to_string(&binary).unwrap(),
+        r#"{"actions":[{"read":{"hex":"ff00"}}]}"#
+    );
+
+    assert!(serde_json::from_str::<CheckedMockStreamBuilder>(r#"{"actions":[{"wait":"1h"}]}"#).is_err());
+    assert!(serde_json::from_str::<CheckedMockStreamBuilder>(r#"{"actions":[{"read":{"hex":"f"}}]}"#).is_err());
+    assert!(serde_json::from_str::<CheckedMockStreamBuilder>(r#"{"actions":[{"read":"a","write":"b"}]}"#).is_err());
+    assert!(serde_json::from_str::<CheckedMockStreamBuilder>(r#"{"actions":[{"barrier":false}]}"#).is_err());
+    assert!(serde_json::from_str::<CheckedMockStreamBuilder>(r#"{"actions":[{"within":"1s"}]}"#).is_err());
+
+    // unknown error kind is loaded as Other
+    let mut stream = serde_json::from_str::<CheckedMockStreamBuilder>(
+        r#"{"actions":[{"read_error":"QuotaExceeded"},{"read_error":{"kind":"QuotaExceeded","message":"quota"}}]}"#,
+    )
+    .unwrap()
+    .build();
+    let err = stream.read(&mut buf).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::Other);
+    assert_eq!(err.to_string(), "QuotaExceeded");
+    let err = stream.read(&mut buf).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::Other);
+    assert_eq!(err.to_string(), "quota");
+
+    // settings and timings
+    let builder = CheckedMockStreamBuilder::new()
+        .duplex()
+        .flush_gated()
+        .read_mode(ReadMode::Message)
+        .bind("user", "admin")
+        .write(b"USER {user}\r\n")
+        .within(Duration::from_secs(1))
+        .barrier()
+        .read(b"+OK\r\n")
+        .after(Duration::from_millis(10));
+    let json = serde_json::to_string(&builder).unwrap();
+    assert_eq!(
+        json,
+        r#"{"duplex":true,"flush_gated":true,"read_mode":"message","vars":{"user":"admin"},"actions":[{"write":"USER {user}\r\n","within":"1s"},{"barrier":true},{"read":"+OK\r\n","after":"10ms"}]}"#
+    );
+    let restored: CheckedMockStreamBuilder = serde_json::from_str(&json).unwrap();
+    assert_eq!(serde_json::to_string(&restored).unwrap(), json);
+    let mut stream = restored.build();
+    stream.write_all(b"USER admin\r\n").unwrap();
+    stream.flush().unwrap();
+    assert_eq!(stream.written(), b"USER admin\r\n");
+
+    let err = serde_json::to_string(&CheckedMockStreamBuilder::new().chaos(Chaos::new(1))).unwrap_err();
+    assert_eq!(err.to_string(), "chaos can't be serialized");
+    assert!(serde_json::to_string(&CheckedMockStreamBuilder::new().on_mismatch(MismatchPolicy::Panic)).is_err());
+}
+
+#[test]
+fn checked_mockstream_transcript() {
+    let transcript = r"
+# greeting
+R: +OK ready\r\n
+W: USER foo\r\n
+WAIT: 10ms
+r: +OK\x20\r\n
+RERR: timeout
+WERR: BrokenPipe connection closed
+";
+    let builder = CheckedMockStreamBuilder::from_transcript(transcript).unwrap();
+    let mut stream = builder.clone().build();
+
+    let mut buf = [0; 11];
+    stream.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"+OK ready\r\n");
+    stream.write_all(b"USER foo\r\n").unwrap();
+    let mut buf = [0; 6];
+    stream.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"+OK \r\n");
+    let err = stream.read(&mut buf).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+    let err = stream.write(b"QUIT\r\n").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::BrokenPipe);
+    assert_eq!(err.to_string(), "connection closed");
+
+    let text = builder.to_transcript();
+    assert_eq!(
+        text,
+        "R: +OK ready\\r\\n\nW: USER foo\\r\\n\nWAIT: 10ms\nR: +OK \\r\\n\nRERR: TimedOut timed out\nWERR: BrokenPipe connection closed\n"
+    );
+    let restored = CheckedMockStreamBuilder::from_transcript(&text).unwrap();
+    assert_eq!(restored.to_transcript(), text);
+    assert_eq!(
+        CheckedMockStreamBuilder::new()
+            .read(vec![b' ', 0xff, 0, b'\\'])
+            .to_transcript(),
+        "R: \\x20\\xff\\0\\\\\n"
+    );
+
+    let err = CheckedMockStreamBuilder::from_transcript("R: ok\nX: data").unwrap_err();
+    assert_eq!(err.to_string(), "line 2: unknown action tag: \"X\"");
+    assert!(CheckedMockStreamBuilder::from_transcript("WAIT: 1h").is_err());
+    assert!(CheckedMockStreamBuilder::from_transcript("RERR: unknown").is_err());
+    assert!(CheckedMockStreamBuilder::from_transcript("R: \\xZZ").is_err());
+    assert!(CheckedMockStreamBuilder::from_transcript("R no tag").is_err());
+}
+
+#[cfg(feature = "pcap")]
+#[test]
+fn checked_mockstream_pcap() {
+    fn record(pcap: &mut Vec<u8>, src: u16, dst: u16, seq: u32, flags: u8, payload: &[u8]) {
+        let mut frame = vec![0; 12];
+        frame.extend_from_slice(&[0x08, 0x00]);
+        let total = (20 + 20 + payload.len()) as u16;
+        frame.extend_from_slice(&[0x45, 0, (total >> 8) as u8, total as u8, 0, 0, 0, 0, 64, 6, 0, 0]);
+        frame.extend_from_slice(&[127, 0, 0, 1, 127, 0, 0, 1]);
+        frame.extend_from_slice(&src.to_be_bytes());
+        frame.extend_from_slice(&dst.to_be_bytes());
+        frame.extend_from_slice(&seq.to_be_bytes());
+        frame.extend_from_slice(&[0, 0, 0, 0, 0x50, flags, 0xff, 0xff, 0, 0, 0, 0]);
+        frame.extend_from_slice(payload);
+
+        pcap.extend_from_slice(&[0; 8]);
+        pcap.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        pcap.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        pcap.extend(frame);
+    }
+
+    let mut pcap = vec![0xd4, 0xc3, 0xb2, 0xa1, 2, 0, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    pcap.extend_from_slice(&[0xff, 0xff, 0, 0, 1, 0, 0, 0]);
+    record(&mut pcap, 40000, 6379, 100, 0x02, b""); // SYN
+    record(&mut pcap, 6379, 40000, 500, 0x12, b""); // SYN-ACK
+    record(&mut pcap, 40000, 6379, 101, 0x18, b"PING\r\n");
+    record(&mut pcap, 40000, 6379, 101, 0x18, b"PING\r\n"); // retransmission
+    record(&mut pcap, 1000, 53, 1, 0x18, b"other"); // other conversation
+    record(&mut pcap, 6379, 40000, 501, 0x18, b"+PONG\r\n");
+    record(&mut pcap, 40000, 6379, 107, 0x18, b"QUIT\r\n");
+    record(&mut pcap, 6379, 40000, 508, 0x18, b"+OK\r\n");
+
+    let builder = CheckedMockStreamBuilder::from_pcap(&pcap, 40000, 6379).unwrap();
+    assert_eq!(
+        builder.to_transcript(),
+        "W: PING\\r\\n\nR: +PONG\\r\\n\nW: QUIT\\r\\n\nR: +OK\\r\\n\n"
+    );
+
+    let mut stream = builder.build();
+    stream.write_all(b"PING\r\n").unwrap();
+    let mut buf = [0; 7];
+    stream.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"+PONG\r\n");
+
+    assert!(CheckedMockStreamBuilder::from_pcap(&pcap[..30], 40000, 6379).is_err());
+    assert!(CheckedMockStreamBuilder::from_pcap(b"not a pcap file at all!!", 1, 2).is_err());
+}
+
+#[cfg(feature = "har")]
+#[test]
+fn checked_mockstream_har() {
+    let har = r#"{"log": {"version": "1.2", "entries": [
+        {
+            "request": {
+                "method": "GET",
+                "url": "https://example.com/api/items?limit=1",
+                "httpVersion": "HTTP/2",
+                "headers": [
+                    {"name": ":authority", "value": "example.com"},
+                    {"name": "accept", "value": "application/json"}
+                ]
+            },
+            "response": {
+                "status": 200,
+                "statusText": "OK",
+                "headers": [
+                    {"name": "content-type", "value": "application/json"},
+                    {"name": "content-encoding", "value": "gzip"},
+                    {"name": "content-length", "value": "123"}
+                ],
+                "content": {"size": 7, "mimeType": "application/json", "text": "[\"a\"]\n"}
+            }
+        },
+        {
+            "request": {
+                "method": "POST",
+                "url": "http://example.com:8080?x=1",
+                "headers": [],
+                "postData": {"mimeType": "text/plain", "text": "hello"}
+            },
+            "response": {
+                "status": 204,
+                "statusText": "No Content",
+                "headers": [],
+                "content": {"size": 2, "text": "AAE=", "encoding": "base64"}
+            }
+        }
+    ]}}"#;
+    let builder = CheckedMockStreamBuilder::from_har(har).unwrap();
+    assert_eq!(
+        builder.to_transcript(),
+        "W: GET /api/items?limit=1 HTTP/1.1\\r\\nHost: example.com\\r\\naccept: application/json\\r\\n\\r\\n\n\
+         R: HTTP/1.1 200 OK\\r\\ncontent-type: application/json\\r\\nContent-Length: 6\\r\\n\\r\\n[\"a\"]\\n\n\
+         W: POST /?x=1 HTTP/1.1\\r\\nHost: example.com:8080\\r\\nContent-Length: 5\\r\\n\\r\\nhello\n\
+         R: HTTP/1.1 204 No Content\\r\\nContent-Length: 2\\r\\n\\r\\n\\0\\x01\n"
+    );
+
+    let mut stream = builder.build();
+    stream
+        .write_all(b"GET /api/items?limit=1 HTTP/1.1\r\nHost: example.com\r\naccept: application/json\r\n\r\n")
+        .unwrap();
+    let mut buf = Vec::new();
+    stream.read_to_end(&mut buf).unwrap();
+    assert!(buf.ends_with(b"\r\n\r\n[\"a\"]\n"));
+
+    assert!(CheckedMockStreamBuilder::from_har("{}").is_err());
+    assert!(CheckedMockStreamBuilder::from_har(
+        r#"{"log": {"entries": [{"request": {"method": "GET", "url": "/"}, "response": {"status": 200}}]}}"#
+    )
+    .is_err());
+}
+
+#[test]
+fn recording_stream() {
+    let server = CheckedMockStreamBuilder::new()
+        .read(b"+OK ready\r\n".to_vec())
+        .write(b"USER foo\r\n".to_vec())
+        .wait(Duration::from_millis(20))
+        .read(b"+OK\r\n".to_vec())
+        .write_error(Error::new(std::io::ErrorKind::BrokenPipe, "connection closed"))
+        .build();
+    let mut stream = RecordingStream::new(server);
+
+    let mut buf = [0; 4];
+    stream.read_exact(&mut buf).unwrap();
+    let mut buf = [0; 7];
+    stream.read_exact(&mut buf).unwrap();
+    stream.write_all(b"USER foo\r\n").unwrap();
+    let mut buf = [0; 5];
+    stream.read_exact(&mut buf).unwrap();
+    assert!(stream.write(b"QUIT\r\n").is_err());
+
+    assert_eq!(stream.events().len(), 5);
+    assert!(stream.events()[3].elapsed >= Duration::from_millis(20));
+
+    let builder = stream.to_builder_min_wait(Duration::MAX);
+    assert_eq!(
+        builder.to_transcript(),
+        "R: +OK ready\\r\\n\nW: USER foo\\r\\n\nR: +OK\\r\\n\nWERR: BrokenPipe connection closed\n"
+    );
+    let text = stream.to_builder().to_transcript();
+    let tags: Vec<&str> = text.lines().map(|l| l.split(':').next().unwrap()).collect();
+    assert_eq!(tags, ["R", "W", "WAIT", "R", "WERR"]);
+
+    // replay
+    let mut replay = builder.build();
+    let mut buf = [0; 11];
+    replay.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"+OK ready\r\n");
+    replay.write_all(b"USER foo\r\n").unwrap();
+}
+
+#[test]
+fn checked_mockstream_to_rust() {
+    let builder = CheckedMockStreamBuilder::new()
+        .read(b"+OK \"ready\"\r\n".to_vec())
+        .write(vec![0, 0xff, b'\\'])
+        .wait(Duration::from_millis(1500))
+        .wait(Duration::from_nanos(10))
+        .read_error(Error::new(std::io::ErrorKind::TimedOut, "read timeout"));
+    assert_eq!(
+        builder.to_rust(),
+        r#"CheckedMockStreamBuilder::new()
+    .read(b"+OK \"ready\"\r\n".to_vec())
+    .write(b"\0\xff\\".to_vec())
+    .wait(std::time::Duration::from_millis(1500))
+    .wait(std::time::Duration::from_nanos(10))
+    .read_error(std::io::Error::new(std::io::ErrorKind::TimedOut, "read timeout"))
+"#
+    );
+}
+
+#[test]
+fn fault_stream() {
+    let upstream = SimpleMockStream::new(b"0123456789".to_vec());
+    let mut stream = FaultStream::new(upstream)
+        .on_read(2, Fault::Corrupt(0x20))
+        .on_read(4, Fault::Delay(Duration::from_millis(20)))
+        .on_read(6, Fault::Reset)
+        .on_write(3, Fault::Corrupt(0x01))
+        .on_write(5, Fault::Truncate);
+
+    let mut buf = [0; 10];
+    let readed = stream.read(&mut buf).unwrap();
+    assert_eq!(&buf[..readed], b"01\x123");
+    let now = std::time::Instant::now();
+    let readed = stream.read(&mut buf).unwrap();
+    assert!(now.elapsed() >= Duration::from_millis(20));
+    assert_eq!(&buf[..readed], b"45");
+    let err = stream.read(&mut buf).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::ConnectionReset);
+    assert_eq!(stream.readed(), 6);
+
+    stream.write_all(b"abcdefgh").unwrap();
+    assert_eq!(stream.written(), 8);
+    assert_eq!(stream.get_ref().written(), b"abcee");
+}
+
+#[test]
+fn checked_mockstream_dump() {
+    let mut stream = CheckedMockStreamBuilder::new()
+        .read(b"+OK ready\r\n".to_vec())
+        .read(b"First\nSecond\n".to_vec())
+        .wait(Duration::from_millis(10))
+        .write(b"QUIT\r\n".to_vec())
+        .write_error(Error::new(std::io::ErrorKind::BrokenPipe, "closed"))
+        .read(vec![b'x'; 40])
+        .build();
+
+    let mut buf = [0; 17];
+    stream.read_exact(&mut buf).unwrap();
+    assert_eq!(
+        stream.dump(),
+        r#"    0 done     R 11 bytes "+OK ready\r\n"
+>   1 partial  R 13 bytes "First\nSecond\n" (6/13)
+    2 pending  WAIT 10ms
+    3 pending  W 6 bytes "QUIT\r\n"
+    4 pending  WERR BrokenPipe closed
+    5 pending  R 40 bytes "xxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxx"...
+"#
+    );
+
+    stream.seek_action(6);
+    assert!(stream.dump().ends_with("    5 done     R 40 bytes \"xxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxx\"...\n>     end\n"));
+}
+
+#[test]
+fn checked_mockstream_golden_file() {
+    let dir = std::env::temp_dir().join(format!("netmock-golden-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("written.bin");
+    std::fs::write(&path, b"SET key 0 0 5\r\nvalue\r\n").unwrap();
+
+    let mut stream = CheckedMockStreamBuilder::new()
+        .write(b"SET key 0 0 5\r\n".to_vec())
+        .write(b"value\r\n".to_vec())
+        .build();
+    stream.write_all(b"SET key 0 0 5\r\n").unwrap();
+    stream.write_all(b"value\r\n").unwrap();
+    stream.assert_written_matches_file(&path);
+
+    let mut stream = SimpleMockStream::empty();
+    stream.write_all(b"SET key 0 0 5\r\nvalux\r\n").unwrap();
+    let err = std::panic::catch_unwind(|| stream.assert_written_matches_file(&path)).unwrap_err();
+    let msg = err.downcast_ref::<String>().unwrap();
+    assert!(msg.contains("at offset 19 (written 22 bytes, golden 22 bytes)"), "{}", msg);
+    assert!(msg.contains("written: \"x\\r\\n\""), "{}", msg);
+
+    let missing = dir.join("missing.bin");
+    assert!(std::panic::catch_unwind(|| stream.assert_written_matches_file(&missing)).is_err());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(feature = "insta")]
+#[test]
+fn checked_mockstream_snapshot() {
+    let mut stream = SimpleMockStream::empty();
+    stream.write_all(b"SET key 0 0 5\r\nvalue\r\n\x00\xff").unwrap();
+    assert_eq!(
+        stream.written_snapshot(),
+        "00000000  53 45 54 20 6b 65 79 20  30 20 30 20 35 0d 0a 76  |SET key 0 0 5..v|\n\
+         00000010  61 6c 75 65 0d 0a 00 ff                           |alue....|\n\
+         --\n\
+         SET key 0 0 5\\r\nvalue\\r\n\\x00\u{fffd}"
+    );
+    crate::assert_written_snapshot!(stream, @r"
+    00000000  53 45 54 20 6b 65 79 20  30 20 30 20 35 0d 0a 76  |SET key 0 0 5..v|
+    00000010  61 6c 75 65 0d 0a 00 ff                           |alue....|
+    --
+    SET key 0 0 5\r
+    value\r
+    \x00�
+    ");
+}
+
+#[test]
+fn checked_mockstream_template() {
+    let template = CheckedMockStreamBuilder::new()
+        .read(b"+OK session {session_id}\r\n".to_vec())
+        .write(b"GET {session_id} {len} {unknown}\r\n".to_vec())
+        .read(b"{\"id\": {session_id}}\r\n".to_vec());
+
+    for (id, len) in [("1", 10), ("42", 200)] {
+        let mut stream = template
+            .clone()
+            .bind("session_id", id)
+            .bind("len", len.to_string())
+            .build_cap();
+        let mut buf = String::new();
+        stream.read_to_string(&mut buf).unwrap();
+        assert_eq!(buf, format!("+OK session {}\r\n", id));
+        let cmd = format!("GET {} {} {{unknown}}\r\n", id, len);
+        stream.write_all(cmd.as_bytes()).unwrap();
+        assert_eq!(stream.written(), cmd.as_bytes());
+        buf.clear();
+        stream.read_to_string(&mut buf).unwrap();
+        assert_eq!(buf, format!("{{\"id\": {}}}\r\n", id));
+    }
+}
+
+#[cfg(feature = "proptest")]
+mod arbitrary {
+    use std::time::Duration;
+
+    use proptest::prelude::*;
+
+    use super::super::{CheckedMockStreamBuilder, ScenarioParams};
+
+    fn params() -> ScenarioParams {
+        ScenarioParams {
+            max_actions: 8,
+            max_wait: Duration::from_millis(1),
+            ..Default::default()
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn checked_mockstream_arbitrary(builder in any_with::<CheckedMockStreamBuilder>(params())) {
+            let text = builder.to_transcript();
+            prop_assert!(text.lines().count() <= 8);
+            let restored = CheckedMockStreamBuilder::from_transcript(&text).unwrap();
+            prop_assert_eq!(restored.to_transcript(), text);
+            prop_assert_eq!(restored.to_rust(), builder.to_rust());
+        }
+    }
+}
+
+#[test]
+fn checked_mockstream_chaos() {
+    let builder = CheckedMockStreamBuilder::new()
+        .read(b"+OK ready\r\n".to_vec())
+        .write(b"USER foo\r\n".to_vec())
+        .read(b"+OK user accepted\r\n".to_vec());
+
+    let run = |seed: u64| {
+        let mut stream = builder
+            .clone()
+            .chaos(
+                Chaos::new(seed)
+                    .errors(0.3, std::io::ErrorKind::Interrupted)
+                    .short_reads(0.5)
+                    .delays(0.2, Duration::from_millis(1)),
+            )
+            .build();
+        assert_eq!(stream.chaos_seed(), Some(seed));
+        let mut reads = Vec::new();
+        let mut errors = 0;
+        let mut data = Vec::new();
+        while data.len() < 11 {
+            let mut buf = [0; 64];
+            match stream.read(&mut buf[..11 - data.len()]) {
+                Ok(n) => {
+                    reads.push(n);
+                    data.extend_from_slice(&buf[..n]);
+                }
+                Err(err) => {
+                    assert_eq!(err.kind(), std::io::ErrorKind::Interrupted);
+                    assert_eq!(err.to_string(), format!("chaos (seed {}): injected error", seed));
+                    errors += 1;
+                }
+            }
+        }
+        assert_eq!(&data, b"+OK ready\r\n");
+        stream.write_all(b"USER foo\r\n").unwrap();
+        let mut buf = [0; 19];
+        stream.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"+OK user accepted\r\n");
+        assert!(stream.dump().starts_with(&format!("chaos seed: {}\n", seed)));
+        (reads, errors)
+    };
+
+    let (reads, errors) = run(42);
+    assert_eq!(run(42), (reads.clone(), errors));
+    let runs: Vec<_> = (0..8).map(run).collect();
+    assert!(runs.iter().any(|(reads, _)| reads.len() > 1));
+    assert!(runs.iter().any(|(_, errors)| *errors > 0));
+}
+
+#[test]
+fn checked_mockstream_diff() {
+    let mut stream = CheckedMockStreamBuilder::new()
+        .write(b"USER foo\r\n".to_vec())
+        .read(b"+OK\r\n".to_vec())
+        .write(b"QUIT\r\n".to_vec())
+        .build();
+
+    assert!(stream.write(b"USER bar\r\n").is_err());
+    stream.write_all(b"USER foo\r\n").unwrap();
+    assert_eq!(stream.write(b"PASS x\r\n").unwrap(), 0);
+    let mut buf = [0; 3];
+    stream.read_exact(&mut buf).unwrap();
+    assert!(!stream.is_done());
+
+    let diff = stream.diff();
+    assert_eq!(
+        diff[0],
+        Divergence::MismatchedWrite {
+            action: 0,
+            offset: 5,
+            expected: b"USER foo\r\n".to_vec(),
+            written: b"USER bar\r\n".to_vec(),
+        }
+    );
+    let text: Vec<String> = diff.iter().map(|d| d.to_string()).collect();
+    assert_eq!(
+        text,
+        [
+            "action 0: written data mismatch at offset 5: expected 5 bytes \"foo\\r\\n\" at offset, written 5 bytes \"bar\\r\\n\"",
+            "action 1: unexpected write 8 bytes \"PASS x\\r\\n\"",
+            "action 1: missing R 2 bytes \"\\r\\n\" (partially readed 3/5)",
+            "action 2: missing W 6 bytes \"QUIT\\r\\n\"",
+        ]
+    );
+    let err = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| stream.assert_done())).unwrap_err();
+    let msg = err.downcast_ref::<String>().unwrap();
+    assert!(msg.starts_with("scenario diverged:\n  action 0: written data mismatch"), "{}", msg);
+    assert!(msg.contains("scenario:\n    0 done     W 10 bytes"), "{}", msg);
+
+    stream.read_exact(&mut buf[..2]).unwrap();
+    assert_eq!(stream.read(&mut buf).unwrap(), 0);
+    assert_eq!(stream.read(&mut buf).unwrap(), 0);
+    stream.write_all(b"QUIT\r\n").unwrap();
+    assert!(stream.is_done());
+    assert_eq!(stream.diff().len(), 3);
+    assert_eq!(stream.diff()[2], Divergence::UnexpectedRead { action: 2 });
+
+    stream.reset_actions();
+    let mut buf = [0; 5];
+    stream.write_all(b"USER foo\r\n").unwrap();
+    stream.read_exact(&mut buf).unwrap();
+    stream.write_all(b"QUIT\r\n").unwrap();
+    stream.assert_done();
+}
+
+#[test]
+fn checked_mockstream_splice() {
+    let recorded = CheckedMockStreamBuilder::from_transcript(
+        r"
+R: +OK ready\r\n
+W: AUTH secret-token\r\n
+R: +OK\r\n
+W: GET key\r\n
+WAIT: 1ms
+R: $5\r\nvalue\r\n
+W: QUIT\r\n
+",
+    )
+    .unwrap();
+
+    let auth = CheckedMockStreamBuilder::new()
+        .write(b"AUTH {token}\r\n".to_vec())
+        .read(b"+OK\r\n".to_vec())
+        .bind("token", "test");
+    let mut stream = recorded.clone().splice(1..3, auth).build();
+    let mut buf = [0; 11];
+    stream.read_exact(&mut buf).unwrap();
+    stream.write_all(b"AUTH test\r\n").unwrap();
+
+    assert_eq!(
+        recorded.clone().trim(3..=5).to_transcript(),
+        "W: GET key\\r\\n\nWAIT: 1ms\nR: $5\\r\\nvalue\\r\\n\n"
+    );
+    assert_eq!(
+        recorded
+            .clone()
+            .trim(5..)
+            .concat(recorded.clone().trim(..1))
+            .to_transcript(),
+        "R: $5\\r\\nvalue\\r\\n\nW: QUIT\\r\\n\nR: +OK ready\\r\\n\n"
+    );
+    // offsets: R 0..11, W 11..30, R 30..35, W 35..44, WAIT 44, R 44..55
+    assert_eq!(
+        recorded.clone().trim_bytes(32..48).to_transcript(),
+        "R: K\\r\\n\nW: GET key\\r\\n\nWAIT: 1ms\nR: $5\\r\\n\n"
+    );
+    assert_eq!(recorded.clone().trim(10..).to_transcript(), "");
+}
+
+#[cfg(feature = "bytes")]
+#[test]
+fn checked_mockstream_bytes() {
+    let fixture = bytes::Bytes::from(vec![b'x'; 1 << 20]);
+    let builder = CheckedMockStreamBuilder::new()
+        .write(bytes::Bytes::from_static(b"GET /large\r\n"))
+        .read(fixture.clone());
+
+    for _ in 0..2 {
+        let mut stream = builder.clone().build();
+        stream.write_all(b"GET /large\r\n").unwrap();
+        let mut buf = Vec::new();
+        stream.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, fixture);
+    }
+    assert_eq!(
+        builder.trim_bytes(..14).to_transcript(),
+        "W: GET /large\\r\\n\nR: xx\n"
+    );
+}
+
+#[test]
+fn checked_mockstream_segmented_written() {
+    let chunk = vec![b'x'; 40 * 1024];
+    let mut stream = CheckedMockStreamBuilder::new()
+        .write(chunk.clone())
+        .write(chunk.clone())
+        .write(b"end".to_vec())
+        .written_mode(WrittenMode::Segmented)
+        .build_cap();
+
+    assert_eq!(stream.written(), b"");
+    stream.write_all(&chunk).unwrap();
+    assert_eq!(stream.written(), &chunk[..]);
+    stream.write_all(&chunk).unwrap();
+    assert_eq!(stream.written_segments().count(), 2);
+    assert_eq!(stream.written_len(), 80 * 1024);
+    assert_eq!(stream.written().len(), 80 * 1024);
+    stream.write_all(b"end").unwrap();
+    assert_eq!(stream.written_segments().count(), 2);
+    assert!(stream.written().ends_with(b"xxend"));
+    assert_eq!(stream.written_to_vec(), stream.written());
+
+    stream.reset_written();
+    assert_eq!(stream.written_len(), 0);
+    assert_eq!(stream.written(), b"");
+    assert_eq!(stream.written_segments().count(), 0);
+}
+
+#[test]
+fn checked_mockstream_ring_written() {
+    let writes: Vec<Vec<u8>> = (0..100).map(|i| format!("msg {}\n", i).into_bytes()).collect();
+    let builder = writes
+        .iter()
+        .fold(CheckedMockStreamBuilder::new(), |b, w| b.write(w.clone()));
+
+    let mut stream = builder.clone().written_mode(WrittenMode::LastBytes(15)).build();
+    for w in &writes {
+        stream.write_all(w).unwrap();
+    }
+    assert_eq!(stream.written(), b"\nmsg 98\nmsg 99\n");
+    assert_eq!(stream.written_len(), writes.iter().map(Vec::len).sum::<usize>());
+
+    let mut stream = builder.written_mode(WrittenMode::LastWrites(2)).build();
+    for w in &writes {
+        stream.write_all(w).unwrap();
+    }
+    assert_eq!(stream.written(), b"msg 98\nmsg 99\n");
+    let segments: Vec<&[u8]> = stream.written_segments().collect();
+    assert_eq!(segments, [&b"msg 98\n"[..], &b"msg 99\n"[..]]);
+}
+
+#[test]
+fn checked_mockstream_read_file() {
+    let dir = std::env::temp_dir().join(format!("netmock-file-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("payload.bin");
+    let payload: Vec<u8> = (0..100_000u32).map(|i| i as u8).collect();
+    std::fs::write(&path, &payload).unwrap();
+
+    let builder = CheckedMockStreamBuilder::new()
+        .write(b"GET /file\r\n".to_vec())
+        .read_file(&path)
+        .read(b"END".to_vec())
+        .read_file(dir.join("missing.bin"));
+    let mut stream = builder.clone().build();
+    stream.write_all(b"GET /file\r\n").unwrap();
+    let mut buf = vec![0; payload.len() + 3];
+    stream.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf[..payload.len()], &payload[..]);
+    assert_eq!(&buf[payload.len()..], b"END");
+    let err = stream.read(&mut buf).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    assert!(stream.is_done());
+
+    let text = builder.to_transcript();
+    assert!(text.contains(&format!("RFILE: {}\n", path.display())), "{}", text);
+    let restored = CheckedMockStreamBuilder::from_transcript(&text).unwrap();
+    assert_eq!(restored.to_transcript(), text);
+
+    #[cfg(feature = "mmap")]
+    {
+        let mut stream = CheckedMockStreamBuilder::new()
+            .read_mmap(&path)
+            .unwrap()
+            .build();
+        let mut buf = Vec::new();
+        stream.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, payload);
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn mockstream_capacity() {
+    let builder = CheckedMockStreamBuilder::with_capacity(2)
+        .reserve(8)
+        .write(b"1234".to_vec())
+        .write(b"5678".to_vec());
+    assert!(builder.clone().build().written_capacity() == 0);
+    assert!(builder.clone().build_cap().written_capacity() >= 8);
+    let mut stream = builder.written_capacity(1024).build_cap();
+    assert!(stream.written_capacity() >= 1024);
+    stream.write_all(b"1234").unwrap();
+    stream.shrink_to_fit();
+    assert!(stream.written_capacity() < 1024);
+    assert_eq!(stream.written(), b"1234");
+    stream.clear_capacity();
+    assert_eq!(stream.written_capacity(), 0);
+    assert_eq!(stream.written(), b"");
+
+    let mut stream = SimpleMockStream::with_capacity(vec![], 1024);
+    stream.write_all(b"1234").unwrap();
+    assert!(stream.written_capacity() >= 1024);
+    stream.shrink_to_fit();
+    assert!(stream.written_capacity() < 1024);
+    stream.clear_capacity();
+    assert_eq!(stream.written_capacity(), 0);
+}
+
+#[test]
+fn checked_mockstream_large_write_mismatch() {
+    let expected: Vec<u8> = (0..3_000_000u32).map(|i| (i % 251) as u8).collect();
+    for offset in [0, 15, 16, 4095, 4096, 4111, 2_999_990, 2_999_999] {
+        let mut written = expected.clone();
+        written[offset] ^= 0x80;
+        let mut stream = CheckedMockStreamBuilder::new().write(expected.clone()).build();
+        assert!(stream.write(&written).is_err());
+        match &stream.diff()[0] {
+            Divergence::MismatchedWrite { offset: o, .. } => assert_eq!(*o, offset),
+            d => panic!("unexpected divergence: {}", d),
+        }
+    }
+
+    // shorter write
+    let mut stream = CheckedMockStreamBuilder::new().write(expected.clone()).build();
+    assert!(stream.write(&expected[..100_001]).is_err());
+    match &stream.diff()[0] {
+        Divergence::MismatchedWrite { offset, .. } => assert_eq!(*offset, 100_001),
+        d => panic!("unexpected divergence: {}", d),
+    }
+    stream.write_all(&expected).unwrap();
+    assert_eq!(stream.written().len(), expected.len());
+}
+
+#[test]
+fn checked_mockstream_shared_payload() {
+    let fixture: std::sync::Arc<[u8]> = vec![b'x'; 1 << 20].into();
+    let builder = CheckedMockStreamBuilder::new()
+        .write(b"GET\r\n".to_vec())
+        .read(fixture.clone());
+    assert_eq!(std::sync::Arc::strong_count(&fixture), 2);
+
+    let builders: Vec<CheckedMockStreamBuilder> = (0..4).map(|_| builder.clone()).collect();
+    assert_eq!(std::sync::Arc::strong_count(&fixture), 6);
+    for builder in builders {
+        let mut stream = builder.trim_bytes(3..).build();
+        stream.write_all(b"\r\n").unwrap();
+        let mut buf = Vec::new();
+        stream.read_to_end(&mut buf).unwrap();
+        assert_eq!(&buf[..], &fixture[..]);
+    }
+    assert_eq!(std::sync::Arc::strong_count(&fixture), 2);
+}
+
+#[test]
+fn checked_mockstream_merge_waits() {
+    let stream = CheckedMockStreamBuilder::new()
+        .wait(Duration::from_millis(10))
+        .wait(Duration::from_millis(20))
+        .read(b"1".to_vec())
+        .wait(Duration::from_millis(5))
+        .build();
+    assert_eq!(
+        stream.dump(),
+        ">   0 next     WAIT 30ms\n    1 pending  R 1 bytes \"1\"\n    2 pending  WAIT 5ms\n"
+    );
+}
+
+#[cfg(feature = "inline")]
+#[test]
+fn payload_inline() {
+    use super::{Payload, INLINE_CAPACITY};
+
+    let small = Payload::from(b"+OK\r\n".to_vec());
+    assert_eq!(&small[..], b"+OK\r\n");
+    assert_eq!(&small.slice(1..3)[..], b"OK");
+    let large = Payload::from(vec![b'x'; INLINE_CAPACITY + 1]);
+    assert_eq!(large.len(), INLINE_CAPACITY + 1);
+    assert_eq!(large.slice(0..INLINE_CAPACITY), Payload::from(vec![b'x'; INLINE_CAPACITY]));
+
+    let mut stream = CheckedMockStreamBuilder::new()
+        .read(b"+OK\r\n".to_vec())
+        .write(b"QUIT\r\n".to_vec())
+        .build();
+    let mut buf = [0; 5];
+    stream.read_exact(&mut buf).unwrap();
+    stream.write_all(b"QUIT\r\n").unwrap();
+    stream.assert_done();
+}
+
+#[test]
+fn checked_mockstream_memory_limit() {
+    // written data exceed limit
+    let mut s = CheckedMockStreamBuilder::new()
+        .write(b"hello ".to_vec())
+        .write(b"world".to_vec())
+        .memory_limit(20)
+        .build();
+    assert_eq!(s.memory_limit(), Some(20));
+    s.write_all(b"hello ").unwrap();
+    let err = s.write(b"world").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::OutOfMemory);
+    assert_eq!(
+        err.to_string(),
+        "memory limit of 20 bytes exceeded: fixtures 11 bytes, written 6 bytes, write 5 bytes"
+    );
+    assert_eq!(s.written(), b"hello ");
+
+    // ring buffer retain only last bytes
+    let mut s = CheckedMockStreamBuilder::new()
+        .write(b"hello ".to_vec())
+        .write(b"world".to_vec())
+        .written_mode(WrittenMode::LastBytes(3))
+        .memory_limit(20)
+        .build_cap();
+    s.write_all(b"hello world").unwrap();
+    assert_eq!(s.written(), b"rld");
+
+    // fixtures exceed limit
+    let mut s = CheckedMockStreamBuilder::new()
+        .read(vec![0; 64])
+        .memory_limit(32)
+        .build();
+    let mut buf = [0; 8];
+    let err = s.read(&mut buf).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::OutOfMemory);
+}
+
+#[test]
+fn checked_mockstream_error_fidelity() {
+    #[derive(Debug)]
+    struct ProtoError(u32);
+
+    impl std::fmt::Display for ProtoError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "protocol error {}", self.0)
+        }
+    }
+
+    impl std::error::Error for ProtoError {}
+
+    let builder = CheckedMockStreamBuilder::new()
+        .read_error_with(|| Error::new(std::io::ErrorKind::InvalidData, ProtoError(7)))
+        .write_error(Error::from_raw_os_error(32))
+        .read_error(Error::new(std::io::ErrorKind::InvalidData, ProtoError(8)));
+    let mut first = builder.clone().build();
+    let mut s = builder.build();
+    let mut buf = [0; 8];
+    // every stream (and replay) gets an equal error
+    for _ in 0..2 {
+        let err = s.read(&mut buf).unwrap_err();
+        let inner = err.into_inner().unwrap();
+        assert_eq!(inner.downcast_ref::<ProtoError>().unwrap().0, 7);
+        let err = s.write(b"data").unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(32));
+        // a copy of error without constructor
+        let err = s.read(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "protocol error 8");
+        assert!(err.into_inner().unwrap().downcast_ref::<ProtoError>().is_none());
+        s.reset();
+    }
+    let err = first.read(&mut buf).unwrap_err();
+    assert!(err.into_inner().unwrap().is::<ProtoError>());
+}
+
+#[test]
+fn mockstream_written_since() {
+    let mut s = SimpleMockStream::empty();
+    s.write_all(b"first").unwrap();
+    let mark = s.mark();
+    s.write_all(b"second").unwrap();
+    assert_eq!(s.written_since(mark), b"second");
+    s.reset_written();
+    assert_eq!(s.written_since(mark), b"");
+
+    let mut s = CheckedMockStreamBuilder::new()
+        .write(b"first".to_vec())
+        .write(b"second".to_vec())
+        .write(b"third".to_vec())
+        .written_mode(WrittenMode::LastWrites(1))
+        .build();
+    s.write_all(b"first").unwrap();
+    let mark = s.mark();
+    assert_eq!(mark, 5);
+    s.write_all(b"second").unwrap();
+    assert_eq!(s.written_since(mark), b"second");
+    s.write_all(b"third").unwrap();
+    // dropped writes are not returned
+    assert_eq!(s.written_since(mark), b"third");
+    assert_eq!(s.written_since(s.mark()), b"");
+}
+
+#[test]
+fn checked_mockstream_verify_only() {
+    let payload = vec![7; 100_000];
+    for mode in [WrittenMode::Discard, WrittenMode::Hash] {
+        let mut s = CheckedMockStreamBuilder::new()
+            .write(payload.clone())
+            .write(b"end".to_vec())
+            .written_mode(mode)
+            .build_cap();
+        s.write_all(&payload).unwrap();
+        s.write_all(b"end").unwrap();
+        assert!(s.written().is_empty());
+        assert_eq!(s.written_capacity(), 0);
+        assert_eq!(s.written_len(), payload.len() + 3);
+        s.assert_done();
+
+        let mut all = payload.clone();
+        all.extend_from_slice(b"end");
+        match mode {
+            WrittenMode::Hash => assert_eq!(s.written_hash(), Some(hash_bytes(&all))),
+            _ => assert_eq!(s.written_hash(), None),
+        }
+    }
+}
+
+#[test]
+fn checked_mockstream_set_actions() {
+    let mut s = CheckedMockStreamBuilder::new()
+        .read(b"first".to_vec())
+        .write(b"one".to_vec())
+        .build();
+    let mut buf = [0; 8];
+    assert_eq!(s.read(&mut buf).unwrap(), 5);
+    s.write_all(b"one").unwrap();
+
+    s.set_actions(
+        CheckedMockStreamBuilder::new()
+            .read(b"second".to_vec())
+            .write(b"two".to_vec()),
+    );
+    assert_eq!(s.read(&mut buf).unwrap(), 6);
+    assert_eq!(&buf[..6], b"second");
+    s.write_all(b"two").unwrap();
+    s.assert_done();
+    assert_eq!(s.written(), b"onetwo");
+
+    s.reload(CheckedMockStreamBuilder::new().write(b"three".to_vec()));
+    assert!(s.written().is_empty());
+    assert!(!s.is_done());
+    s.write_all(b"three").unwrap();
+    assert_eq!(s.written(), b"three");
+    s.assert_done();
+}
+
+#[test]
+fn checked_mockstream_static_payload() {
+    static FIXTURE: &[u8] = b"+OK static fixture\r\n";
+    let payload = super::Payload::from(FIXTURE);
+    assert_eq!(payload.as_ptr(), FIXTURE.as_ptr());
+    assert_eq!(payload.slice(4..10).as_ptr(), FIXTURE[4..].as_ptr());
+    let payload = super::Payload::from(std::borrow::Cow::Borrowed(FIXTURE));
+    assert_eq!(payload.as_ptr(), FIXTURE.as_ptr());
+
+    let mut s = CheckedMockStreamBuilder::new()
+        .write(b"PING\r\n")
+        .read(FIXTURE)
+        .build();
+    s.write_all(b"PING\r\n").unwrap();
+    let mut buf = Vec::new();
+    s.read_to_end(&mut buf).unwrap();
+    assert_eq!(buf, FIXTURE);
+}
+
+#[test]
+fn checked_mockstream_message_reads() {
+    let builder = CheckedMockStreamBuilder::new()
+        .read(b"first")
+        .read(b"second message");
+
+    let mut s = builder.clone().read_mode(ReadMode::Message).build();
+    let mut buf = [0; 8];
+    assert_eq!(s.read(&mut buf).unwrap(), 5);
+    assert_eq!(&buf[..5], b"first");
+    // truncated
+    assert_eq!(s.read(&mut buf).unwrap(), 8);
+    assert_eq!(&buf, b"second m");
+    assert_eq!(s.read(&mut buf).unwrap(), 0);
+
+    let mut s = builder.read_mode(ReadMode::MessageStrict).build();
+    assert_eq!(s.read(&mut buf).unwrap(), 5);
+    let err = s.read(&mut buf).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert_eq!(
+        err.to_string(),
+        "read buffer too small for message: 8 bytes, message 14 bytes"
+    );
+    let mut buf = [0; 32];
+    assert_eq!(s.read(&mut buf).unwrap(), 14);
+    assert_eq!(&buf[..14], b"second message");
+    s.assert_done();
+}
+
+#[test]
+fn checked_mockstream_duplex() {
+    let mut s = CheckedMockStreamBuilder::new()
+        .read(b"HELLO\n")
+        .write(b"SUB a\n")
+        .write(b"SUB b\n")
+        .barrier()
+        .read(b"MSG a\n")
+        .write(b"UNSUB a\n")
+        .duplex()
+        .build();
+    let mut buf = [0; 6];
+    // writes are not blocked by pending read
+    s.write_all(b"SUB a\nSUB b\n").unwrap();
+    // barrier is not reached by read
+    let err = s.write(b"UNSUB a\n").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::WouldBlock);
+    s.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"HELLO\n");
+    s.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"MSG a\n");
+    assert!(!s.is_done());
+    assert!(s.dump().contains(">   5 next     W 8 bytes \"UNSUB a\\n\""));
+    s.write_all(b"UNSUB a\n").unwrap();
+    s.assert_done();
+    assert_eq!(s.written(), b"SUB a\nSUB b\nUNSUB a\n");
+}
+
+#[test]
+fn checked_mockstream_shared() {
+    let shared = CheckedMockStreamBuilder::new()
+        .write(b"PING\r\n")
+        .wait(Duration::from_millis(50))
+        .read(b"+PONG\r\n")
+        .build()
+        .into_shared();
+
+    let mut client = shared.clone();
+    let handle = std::thread::spawn(move || {
+        client.write_all(b"PING\r\n").unwrap();
+        let mut buf = [0; 7];
+        client.read_exact(&mut buf).unwrap();
+        buf
+    });
+    while shared.written_len() == 0 {
+        std::thread::yield_now();
+    }
+    // lock is not held while waiting
+    assert_eq!(shared.written(), b"PING\r\n");
+    assert!(!shared.is_done());
+    assert_eq!(&handle.join().unwrap(), b"+PONG\r\n");
+    shared.assert_done();
+}
+
+#[test]
+fn checked_mockstream_shared_duplex() {
+    let shared = CheckedMockStreamBuilder::new()
+        .duplex()
+        .write(b"SUB a\n")
+        .barrier()
+        .read(b"+OK\n")
+        .build()
+        .into_shared();
+
+    // reader waits at barrier for writer thread
+    let mut reader = shared.clone();
+    let handle = std::thread::spawn(move || {
+        let mut buf = [0; 4];
+        reader.read_exact(&mut buf).unwrap();
+        buf
+    });
+    std::thread::sleep(Duration::from_millis(10));
+    assert!(!handle.is_finished());
+    let mut writer = shared.clone();
+    writer.write_all(b"SUB a\n").unwrap();
+    assert_eq!(&handle.join().unwrap(), b"+OK\n");
+    assert_eq!(shared.written(), b"SUB a\n");
+
+    // non-blocking mode
+    let mut shared = CheckedMockStreamBuilder::new()
+        .duplex()
+        .write(b"SUB a\n")
+        .barrier()
+        .read(b"+OK\n")
+        .nonblocking()
+        .build()
+        .into_shared();
+    assert_eq!(
+        shared.read(&mut [0; 4]).unwrap_err().kind(),
+        std::io::ErrorKind::WouldBlock
+    );
+}
+
+#[test]
+fn checked_mockstream_error_shorthands() {
+    let mut s = CheckedMockStreamBuilder::new()
+        .read_timeout()
+        .read_connection_reset()
+        .write_broken_pipe()
+        .write_would_block()
+        .build();
+    let mut buf = [0; 4];
+    assert_eq!(
+        s.read(&mut buf).unwrap_err().kind(),
+        std::io::ErrorKind::TimedOut
+    );
+    assert_eq!(
+        s.read(&mut buf).unwrap_err().kind(),
+        std::io::ErrorKind::ConnectionReset
+    );
+    assert_eq!(
+        s.write(b"data").unwrap_err().kind(),
+        std::io::ErrorKind::BrokenPipe
+    );
+    assert_eq!(
+        s.write(b"data").unwrap_err().kind(),
+        std::io::ErrorKind::WouldBlock
+    );
+    s.assert_done();
+}
+
+#[test]
+fn checked_mockstream_error_times() {
+    let mut s = CheckedMockStreamBuilder::new()
+        .read_error_times(Error::from(std::io::ErrorKind::WouldBlock), 2)
+        .read(b"ok")
+        .write_error_times(Error::from(std::io::ErrorKind::Interrupted), 3)
+        .write(b"done")
+        .build();
+    let mut buf = [0; 2];
+    let mut attempts = 0;
+    // bounded retry
+    let n = loop {
+        attempts += 1;
+        match s.read(&mut buf) {
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock && attempts < 5 => continue,
+            result => break result.unwrap(),
+        }
+    };
+    assert_eq!((n, attempts), (2, 3));
+    // write_all retries interrupted writes
+    s.write_all(b"done").unwrap();
+    s.assert_done();
+}
+
+#[test]
+fn checked_mockstream_snapshot_restore() {
+    let mut s = CheckedMockStreamBuilder::new()
+        .write(b"LOGIN\n")
+        .read(b"+OK welcome\n")
+        .write(b"QUIT\n")
+        .build();
+    s.write_all(b"LOGIN\n").unwrap();
+    let mut buf = [0; 4];
+    s.read_exact(&mut buf).unwrap();
+    let snapshot = s.snapshot();
+    assert_eq!((snapshot.action(), snapshot.pos()), (1, 4));
+
+    // first continuation
+    let mut rest = [0; 8];
+    s.read_exact(&mut rest).unwrap();
+    assert_eq!(&rest, b"welcome\n");
+    s.write_all(b"QUIT\n").unwrap();
+    s.assert_done();
+
+    // alternative continuation
+    s.restore(&snapshot);
+    assert_eq!(s.written(), b"LOGIN\n");
+    assert_eq!(s.write(b"QUIT\n").unwrap(), 0);
+    assert!(!s.diff().iter().all(|d| matches!(d, Divergence::Missing { .. })));
+    s.restore(&snapshot);
+    assert!(s.diff().iter().all(|d| matches!(d, Divergence::Missing { .. })));
+    let mut rest = [0; 8];
+    s.read_exact(&mut rest).unwrap();
+    assert_eq!(&rest, b"welcome\n");
+
+    // shutdown, unflushed data and call counts are restored
+    let mut s = CheckedMockStreamBuilder::new()
+        .write(b"HEAD\r\n")
+        .write(b"BODY\r\n")
+        .flush_gated()
+        .build();
+    s.write_all(b"HEAD\r\n").unwrap();
+    let snapshot = s.snapshot();
+    s.write_all(b"BODY\r\n").unwrap();
+    s.shutdown_write().unwrap();
+    assert!(s.is_shutdown());
+    s.restore(&snapshot);
+    assert!(!s.is_shutdown());
+    assert_eq!(s.unflushed(), b"HEAD\r\n");
+    assert_eq!(s.calls(), (0, 1, 0));
+    s.write_all(b"BODY\r\n").unwrap();
+    s.flush().unwrap();
+    s.assert_done();
+
+    // rest of partially read heartbeat of abandoned branch is dropped
+    let mut s = CheckedMockStreamBuilder::new()
+        .read_every(b"PING\r\n", Duration::from_millis(20))
+        .write(b"A")
+        .read(b"OK")
+        .nonblocking()
+        .build();
+    let snapshot = s.snapshot();
+    let mut buf = [0; 2];
+    assert!(s.read(&mut buf).is_err());
+    std::thread::sleep(Duration::from_millis(20));
+    s.read_exact(&mut buf).unwrap();
+    s.write_all(b"A").unwrap();
+    s.restore(&snapshot);
+    assert_eq!(
+        s.read(&mut buf).unwrap_err().kind(),
+        std::io::ErrorKind::WouldBlock
+    );
+    std::thread::sleep(Duration::from_millis(20));
+    let mut buf = [0; 6];
+    s.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"PING\r\n");
+}
+
+#[test]
+fn checked_mockstream_flush_gated_peer_close() {
+    let mut s = CheckedMockStreamBuilder::new()
+        .write(b"QUIT\r\n")
+        .peer_full_close()
+        .flush_gated()
+        .build();
+    s.write_all(b"QUIT\r\n").unwrap();
+    s.flush().unwrap();
+    let err = s.write(b"PING\r\n").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::BrokenPipe);
+    assert!(s.unflushed().is_empty());
+    assert!(s.is_peer_closed());
+    s.assert_done();
+}
+
+#[test]
+fn checked_mockstream_flush_gated() {
+    let mut s = CheckedMockStreamBuilder::new()
+        .write(b"HEAD\r\n")
+        .write(b"BODY\r\n")
+        .read(b"OK\r\n")
+        .flush_gated()
+        .build();
+    s.write_all(b"HEAD\r\n").unwrap();
+    s.write_all(b"BODY\r\n").unwrap();
+    assert!(s.written().is_empty());
+    assert_eq!(s.unflushed(), b"HEAD\r\nBODY\r\n");
+    let err = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| s.assert_done()))
+        .unwrap_err();
+    let msg = err.downcast_ref::<String>().unwrap();
+    assert!(msg.contains("unflushed write 12 bytes \"HEAD\\r\\nBODY\\r\\n\""), "{}", msg);
+
+    s.flush().unwrap();
+    assert_eq!(s.written(), b"HEAD\r\nBODY\r\n");
+    assert!(s.unflushed().is_empty());
+    let mut buf = [0; 4];
+    s.read_exact(&mut buf).unwrap();
+    s.assert_done();
+
+    // not expected flushed data
+    s.write_all(b"EXTRA").unwrap();
+    assert_eq!(
+        s.flush().unwrap_err().kind(),
+        std::io::ErrorKind::WriteZero
+    );
+}
+
+#[test]
+fn checked_mockstream_shutdown() {
+    let mut s = CheckedMockStreamBuilder::new()
+        .write(b"QUIT\r\n")
+        .read(b"+OK bye\r\n")
+        .build();
+    s.write_all(b"QUIT\r\n").unwrap();
+    s.shutdown_write().unwrap();
+    assert!(s.is_shutdown());
+    let err = s.write(b"more").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::BrokenPipe);
+    // reads drain scripted data
+    let mut buf = Vec::new();
+    s.read_to_end(&mut buf).unwrap();
+    assert_eq!(buf, b"+OK bye\r\n");
+    s.assert_done();
+}
+
+#[test]
+fn checked_mockstream_exhausted_write() {
+    let builder = CheckedMockStreamBuilder::new().write(b"QUIT\r\n");
+
+    let mut s = builder.clone().build();
+    s.write_all(b"QUIT\r\n").unwrap();
+    assert_eq!(s.write(b"more").unwrap(), 0);
+    assert_eq!(s.diff().len(), 1);
+
+    let mut s = builder
+        .clone()
+        .on_exhausted_write(ExhaustedWrite::Error(std::io::ErrorKind::BrokenPipe))
+        .build();
+    let err = s.write_all(b"QUIT\r\nmore").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::BrokenPipe);
+    assert_eq!(
+        err.to_string(),
+        "unexpected write after end of scenario: 4 bytes \"more\""
+    );
+    assert_eq!(s.diff().len(), 1);
+
+    let mut s = builder
+        .clone()
+        .on_exhausted_write(ExhaustedWrite::Accept)
+        .build();
+    s.write_all(b"QUIT\r\nmore").unwrap();
+    assert_eq!(s.written(), b"QUIT\r\nmore");
+    s.assert_done();
+
+    let mut s = builder.on_exhausted_write(ExhaustedWrite::Block).build();
+    s.write_all(b"QUIT\r\n").unwrap();
+    let err = s.write(b"more").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::WouldBlock);
+    s.assert_done();
+}
+
+#[test]
+fn checked_mockstream_nonblocking() {
+    let mut s = CheckedMockStreamBuilder::new()
+        .write(b"PING\r\n")
+        .wait(Duration::from_millis(50))
+        .read(b"+PONG\r\n")
+        .nonblocking()
+        .build();
+    s.write_all(b"PING\r\n").unwrap();
+    let mut buf = [0; 7];
+    let start = std::time::Instant::now();
+    let err = s.read(&mut buf).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::WouldBlock);
+    assert!(start.elapsed() < Duration::from_millis(50));
+    // zero-length read does not wait
+    assert_eq!(s.read(&mut []).unwrap(), 0);
+    assert_eq!(
+        s.read(&mut buf).unwrap_err().kind(),
+        std::io::ErrorKind::WouldBlock
+    );
+
+    std::thread::sleep(Duration::from_millis(50));
+    s.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"+PONG\r\n");
+    s.assert_done();
+}
+
+#[test]
+fn checked_mockstream_mismatch_policy() {
+    let builder = CheckedMockStreamBuilder::new()
+        .write(b"USER foo\r\n")
+        .write(b"PASS bar\r\n")
+        .read(b"+OK\r\n");
+
+    let mut s = builder
+        .clone()
+        .on_mismatch(MismatchPolicy::Continue)
+        .build();
+    s.write_all(b"USER baz\r\n").unwrap();
+    s.write_all(b"PASS qux\r\n").unwrap();
+    let mut buf = [0; 5];
+    s.read_exact(&mut buf).unwrap();
+    assert!(s.is_done());
+    let diff = s.diff();
+    assert_eq!(diff.len(), 2);
+    assert!(matches!(
+        diff[1],
+        Divergence::MismatchedWrite {
+            action: 1,
+            offset: 5,
+            ..
+        }
+    ));
+
+    let mut s = builder.on_mismatch(MismatchPolicy::Panic).build();
+    let err = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let _ = s.write(b"USER baz\r\n");
+    }))
+    .unwrap_err();
+    let msg = err.downcast_ref::<String>().unwrap();
+    assert!(
+        msg.starts_with("scenario diverged:\n  action 0: written data mismatch at offset 5"),
+        "{}",
+        msg
+    );
+}
+
+#[test]
+fn assert_written_macros() {
+    let mut s = CheckedMockStreamBuilder::new()
+        .write(b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n")
+        .build();
+    s.write_all(b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n")
+        .unwrap();
+    crate::assert_written!(s, "GET / HTTP/1.1\r\nHost: example.com\r\n\r\n");
+    crate::assert_written_contains!(s, b"Host: example.com\r\n");
+
+    let err = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        crate::assert_written!(
+            s,
+            "GET / HTTP/1.1\r\nHost: example.org\r\n\r\n",
+            "request {}",
+            1
+        );
+    }))
+    .unwrap_err();
+    let msg = err.downcast_ref::<String>().unwrap();
+    assert!(
+        msg.starts_with(
+            "request 1: written data mismatch at offset 30 (written 37 bytes, expected 37 bytes)\n  \
+             written:  \"com\\r\\n\\r\\n\"\n  expected: \"org\\r\\n\\r\\n\"\nwritten:\n\
+             00000000  47 45 54 20 2f 20 48 54  54 50 2f 31 2e 31 0d 0a  |GET / HTTP/1.1..|\n"
+        ),
+        "{}",
+        msg
+    );
+
+    let err = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        crate::assert_written_contains!(s, "Host: example.org");
+    }))
+    .unwrap_err();
+    let msg = err.downcast_ref::<String>().unwrap();
+    assert!(
+        msg.starts_with(
+            "written data (37 bytes) does not contain 17 bytes \"Host: example.org\""
+        ),
+        "{}",
+        msg
+    );
+}
+
+#[test]
+fn checked_mockstream_utf8_writes() {
+    // character is split between writes
+    let data = "привет\n".as_bytes();
+    let mut s = CheckedMockStreamBuilder::new()
+        .write(&data[..3])
+        .write(&data[3..])
+        .write(b"bad\xff\n")
+        .utf8_writes()
+        .build();
+    s.write_all(&data[..3]).unwrap();
+    s.write_all(&data[3..]).unwrap();
+    assert_eq!(s.written_utf8().unwrap(), "привет\n");
+
+    let err = s.write(b"bad\xff\n").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert_eq!(
+        err.to_string(),
+        "invalid UTF-8 in written data at offset 16 (byte 0xff)"
+    );
+    assert!(!s.is_done());
+
+    let mut s = SimpleMockStream::empty();
+    s.write_all(b"bad\xff\n").unwrap();
+    assert_eq!(s.written_utf8().unwrap_err().valid_up_to(), 3);
+    assert_eq!(s.written_lossy(), "bad\u{fffd}\n");
+}
+
+#[test]
+fn expect_lines() {
+    let mut s = SimpleMockStream::empty();
+    s.write_all(b"HELO a.b\r\nMA").unwrap();
+    s.write_all(b"IL FROM:<a@b>\n").unwrap();
+    s.expect_lines(["HELO a.b", "MAIL FROM:<a@b>"]);
+
+    let err = std::panic::catch_unwind(|| s.expect_lines(["HELO a.b"])).unwrap_err();
+    let msg = err.downcast_ref::<String>().unwrap();
+    assert_eq!(
+        msg,
+        "written 2 lines, expected 1: unexpected line 2 \"MAIL FROM:<a@b>\"\nwritten lines:\n     \
+         1 \"HELO a.b\"\n     2 \"MAIL FROM:<a@b>\""
+    );
+
+    let err = std::panic::catch_unwind(|| s.expect_lines(vec!["HELO a.b", "MAIL FROM:<c@d>"]))
+        .unwrap_err();
+    let msg = err.downcast_ref::<String>().unwrap();
+    assert!(
+        msg.starts_with(
+            "written line 2 mismatch: expected \"MAIL FROM:<c@d>\", written \"MAIL FROM:<a@b>\""
+        ),
+        "{}",
+        msg
+    );
+}
+
+#[test]
+fn checked_mockstream_display() {
+    let mut s = CheckedMockStreamBuilder::new()
+        .write(b"PING\r\n")
+        .read(b"+PONG\r\n")
+        .write(b"QUIT\r\n")
+        .build();
+    s.write_all(b"PING\r\n").unwrap();
+    let mut buf = [0; 3];
+    s.read_exact(&mut buf).unwrap();
+    assert_eq!(
+        s.to_string(),
+        "action 1/3, written 6 bytes\n    \
+         0 done     W 6 bytes \"PING\\r\\n\"\n\
+         >   1 partial  R 7 bytes \"+PONG\\r\\n\" (3/7)\n    \
+         2 pending  W 6 bytes \"QUIT\\r\\n\"\n"
+    );
+    let debug = format!("{:?}", s);
+    assert!(
+        debug.starts_with("CheckedMockStream { action: 1, pos: 3, actions: [\"W 6 bytes"),
+        "{}",
+        debug
+    );
+
+    let mut s = SimpleMockStream::new(b"+PONG\r\n".to_vec());
+    s.write_all(b"PING\r\n").unwrap();
+    assert_eq!(s.to_string(), "read 0/7 bytes, written 6 bytes \"PING\\r\\n\"");
+}
+
+#[test]
+fn checked_mockstream_progress() {
+    let mut s = CheckedMockStreamBuilder::new()
+        .write(b"PING\r\n")
+        .read(b"+PONG\r\n")
+        .read_error(Error::from(std::io::ErrorKind::ConnectionReset))
+        .build();
+    assert_eq!(
+        s.current_action(),
+        CurrentAction::ExpectingWrite {
+            expected: b"PING\r\n"
+        }
+    );
+    s.write_all(b"PING\r\n").unwrap();
+    let mut buf = [0; 3];
+    s.read_exact(&mut buf).unwrap();
+    let progress = s.progress();
+    assert_eq!((progress.action(), progress.total(), progress.pos()), (1, 3, 3));
+    assert_eq!(
+        s.current_action(),
+        CurrentAction::ExpectingRead {
+            remaining: b"NG\r\n"
+        }
+    );
+    let mut buf = [0; 4];
+    s.read_exact(&mut buf).unwrap();
+    assert_eq!(
+        s.current_action(),
+        CurrentAction::ExpectingReadError {
+            kind: std::io::ErrorKind::ConnectionReset
+        }
+    );
+    s.read(&mut buf).unwrap_err();
+    assert_eq!(s.current_action(), CurrentAction::Done);
+    assert!(s.progress().is_done());
+}
+
+#[test]
+fn recording_stream_mermaid() {
+    let server = CheckedMockStreamBuilder::new()
+        .write(b"MAIL FROM:<a@b>\r\n")
+        .read(b"250 OK; queued\r\n")
+        .read_error(Error::new(std::io::ErrorKind::ConnectionReset, "reset"))
+        .build();
+    let mut stream = RecordingStream::new(server);
+    stream.write_all(b"MAIL FROM:<a@b>\r\n").unwrap();
+    let mut buf = [0; 16];
+    stream.read_exact(&mut buf).unwrap();
+    stream.read(&mut buf).unwrap_err();
+
+    let diagram = stream.to_mermaid();
+    let lines: Vec<&str> = diagram.lines().collect();
+    assert_eq!(
+        lines[..3],
+        [
+            "sequenceDiagram",
+            "    participant C as client",
+            "    participant S as stream"
+        ]
+    );
+    assert!(lines[3].starts_with("    C->>S: 0."), "{}", diagram);
+    assert!(
+        lines[3].contains("ms write 17 bytes \"MAIL FROM:#lt;a@b#gt;\\r\\n\""),
+        "{}",
+        diagram
+    );
+    assert!(
+        lines[4].contains("ms read 16 bytes \"250 OK#59; queued\\r\\n\""),
+        "{}",
+        diagram
+    );
+    assert!(
+        lines[5].starts_with("    S--xC: ") && lines[5].contains("read error ConnectionReset: reset"),
+        "{}",
+        diagram
+    );
+}
+
+#[test]
+fn checked_mockstream_fmt() {
+    let id = 42;
+    let mut s = CheckedMockStreamBuilder::new()
+        .write_fmt(format_args!("GET {}\r\n", id))
+        .read_fmt(format_args!("VALUE {} {}\r\n", id, "foo"))
+        .write_string(id)
+        .build();
+    s.write_all(format!("GET {}\r\n", id).as_bytes()).unwrap();
+    let mut buf = [0; 14];
+    s.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"VALUE 42 foo\r\n");
+    s.write_all(b"42").unwrap();
+    s.assert_done();
+}
+
+#[test]
+fn checked_mockstream_fixtures() {
+    let dir = std::env::temp_dir().join(format!("netmock-fixture-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("0.response"), b"220 ready\r\n").unwrap();
+    std::fs::write(dir.join("1.request"), b"HELO a.b\r\n").unwrap();
+    std::fs::write(dir.join("1.response"), b"250 a.b\r\n").unwrap();
+    std::fs::write(dir.join("10.request"), b"QUIT\r\n").unwrap();
+    std::fs::write(dir.join("2.request"), b"MAIL FROM:<a@b>\r\n").unwrap();
+    std::fs::write(dir.join("2.response"), b"250 OK\r\n").unwrap();
+    std::fs::write(dir.join("README"), b"SMTP session").unwrap();
+
+    let builder = CheckedMockStreamBuilder::from_fixture_dir(&dir).unwrap();
+    assert_eq!(
+        builder.to_transcript(),
+        "R: 220 ready\\r\\n\nW: HELO a.b\\r\\n\nR: 250 a.b\\r\\n\nW: MAIL FROM:<a@b>\\r\\n\n\
+         R: 250 OK\\r\\n\nW: QUIT\\r\\n\n"
+    );
+
+    let mut s =
+        CheckedMockStreamBuilder::from_fixture_pair(dir.join("1.request"), dir.join("1.response"))
+            .unwrap()
+            .build();
+    s.write_all(b"HELO a.b\r\n").unwrap();
+    let mut buf = [0; 9];
+    s.read_exact(&mut buf).unwrap();
+    s.assert_done();
+
+    let err = CheckedMockStreamBuilder::from_fixture_pair(dir.join("3.request"), dir.join("3.response"))
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    assert!(err.to_string().contains("3.request"), "{}", err);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn mock_stream_macro() {
+    let mut s = crate::mock_stream! {
+        read b"+OK ready\r\n";
+        wait 20ms;
+        expect_write b"USER foo\r\n";
+        wait (Duration::from_millis(1));
+        read b"+OK\r\n".to_vec();
+        write_error Error::new(std::io::ErrorKind::BrokenPipe, "closed");
+    };
+    let start = std::time::Instant::now();
+    let mut buf = [0; 11];
+    s.read_exact(&mut buf).unwrap();
+    s.write_all(b"USER foo\r\n").unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(20));
+    let mut buf = [0; 5];
+    s.read_exact(&mut buf).unwrap();
+    assert_eq!(
+        s.write(b"QUIT\r\n").unwrap_err().kind(),
+        std::io::ErrorKind::BrokenPipe
+    );
+    s.assert_done();
+}
+
+#[test]
+fn checked_mockstream_from_actions() {
+    use super::{ActionSpec, CheckedMockStream};
+
+    let mut s = CheckedMockStream::empty();
+    assert!(s.is_done());
+    assert_eq!(s.read(&mut [0; 4]).unwrap(), 0);
+
+    let mut s = CheckedMockStream::from_actions(vec![
+        ActionSpec::Write(b"PING\r\n".into()),
+        ActionSpec::Wait(Duration::from_millis(1)),
+        ActionSpec::Read(b"+PONG\r\n".into()),
+        ActionSpec::ReadError(Error::from(std::io::ErrorKind::ConnectionReset)),
+    ]);
+    s.write_all(b"PING\r\n").unwrap();
+    let mut buf = [0; 7];
+    s.read_exact(&mut buf).unwrap();
+    assert_eq!(
+        s.read(&mut buf).unwrap_err().kind(),
+        std::io::ErrorKind::ConnectionReset
+    );
+    s.assert_done();
+}
+
+#[test]
+fn checked_mockstream_builder_introspection() {
+    use super::ActionKind;
+
+    let builder = CheckedMockStreamBuilder::new();
+    assert!(builder.is_empty());
+    let builder = builder
+        .write(b"PING\r\n")
+        .wait(Duration::from_millis(1))
+        .read(b"+PONG\r\n")
+        .read_timeout();
+    assert_eq!(builder.len(), 4);
+    let planned: Vec<(ActionKind, Option<usize>)> = builder
+        .planned()
+        .map(|a| (a.kind(), a.payload_len()))
+        .collect();
+    assert_eq!(
+        planned,
+        [
+            (ActionKind::Write, Some(6)),
+            (ActionKind::Wait, None),
+            (ActionKind::Read, Some(7)),
+            (ActionKind::ReadError, None)
+        ]
+    );
+}
+
+#[cfg(feature = "pretty")]
+#[test]
+fn checked_mockstream_pretty_diff() {
+    let mut s = CheckedMockStreamBuilder::new()
+        .write(b"USER foo\r\nPASS bar\r\n")
+        .build();
+    s.write(b"USER foo\r\nPASS baz\r\n").unwrap_err();
+    let err = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| s.assert_done()))
+        .unwrap_err();
+    let msg = err.downcast_ref::<String>().unwrap();
+    let plain = ["\x1b[31m", "\x1b[32m", "\x1b[7m", "\x1b[0m"]
+        .iter()
+        .fold(msg.clone(), |s, color| s.replace(color, ""));
+    assert!(plain.contains("- expected\n+ written\n"), "{}", plain);
+    assert!(
+        plain.contains("  \"USER foo\\r\\n\"\n- \"PASS bar\\r\\n\"\n+ \"PASS baz\\r\\n\"\nhex:\n"),
+        "{}",
+        plain
+    );
+    assert!(plain.contains("+00000010  61 7a 0d 0a"), "{}", plain);
+}
+
+#[test]
+fn simple_mockstream_from_iter() {
+    let mut s: SimpleMockStream = (0..1024).map(|i| i as u8).collect();
+    s.extend(vec![b"END".to_vec()]);
+    s.extend(b"\r\n".iter().copied());
+    let mut buf = Vec::new();
+    s.read_to_end(&mut buf).unwrap();
+    assert_eq!(buf.len(), 1029);
+    assert_eq!(buf[255], 255);
+    assert_eq!(&buf[1024..], b"END\r\n");
+
+    let s: SimpleMockStream = vec![b"+OK".to_vec(), b"\r\n".to_vec()].into_iter().collect();
+    assert_eq!(s.remaining(), b"+OK\r\n");
+}
+
+#[test]
+fn checked_mockstream_timing() {
+    let mut s = CheckedMockStreamBuilder::new()
+        .read(b"+OK\r\n")
+        .write(b"PING\r\n")
+        .within(Duration::from_secs(5))
+        .wait(Duration::from_millis(20))
+        .read(b"+PONG\r\n")
+        .within(Duration::from_secs(5))
+        .after(Duration::from_millis(10))
+        .write(b"PING\r\n")
+        .within(Duration::from_millis(10))
+        .write(b"QUIT\r\n")
+        .after(Duration::from_secs(5))
+        .build();
+    let mut buf = [0; 7];
+    s.read_exact(&mut buf[..5]).unwrap();
+    s.write_all(b"PING\r\n").unwrap();
+    // scripted wait is not counted, so read is too early
+    s.read_exact(&mut buf).unwrap();
+    std::thread::sleep(Duration::from_millis(30));
+    s.write_all(b"PING\r\n").unwrap();
+    s.write_all(b"QUIT\r\n").unwrap();
+
+    let diff = s.diff();
+    assert_eq!(diff.len(), 3, "{:?}", diff);
+    assert!(matches!(
+        diff[0],
+        Divergence::Timing {
+            action: 3,
+            within: Some(within),
+            after: Some(after),
+            ..
+        } if within == Duration::from_secs(5) && after == Duration::from_millis(10)
+    ));
+    assert!(matches!(
+        diff[1],
+        Divergence::Timing { action: 4, elapsed, .. } if elapsed >= Duration::from_millis(30)
+    ));
+    assert!(matches!(diff[2], Divergence::Timing { action: 5, .. }));
+    assert!(diff[2].to_string().starts_with("action 5: performed after"));
+    assert!(diff[2].to_string().ends_with(", expected after 5s"));
+
+    // constraints are moved with actions on scenario editing
+    let late = CheckedMockStreamBuilder::new()
+        .write(b"QUIT\r\n")
+        .after(Duration::from_secs(5));
+    let mut s = CheckedMockStreamBuilder::new()
+        .read(b"+OK\r\n")
+        .concat(late)
+        .trim(1..)
+        .build();
+    s.write_all(b"QUIT\r\n").unwrap();
+    assert!(matches!(s.diff()[..], [Divergence::Timing { action: 0, .. }]));
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        CheckedMockStreamBuilder::new().within(Duration::from_secs(1))
+    }));
+    assert!(result.is_err());
+}
+
+#[test]
+fn checked_mockstream_read_every() {
+    let mut s = CheckedMockStreamBuilder::new()
+        .read_every(b"PING\r\n", Duration::from_millis(20))
+        .write(b"QUIT\r\n")
+        .read(b"+OK bye\r\n")
+        .nonblocking()
+        .build();
+    let mut buf = [0; 6];
+    assert_eq!(
+        s.read(&mut buf).unwrap_err().kind(),
+        std::io::ErrorKind::WouldBlock
+    );
+    assert!(matches!(
+        s.current_action(),
+        CurrentAction::ExpectingHeartbeat { payload: b"PING\r\n", .. }
+    ));
+    for _ in 0..2 {
+        std::thread::sleep(Duration::from_millis(20));
+        // partial read of heartbeat is continued without wait
+        s.read_exact(&mut buf[..2]).unwrap();
+        s.read_exact(&mut buf[2..]).unwrap();
+        assert_eq!(&buf, b"PING\r\n");
+        assert_eq!(
+            s.read(&mut buf).unwrap_err().kind(),
+            std::io::ErrorKind::WouldBlock
+        );
+    }
+    s.write_all(b"QUIT\r\n").unwrap();
+    let mut buf = Vec::new();
+    s.read_to_end(&mut buf).unwrap();
+    assert_eq!(buf, b"+OK bye\r\n");
+    s.assert_done();
+
+    // write in the middle of heartbeat payload doesn't truncate it
+    for duplex in [false, true] {
+        let mut builder = CheckedMockStreamBuilder::new()
+            .read_every(b"PING\r\n", Duration::from_millis(20))
+            .write(b"QUIT\r\n")
+            .read(b"+OK bye\r\n");
+        if duplex {
+            builder = builder.duplex();
+        }
+        let mut s = builder.build();
+        let mut buf = [0; 2];
+        s.read_exact(&mut buf).unwrap();
+        s.write_all(b"QUIT\r\n").unwrap();
+        let mut buf = Vec::new();
+        s.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, b"NG\r\n+OK bye\r\n");
+        s.assert_done();
+    }
+
+    let text = "REVERY: 30s PING\\r\\n\nW: QUIT\\r\\n\n";
+    let builder = CheckedMockStreamBuilder::from_transcript(text).unwrap();
+    assert_eq!(builder.to_transcript(), text);
+}
+
+#[test]
+fn checked_mockstream_at() {
+    let mut s = CheckedMockStreamBuilder::new()
+        .at(Duration::from_millis(30), super::ActionSpec::Read(b"+OK\r\n".into()))
+        .nonblocking()
+        .build();
+    assert!(matches!(
+        s.current_action(),
+        CurrentAction::WaitingUntil { offset } if offset == Duration::from_millis(30)
+    ));
+    let mut buf = [0; 5];
+    assert_eq!(
+        s.read(&mut buf).unwrap_err().kind(),
+        std::io::ErrorKind::WouldBlock
+    );
+    std::thread::sleep(Duration::from_millis(30));
+    s.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"+OK\r\n");
+    s.assert_done();
+}
+
+#[test]
+fn checked_mockstream_peer_close() {
+    let mut s = CheckedMockStreamBuilder::new()
+        .read(b"+OK bye\r\n")
+        .peer_half_close()
+        .write(b"QUIT\r\n")
+        .peer_full_close()
+        .build();
+    // remaining data is drained before EOF
+    let mut buf = Vec::new();
+    s.read_to_end(&mut buf).unwrap();
+    assert_eq!(buf, b"+OK bye\r\n");
+    assert!(s.is_peer_half_closed());
+    assert!(!s.is_peer_closed());
+    assert_eq!(s.read(&mut [0; 8]).unwrap(), 0);
+
+    s.write_all(b"QUIT\r\n").unwrap();
+    for _ in 0..2 {
+        let err = s.write(b"QUIT\r\n").unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::BrokenPipe);
+    }
+    assert!(s.is_peer_closed());
+    assert_eq!(s.read(&mut [0; 8]).unwrap(), 0);
+    s.assert_done();
+
+    let builder = CheckedMockStreamBuilder::from_transcript("HALFCLOSE:\nCLOSE:\n").unwrap();
+    assert_eq!(builder.to_transcript(), "HALFCLOSE:\nCLOSE:\n");
+}
+
+#[test]
+fn checked_mockstream_sync_point() {
+    use super::SyncPoint;
+
+    let reset = SyncPoint::new(2);
+    let mut primary = CheckedMockStreamBuilder::new()
+        .write(b"PING\r\n")
+        .read_error(Error::new(std::io::ErrorKind::ConnectionReset, "reset"))
+        .sync_point(&reset)
+        .build();
+    let mut failover = CheckedMockStreamBuilder::new()
+        .write(b"PING\r\n")
+        .sync_point(&reset)
+        .read(b"+PONG\r\n")
+        .build();
+
+    let client = std::thread::spawn(move || {
+        failover.write_all(b"PING\r\n").unwrap();
+        let mut buf = [0; 7];
+        failover.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"+PONG\r\n");
+        failover
+    });
+    // failover is blocked at sync point
+    while reset.arrived() == 0 {
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    std::thread::sleep(Duration::from_millis(10));
+    assert!(!client.is_finished());
+
+    primary.write_all(b"PING\r\n").unwrap();
+    let err = primary.read(&mut [0; 8]).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::ConnectionReset);
+    let failover = client.join().unwrap();
+    failover.assert_done();
+    primary.assert_done();
+    assert_eq!(reset.arrived(), 0);
+
+    // non-blocking mode
+    let point = SyncPoint::new(2);
+    let mut s = CheckedMockStreamBuilder::new()
+        .sync_point(&point)
+        .read(b"+OK\r\n")
+        .nonblocking()
+        .build();
+    let mut buf = [0; 5];
+    assert_eq!(
+        s.read(&mut buf).unwrap_err().kind(),
+        std::io::ErrorKind::WouldBlock
+    );
+    let mut other = CheckedMockStreamBuilder::new().sync_point(&point).build();
+    assert_eq!(other.read(&mut buf).unwrap(), 0);
+    other.assert_done();
+    s.read_exact(&mut buf).unwrap();
+    s.assert_done();
+}
+
+#[test]
+fn checked_mockstream_call_counts() {
+    use super::{at_least, at_most, between, exactly, SharedMockStream};
+
+    assert_eq!(exactly(2).to_string(), "exactly 2");
+    assert_eq!(at_most(2).to_string(), "at most 2");
+    assert_eq!(at_least(2).to_string(), "at least 2");
+    assert_eq!(between(1, 3).to_string(), "between 1 and 3");
+
+    let mut s = CheckedMockStreamBuilder::new()
+        .read(b"+OK\r\n")
+        .write(b"PING\r\n")
+        .expect_read_calls(1)
+        .expect_write_calls(at_most(1))
+        .expect_flush_calls(exactly(1))
+        .build();
+    let mut buf = [0; 5];
+    s.read_exact(&mut buf).unwrap();
+    s.write_all(b"PING\r\n").unwrap();
+    s.flush().unwrap();
+    assert_eq!(s.calls(), (1, 1, 1));
+    s.assert_done();
+
+    // byte-by-byte writes, implicit flush of shutdown is not counted
+    let mut s = CheckedMockStreamBuilder::new()
+        .write(b"PING\r\n")
+        .expect_write_calls(at_most(2))
+        .expect_flush_calls(at_least(1))
+        .flush_gated()
+        .build();
+    for b in b"PING\r\n" {
+        s.write_all(&[*b]).unwrap();
+    }
+    s.shutdown_write().unwrap();
+    assert_eq!(
+        s.diff(),
+        vec![
+            Divergence::Calls {
+                operation: "write",
+                calls: 6,
+                expected: at_most(2),
+            },
+            Divergence::Calls {
+                operation: "flush",
+                calls: 0,
+                expected: at_least(1),
+            },
+        ]
+    );
+    assert_eq!(
+        s.diff()[0].to_string(),
+        "write called 6 times, expected at most 2"
+    );
+
+    // reset clears counts
+    s.reset();
+    s.write_all(b"PING\r\n").unwrap();
+    s.flush().unwrap();
+    assert_eq!(s.calls(), (0, 1, 1));
+    s.assert_done();
+
+    // reload drops pending expectations of the replaced stream
+    let mut s = CheckedMockStreamBuilder::new()
+        .write(b"PING\r\n")
+        .expect_write_calls(1)
+        .build();
+    s.reload(CheckedMockStreamBuilder::new().write(b"QUIT\r\n"));
+    s.write_all(b"QUIT\r\n").unwrap();
+    s.assert_done();
+
+    // wait returned as WouldBlock is not counted (like pending poll of async read)
+    let mut s = CheckedMockStreamBuilder::new()
+        .wait(Duration::from_millis(20))
+        .read(b"+OK\r\n")
+        .expect_read_calls(1)
+        .nonblocking()
+        .build();
+    assert_eq!(
+        s.read(&mut buf).unwrap_err().kind(),
+        std::io::ErrorKind::WouldBlock
+    );
+    assert_eq!(s.calls(), (0, 0, 0));
+    std::thread::sleep(Duration::from_millis(20));
+    s.read_exact(&mut buf).unwrap();
+    assert_eq!(s.calls(), (1, 0, 0));
+    s.assert_done();
+
+    let shared = SharedMockStream::new(
+        CheckedMockStreamBuilder::new()
+            .read(b"+OK\r\n")
+            .expect_read_calls(between(1, 2))
+            .build(),
+    );
+    let mut reader = shared.clone();
+    reader.read_exact(&mut buf).unwrap();
+    shared.assert_done();
+}
+
+#[test]
+#[should_panic(expected = "write called 2 times, expected exactly 1")]
+fn checked_mockstream_call_counts_on_drop() {
+    let mut s = CheckedMockStreamBuilder::new()
+        .write(b"PING\r\n")
+        .expect_write_calls(1)
+        .flush_gated()
+        .build();
+    s.write_all(b"PI").unwrap();
+    s.write_all(b"NG\r\n").unwrap();
+    s.flush().unwrap();
+}
+
+#[test]
+fn mockstream_socket_metadata() {
+    use super::{SharedMockStream, SocketCall, DEFAULT_TTL};
+    use std::io::ErrorKind;
+    use std::net::SocketAddr;
+
+    let peer: SocketAddr = "10.0.0.1:6379".parse().unwrap();
+    let local: SocketAddr = "10.0.0.2:40000".parse().unwrap();
+
+    let s = SimpleMockStream::empty()
+        .with_peer_addr(peer)
+        .with_ttl(32);
+    assert_eq!(s.peer_addr().unwrap(), peer);
+    assert_eq!(s.local_addr().unwrap_err().kind(), ErrorKind::NotConnected);
+    assert!(!s.nodelay().unwrap());
+    assert_eq!(s.ttl().unwrap(), 32);
+    s.set_nodelay(true).unwrap();
+    assert_eq!(s.set_ttl(0).unwrap_err().kind(), ErrorKind::InvalidInput);
+    assert!(s.nodelay().unwrap());
+    assert_eq!(s.clone().socket_calls(), vec![SocketCall::SetNodelay(true)]);
+
+    let s = CheckedMockStreamBuilder::new()
+        .peer_addr(peer)
+        .local_addr(local)
+        .nodelay(true)
+        .build();
+    assert_eq!(s.peer_addr().unwrap(), peer);
+    assert_eq!(s.local_addr().unwrap(), local);
+    assert!(s.nodelay().unwrap());
+    assert_eq!(s.ttl().unwrap(), DEFAULT_TTL);
+    assert!(s.socket_calls().is_empty());
+
+    let shared = SharedMockStream::new(s);
+    let conn = shared.clone();
+    conn.set_nodelay(false).unwrap();
+    conn.set_ttl(128).unwrap();
+    assert_eq!(shared.peer_addr().unwrap(), peer);
+    assert!(!shared.nodelay().unwrap());
+    assert_eq!(shared.ttl().unwrap(), 128);
+    assert_eq!(
+        shared.socket_calls(),
+        vec![SocketCall::SetNodelay(false), SocketCall::SetTtl(128)]
+    );
+}
+
+#[test]
+fn checked_mockstream_mismatch_error() {
+    use crate::MismatchError;
+
+    let mut s = CheckedMockStreamBuilder::new()
+        .read(b"+OK\r\n")
+        .write(b"GET key\r\n")
+        .build();
+    let mut buf = [0; 5];
+    s.read_exact(&mut buf).unwrap();
+    let err = s.write(b"GET kez\r\n").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    let mismatch = err
+        .get_ref()
+        .and_then(|e| e.downcast_ref::<MismatchError>())
+        .unwrap();
+    assert_eq!(mismatch.action(), 1);
+    assert_eq!(mismatch.offset(), 6);
+    assert_eq!(mismatch.label(), r#"W 9 bytes "GET key\r\n""#);
+    assert_eq!(mismatch.expected(), b"GET key\r\n");
+    assert_eq!(mismatch.written(), b"GET kez\r\n");
+}
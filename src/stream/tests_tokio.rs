@@ -3,6 +3,7 @@ extern crate tokio;
 
 use super::SimpleMockStream;
 use super::CheckedMockStreamBuilder;
+use super::{Fault, FaultStream, RecordingStream};
 
 #[cfg(feature = "tokio")]
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
@@ -91,7 +92,7 @@ async fn checked_mockstream() {
 
     let result = stream.write_all(b"Success\n").await;
     assert!(result.is_err());
-    assert_eq!(stream.written(), &vec![]);
+    assert_eq!(stream.written(), &Vec::<u8>::new());
 
     buf.clear();
     let start = std::time::SystemTime::now();
@@ -113,7 +114,7 @@ async fn checked_mockstream() {
 
     let result = stream.write_all(b"Missmatch").await;
     assert!(result.is_err());
-    assert_eq!(stream.written(), &vec![]);
+    assert_eq!(stream.written(), &Vec::<u8>::new());
 
     stream.seek_action(3);
     let result = stream.write_all(b"Success\n").await;
@@ -194,7 +195,7 @@ async fn checked_mockstream_error() {
 
     let result = stream.write_all(b"Success\n").await;
     assert!(result.is_err());
-    assert_eq!(stream.written(), &vec![]);
+    assert_eq!(stream.written(), &Vec::<u8>::new());
 
     buf.clear();
     let readed = stream.read_to_end(&mut buf).await.unwrap();
@@ -206,7 +207,7 @@ async fn checked_mockstream_error() {
 
     let result = stream.write_all(b"Error\n").await;
     assert!(result.is_err());
-    assert_eq!(stream.written(), &vec![]);
+    assert_eq!(stream.written(), &Vec::<u8>::new());
 
     let result = stream.write_all(b"Success\n").await;
     assert!(result.is_ok(), "{}", result.err().unwrap());
@@ -223,3 +224,397 @@ async fn checked_mockstream_error() {
     assert_eq!(&buf, b"Third\n");
     assert_eq!(readed, 6);
 }
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn recording_stream() {
+    use std::time::Duration;
+
+    let server = CheckedMockStreamBuilder::new()
+        .write(b"PING\r\n".to_vec())
+        .wait(Duration::from_millis(20))
+        .read(b"+PONG\r\n".to_vec())
+        .build();
+    let mut stream = RecordingStream::new(server);
+
+    stream.write_all(b"PING\r\n").await.unwrap();
+    let mut buf = [0; 7];
+    stream.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"+PONG\r\n");
+
+    assert_eq!(stream.events().len(), 2);
+    assert!(stream.events()[1].elapsed >= Duration::from_millis(20));
+    let text = stream.to_builder().to_transcript();
+    assert!(text.starts_with("W: PING\\r\\n\nWAIT: "), "{}", text);
+    assert!(text.ends_with("\nR: +PONG\\r\\n\n"), "{}", text);
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn fault_stream() {
+    use std::time::Duration;
+
+    let upstream = SimpleMockStream::new(b"0123456789".to_vec());
+    let mut stream = FaultStream::new(upstream)
+        .on_read(2, Fault::Delay(Duration::from_millis(20)))
+        .on_read(4, Fault::Corrupt(0x20))
+        .on_read(6, Fault::Truncate)
+        .on_write(2, Fault::Reset);
+
+    let now = tokio::time::Instant::now();
+    let mut buf = Vec::new();
+    stream.read_to_end(&mut buf).await.unwrap();
+    assert!(now.elapsed() >= Duration::from_millis(20));
+    assert_eq!(&buf, b"0123\x145");
+
+    let err = stream.write_all(b"abcd").await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::ConnectionReset);
+    assert_eq!(stream.get_ref().written(), b"ab");
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test(start_paused = true)]
+async fn checked_mockstream_waits() {
+    use std::time::Duration;
+
+    let mut stream = CheckedMockStreamBuilder::new()
+        .wait(Duration::from_millis(10))
+        .wait(Duration::from_millis(20))
+        .read(b"1".to_vec())
+        .wait(Duration::from_millis(5))
+        .write(b"2".to_vec())
+        .wait(Duration::from_millis(5))
+        .read(b"3".to_vec())
+        .build();
+
+    let start = tokio::time::Instant::now();
+    let mut buf = [0; 1];
+    stream.read_exact(&mut buf).await.unwrap();
+    assert_eq!(start.elapsed(), Duration::from_millis(30));
+    stream.write_all(b"2").await.unwrap();
+    assert_eq!(start.elapsed(), Duration::from_millis(35));
+    stream.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"3");
+    assert_eq!(start.elapsed(), Duration::from_millis(40));
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn checked_mockstream_pending_reads() {
+    let stream = CheckedMockStreamBuilder::new()
+        .write(b"PING\r\n")
+        .read(b"+PONG\r\n")
+        .pending_reads(true)
+        .build();
+    let (mut reader, mut writer) = tokio::io::split(stream);
+
+    let mut read = tokio_test::task::spawn(async move {
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf).await.unwrap();
+        buf
+    });
+    // response is not ready until request is written
+    tokio_test::assert_pending!(read.poll());
+    assert!(!read.is_woken());
+
+    writer.write_all(b"PING\r\n").await.unwrap();
+    assert!(read.is_woken());
+    let buf = tokio_test::assert_ready!(read.poll());
+    assert_eq!(buf, b"+PONG\r\n");
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test(start_paused = true)]
+async fn checked_mockstream_directional_waits() {
+    use std::time::Duration;
+
+    let mut stream = CheckedMockStreamBuilder::new()
+        .wait_read(Duration::from_millis(50))
+        .write(b"REQ")
+        .wait_write(Duration::from_millis(20))
+        .read(b"RESP")
+        .write(b"ACK")
+        .build();
+
+    let start = tokio::time::Instant::now();
+    // read wait is not applied to write
+    stream.write_all(b"REQ").await.unwrap();
+    assert_eq!(start.elapsed(), Duration::from_millis(0));
+    let mut buf = [0; 4];
+    stream.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"RESP");
+    assert_eq!(start.elapsed(), Duration::from_millis(50));
+    // write wait is armed by read
+    stream.write_all(b"ACK").await.unwrap();
+    assert_eq!(start.elapsed(), Duration::from_millis(70));
+    stream.assert_done();
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn checked_mockstream_duplex() {
+    let stream = CheckedMockStreamBuilder::new()
+        .write(b"SUB\n")
+        .barrier()
+        .read(b"MSG 1\n")
+        .write(b"PING\n")
+        .read(b"MSG 2\n")
+        .duplex()
+        .build();
+    let (mut reader, mut writer) = tokio::io::split(stream);
+
+    let mut read = tokio_test::task::spawn(async move {
+        let mut buf = [0; 12];
+        reader.read_exact(&mut buf).await.unwrap();
+        buf
+    });
+    // read wait for barrier
+    tokio_test::assert_pending!(read.poll());
+
+    writer.write_all(b"SUB\n").await.unwrap();
+    assert!(read.is_woken());
+    writer.write_all(b"PING\n").await.unwrap();
+    let buf = tokio_test::assert_ready!(read.poll());
+    assert_eq!(&buf, b"MSG 1\nMSG 2\n");
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test(start_paused = true)]
+async fn checked_mockstream_cancel_wait() {
+    use std::time::Duration;
+
+    let mut stream = CheckedMockStreamBuilder::new()
+        .wait(Duration::from_millis(100))
+        .read(b"data")
+        .build();
+
+    let start = tokio::time::Instant::now();
+    let mut buf = [0; 4];
+    let result = tokio::time::timeout(Duration::from_millis(30), stream.read(&mut buf)).await;
+    assert!(result.is_err());
+    assert_eq!(start.elapsed(), Duration::from_millis(30));
+
+    // remaining delay is resumed
+    stream.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"data");
+    assert_eq!(start.elapsed(), Duration::from_millis(100));
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn checked_mockstream_shutdown() {
+    let mut stream = CheckedMockStreamBuilder::new()
+        .write(b"QUIT\r\n")
+        .read(b"+OK bye\r\n")
+        .flush_gated()
+        .build();
+    stream.write_all(b"QUIT\r\n").await.unwrap();
+    // shutdown flush buffered data
+    stream.shutdown().await.unwrap();
+    assert_eq!(stream.written(), b"QUIT\r\n");
+    let err = stream.write_all(b"more").await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::BrokenPipe);
+    let mut buf = Vec::new();
+    stream.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, b"+OK bye\r\n");
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn checked_mockstream_spurious_pending() {
+    let stream = CheckedMockStreamBuilder::new()
+        .write(b"PING\r\n")
+        .read(b"+PONG\r\n")
+        .chaos(super::Chaos::new(42).spurious_pending(1.0))
+        .build();
+
+    let mut session = tokio_test::task::spawn(async move {
+        let mut stream = stream;
+        stream.write_all(b"PING\r\n").await.unwrap();
+        let mut buf = [0; 7];
+        stream.read_exact(&mut buf).await.unwrap();
+        stream.assert_done();
+        buf
+    });
+    // every operation yields once with immediate wake
+    let mut polls = 1;
+    while session.poll().is_pending() {
+        assert!(session.is_woken());
+        polls += 1;
+    }
+    assert_eq!(polls, 3);
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test(start_paused = true)]
+async fn checked_mockstream_read_every() {
+    use std::time::Duration;
+
+    let mut stream = CheckedMockStreamBuilder::new()
+        .read_every(b"PING\r\n", Duration::from_secs(30))
+        .write(b"QUIT\r\n")
+        .read(b"+OK bye\r\n")
+        .build();
+
+    let start = tokio::time::Instant::now();
+    let mut buf = [0; 6];
+    for i in 1..=3 {
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"PING\r\n");
+        assert_eq!(start.elapsed(), Duration::from_secs(30 * i));
+    }
+    // write ends heartbeat
+    stream.write_all(b"QUIT\r\n").await.unwrap();
+    let mut buf = Vec::new();
+    stream.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, b"+OK bye\r\n");
+    assert_eq!(start.elapsed(), Duration::from_secs(90));
+    stream.assert_done();
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test(start_paused = true)]
+async fn checked_mockstream_at() {
+    use super::ActionSpec;
+    use std::time::Duration;
+
+    let mut stream = CheckedMockStreamBuilder::new()
+        .at(Duration::from_millis(100), ActionSpec::Read(b"1".into()))
+        .write(b"2")
+        .at(Duration::from_millis(300), ActionSpec::Read(b"3".into()))
+        .build();
+
+    let start = tokio::time::Instant::now();
+    let mut buf = [0; 1];
+    stream.read_exact(&mut buf).await.unwrap();
+    assert_eq!(start.elapsed(), Duration::from_millis(100));
+    stream.write_all(b"2").await.unwrap();
+    stream.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"3");
+    assert_eq!(start.elapsed(), Duration::from_millis(300));
+    stream.assert_done();
+
+    // elapsed offset does not wait
+    let mut stream = CheckedMockStreamBuilder::new()
+        .at(Duration::from_millis(100), ActionSpec::Read(b"1".into()))
+        .build();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let start = tokio::time::Instant::now();
+    stream.read_exact(&mut buf).await.unwrap();
+    assert_eq!(start.elapsed(), Duration::ZERO);
+
+    // offset is not moved by the preceding wait
+    let mut stream = CheckedMockStreamBuilder::new()
+        .wait(Duration::from_secs(1))
+        .at(Duration::from_secs(1), ActionSpec::Read(b"1".into()))
+        .build();
+    let start = tokio::time::Instant::now();
+    stream.read_exact(&mut buf).await.unwrap();
+    assert_eq!(start.elapsed(), Duration::from_secs(1));
+    stream.assert_done();
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn checked_mockstream_peer_half_close() {
+    let mut stream = CheckedMockStreamBuilder::new()
+        .read(b"+OK bye\r\n")
+        .peer_half_close()
+        .write(b"QUIT\r\n")
+        .pending_reads(true)
+        .build();
+
+    let mut buf = Vec::new();
+    stream.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, b"+OK bye\r\n");
+    // EOF instead of pending read before write
+    assert_eq!(stream.read(&mut [0; 8]).await.unwrap(), 0);
+    stream.write_all(b"QUIT\r\n").await.unwrap();
+    stream.assert_done();
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn checked_mockstream_sync_point() {
+    use super::SyncPoint;
+
+    let reset = SyncPoint::new(2);
+    let mut primary = CheckedMockStreamBuilder::new()
+        .write(b"PING\r\n")
+        .read_error(std::io::ErrorKind::ConnectionReset.into())
+        .sync_point(&reset)
+        .build();
+    let failover = CheckedMockStreamBuilder::new()
+        .write(b"PING\r\n")
+        .sync_point(&reset)
+        .read(b"+PONG\r\n")
+        .build();
+
+    let mut session = tokio_test::task::spawn(async move {
+        let mut failover = failover;
+        failover.write_all(b"PING\r\n").await.unwrap();
+        let mut buf = [0; 7];
+        failover.read_exact(&mut buf).await.unwrap();
+        failover.assert_done();
+        buf
+    });
+    tokio_test::assert_pending!(session.poll());
+
+    primary.write_all(b"PING\r\n").await.unwrap();
+    let err = primary.read(&mut [0; 8]).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::ConnectionReset);
+    primary.assert_done();
+    assert!(session.is_woken());
+    assert_eq!(&tokio_test::assert_ready!(session.poll()), b"+PONG\r\n");
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn checked_mockstream_call_counts() {
+    use super::{at_most, exactly, Divergence};
+
+    let mut s = CheckedMockStreamBuilder::new()
+        .read(b"+OK\r\n")
+        .write(b"PING\r\n")
+        .expect_read_calls(exactly(1))
+        .expect_write_calls(at_most(1))
+        .expect_flush_calls(exactly(1))
+        .build();
+    let mut buf = [0; 5];
+    s.read_exact(&mut buf).await.unwrap();
+    s.write_all(b"PING\r\n").await.unwrap();
+    s.shutdown().await.unwrap();
+    assert_eq!(s.calls(), (1, 1, 0));
+    assert_eq!(
+        s.diff(),
+        vec![Divergence::Calls {
+            operation: "flush",
+            calls: 0,
+            expected: exactly(1),
+        }]
+    );
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn checked_mockstream_mismatch_error() {
+    use crate::MismatchError;
+
+    let mut s = CheckedMockStreamBuilder::new()
+        .read(b"+OK\r\n")
+        .write(b"GET key\r\n")
+        .build();
+    let mut buf = [0; 5];
+    s.read_exact(&mut buf).await.unwrap();
+    let err = s.write(b"GET kez\r\n").await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    let mismatch = err
+        .get_ref()
+        .and_then(|e| e.downcast_ref::<MismatchError>())
+        .unwrap();
+    assert_eq!(mismatch.action(), 1);
+    assert_eq!(mismatch.offset(), 6);
+    assert_eq!(mismatch.label(), r#"W 9 bytes "GET key\r\n""#);
+    assert_eq!(mismatch.expected(), b"GET key\r\n");
+    assert_eq!(mismatch.written(), b"GET kez\r\n");
+}
@@ -0,0 +1,170 @@
+//! Timing constraints of caller operations.
+//!
+//! With [`CheckedMockStreamBuilder::within`] and [`CheckedMockStreamBuilder::after`] the time, when
+//! the caller performed the operation of action, is checked against the completion of the previous
+//! action (or the end of the scripted wait before action, or the stream creation for the first action).
+//! Violations are recorded as [`super::Divergence::Timing`] and reported by [`CheckedMockStream::assert_done`].
+
+use std::time::Duration;
+
+use super::{Action, CheckedMockStream, CheckedMockStreamBuilder, Divergence, Instant};
+
+// timing constraint of action
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(super) struct Timing {
+    pub(super) within: Option<Duration>,
+    pub(super) after: Option<Duration>,
+}
+
+impl Timing {
+    fn violated(&self, elapsed: Duration) -> bool {
+        self.within.is_some_and(|within| elapsed > within)
+            || self.after.is_some_and(|after| elapsed < after)
+    }
+}
+
+// timing state of stream
+#[derive(Debug)]
+pub(super) struct TimingState {
+    // constraints by action index (empty if scenario has no constraints)
+    timings: Vec<Timing>,
+    // completion of the last advanced action
+    ready: Instant,
+    // the last checked action
+    checked: Option<usize>,
+}
+
+impl TimingState {
+    pub(super) fn new(timings: Vec<Timing>) -> Self {
+        TimingState {
+            timings,
+            ready: Instant::now(),
+            checked: None,
+        }
+    }
+}
+
+impl CheckedMockStreamBuilder {
+    /// Require the caller to perform the operation of the last queued action within a duration
+    /// after the previous action is completed.
+    ///
+    /// ```
+    /// # use netmock::stream::CheckedMockStreamBuilder;
+    /// # use std::time::Duration;
+    /// // heartbeat must be sent within 1s after the previous read
+    /// let builder = CheckedMockStreamBuilder::new()
+    ///     .read(b"+OK\r\n")
+    ///     .write(b"PING\r\n")
+    ///     .within(Duration::from_secs(1));
+    /// ```
+    ///
+    /// Panics if no action is queued.
+    #[track_caller]
+    pub fn within(mut self, duration: Duration) -> Self {
+        self.last_timing().within = Some(duration);
+        self
+    }
+
+    /// Require the caller to perform the operation of the last queued action not earlier than
+    /// a duration after the previous action is completed.
+    ///
+    /// Panics if no action is queued.
+    #[track_caller]
+    pub fn after(mut self, duration: Duration) -> Self {
+        self.last_timing().after = Some(duration);
+        self
+    }
+
+    #[track_caller]
+    fn last_timing(&mut self) -> &mut Timing {
+        let action = match self.actions.len().checked_sub(1) {
+            Some(action) => action,
+            None => panic!("timing constraint without action"),
+        };
+        let idx = match self.timings.iter().position(|(i, _)| *i == action) {
+            Some(idx) => idx,
+            None => {
+                self.timings.push((action, Timing::default()));
+                self.timings.len() - 1
+            }
+        };
+        &mut self.timings[idx].1
+    }
+
+    // move constraints with actions (dropped, if mapped to None)
+    pub(super) fn remap_timings<F: Fn(usize) -> Option<usize>>(&mut self, map: F) {
+        self.timings = self
+            .timings
+            .iter()
+            .filter_map(|&(i, timing)| map(i).map(|i| (i, timing)))
+            .collect();
+    }
+}
+
+// constraints by index of built actions (`index` maps builder actions to built actions)
+pub(super) fn build_timings(
+    timings: &[(usize, Timing)],
+    index: &[usize],
+    len: usize,
+) -> Vec<Timing> {
+    if timings.is_empty() {
+        return Vec::new();
+    }
+    let mut built = vec![Timing::default(); len];
+    for &(i, timing) in timings {
+        if let Some(&i) = index.get(i) {
+            built[i] = timing;
+        }
+    }
+    built
+}
+
+impl CheckedMockStream {
+    // restart timing from now (after scenario reset or seek)
+    pub(super) fn reset_timing(&mut self) {
+        self.timing.ready = Instant::now();
+        self.timing.checked = None;
+    }
+
+    // scenario is advanced, the next action is ready after started waits
+    pub(super) fn advance_timing(&mut self) {
+        if self.timing.timings.is_empty() {
+            return;
+        }
+        let now = Instant::now();
+        self.timing.ready = [self.read_deadline, self.write_deadline]
+            .iter()
+            .flatten()
+            .fold(now, |ready, &deadline| ready.max(deadline));
+    }
+
+    // check timing of the current action, reached by operation of the direction
+    pub(super) fn check_timing(&mut self, read: bool) {
+        let action = self.action;
+        let timing = match self.timing.timings.get(action) {
+            Some(timing) if self.timing.checked != Some(action) => *timing,
+            _ => return,
+        };
+        let matched = match self.actions[action] {
+            Action::Read(_)
+            | Action::ReadError(_)
+            | Action::ReadFile(_)
+            | Action::ReadEvery(..) => read,
+            Action::Write(_) | Action::WriteError(_) => !read,
+            _ => false,
+        };
+        if !matched {
+            return;
+        }
+        self.timing.checked = Some(action);
+        let elapsed = Instant::now().saturating_duration_since(self.timing.ready);
+        if timing.violated(elapsed) {
+            self.divergences.push(Divergence::Timing {
+                action,
+                elapsed,
+                within: timing.within,
+                after: timing.after,
+            });
+        }
+    }
+}
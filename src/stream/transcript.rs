@@ -0,0 +1,197 @@
+//! Plain-text scenario format (transcript) for [`CheckedMockStreamBuilder`].
+//!
+//! One action per line:
+//!
+//! ```text
+//! # comment
+//! R: +OK ready\r\n
+//! W: USER foo\r\n
+//! WAIT: 100ms
+//! RERR: timeout
+//! WERR: BrokenPipe connection closed
+//! ```
+//!
+//! - `R:` - data to read, `W:` - data required to be written. Payload supports escapes
+//!   `\r`, `\n`, `\t`, `\0`, `\\` and `\xHH`.
+//! - `RFILE:` - path of file, which content is streamed on read.
+//! - `REVERY:` - interval and data to read once per interval until the next write
+//!   (`REVERY: 30s PING\r\n`).
+//! - `WAIT:` - wait duration with unit (`ns`, `us`, `ms`, `s`, `m`).
+//! - `RWAIT:`/`WWAIT:` - wait duration only for read/write.
+//! - `AT:` - wait until offset from stream creation (the next action is available after it).
+//! - `BARRIER:` - barrier of duplex mode.
+//! - `HALFCLOSE:`/`CLOSE:` - peer shutdown of its write direction/connection.
+//! - `RERR:`/`WERR:` - read/write error kind (`TimedOut`, `timed_out` or short alias like
+//!   `timeout`, `reset`, `refused`, `eof`) with optional message.
+//!
+//! Sync points (shared with other streams) can't be represented and are rendered as comments.
+//! Duplex mode isn't represented either: `BARRIER:` lines are kept, but have effect only if
+//! the parsed builder is switched to [`duplex`](CheckedMockStreamBuilder::duplex) mode.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, Error, ErrorKind};
+use std::path::Path;
+
+use super::util::{error_kind_from_str, escape, format_duration, parse_duration};
+use super::{Action, CheckedMockStreamBuilder};
+
+impl CheckedMockStreamBuilder {
+    /// Parse scenario from plain-text transcript.
+    pub fn from_transcript(text: &str) -> io::Result<Self> {
+        let mut builder = CheckedMockStreamBuilder::new();
+        for (n, line) in text.lines().enumerate() {
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() || line.trim_start().starts_with('#') {
+                continue;
+            }
+            builder = parse_line(builder, line).map_err(|e| {
+                Error::new(ErrorKind::InvalidData, format!("line {}: {}", n + 1, e))
+            })?;
+        }
+        Ok(builder)
+    }
+
+    /// Parse scenario from plain-text transcript file.
+    pub fn from_transcript_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        CheckedMockStreamBuilder::from_transcript(&text)
+    }
+
+    /// Render scenario as plain-text transcript.
+    pub fn to_transcript(&self) -> String {
+        let mut text = String::new();
+        for action in &self.actions {
+            match action {
+                Action::Read(data) => {
+                    let _ = writeln!(text, "R: {}", escape(data));
+                }
+                Action::Write(data) => {
+                    let _ = writeln!(text, "W: {}", escape(data));
+                }
+                Action::ReadFile(path) => {
+                    let _ = writeln!(text, "RFILE: {}", path.display());
+                }
+                Action::ReadEvery(data, d) => {
+                    let _ = writeln!(text, "REVERY: {} {}", format_duration(*d), escape(data));
+                }
+                Action::Wait(d) => {
+                    let _ = writeln!(text, "WAIT: {}", format_duration(*d));
+                }
+                Action::WaitRead(d) => {
+                    let _ = writeln!(text, "RWAIT: {}", format_duration(*d));
+                }
+                Action::WaitWrite(d) => {
+                    let _ = writeln!(text, "WWAIT: {}", format_duration(*d));
+                }
+                Action::WaitUntil(d) => {
+                    let _ = writeln!(text, "AT: {}", format_duration(*d));
+                }
+                Action::Barrier => text.push_str("BARRIER:\n"),
+                Action::PeerHalfClose => text.push_str("HALFCLOSE:\n"),
+                Action::PeerFullClose => text.push_str("CLOSE:\n"),
+                Action::SyncPoint(point) => {
+                    let _ = writeln!(text, "# sync point of {} parties", point.parties());
+                }
+                Action::ReadError(err) => {
+                    let _ = writeln!(
+                        text,
+                        "RERR: {:?} {}",
+                        err.kind(),
+                        err.to_string().replace('\n', " ")
+                    );
+                }
+                Action::WriteError(err) => {
+                    let _ = writeln!(
+                        text,
+                        "WERR: {:?} {}",
+                        err.kind(),
+                        err.to_string().replace('\n', " ")
+                    );
+                }
+            }
+        }
+        text
+    }
+}
+
+fn parse_line(
+    builder: CheckedMockStreamBuilder,
+    line: &str,
+) -> Result<CheckedMockStreamBuilder, String> {
+    let (tag, value) = line
+        .split_once(':')
+        .ok_or_else(|| format!("missing action tag: {:?}", line))?;
+    let value = value.strip_prefix(' ').unwrap_or(value);
+    match tag.trim().to_ascii_uppercase().as_str() {
+        "R" => Ok(builder.read(unescape(value)?)),
+        "W" => Ok(builder.write(unescape(value)?)),
+        "RFILE" => Ok(builder.read_file(value.trim())),
+        "REVERY" => {
+            let (interval, data) = value.trim_start().split_once(' ').unwrap_or((value, ""));
+            let interval = parse_duration(interval)
+                .ok_or_else(|| format!("invalid duration: {:?}", interval))?;
+            Ok(builder.read_every(unescape(data)?, interval))
+        }
+        "BARRIER" => Ok(builder.barrier()),
+        "HALFCLOSE" => Ok(builder.peer_half_close()),
+        "CLOSE" => Ok(builder.peer_full_close()),
+        "WAIT" => parse_duration(value)
+            .map(|d| builder.wait(d))
+            .ok_or_else(|| format!("invalid duration: {:?}", value)),
+        "RWAIT" => parse_duration(value)
+            .map(|d| builder.wait_read(d))
+            .ok_or_else(|| format!("invalid duration: {:?}", value)),
+        "WWAIT" => parse_duration(value)
+            .map(|d| builder.wait_write(d))
+            .ok_or_else(|| format!("invalid duration: {:?}", value)),
+        "AT" => parse_duration(value)
+            .map(|d| builder.wait_until(d))
+            .ok_or_else(|| format!("invalid duration: {:?}", value)),
+        "RERR" => Ok(builder.read_error(parse_error(value)?)),
+        "WERR" => Ok(builder.write_error(parse_error(value)?)),
+        tag => Err(format!("unknown action tag: {:?}", tag)),
+    }
+}
+
+fn parse_error(value: &str) -> Result<Error, String> {
+    let value = value.trim();
+    let (kind, message) = match value.split_once(char::is_whitespace) {
+        Some((kind, message)) => (kind, Some(message.trim())),
+        None => (value, None),
+    };
+    let kind =
+        error_kind_from_str(kind).ok_or_else(|| format!("unknown error kind: {:?}", kind))?;
+    Ok(match message {
+        Some(message) if !message.is_empty() => Error::new(kind, message.to_string()),
+        _ => Error::from(kind),
+    })
+}
+
+fn unescape(s: &str) -> Result<Vec<u8>, String> {
+    let mut buf = Vec::with_capacity(s.len());
+    let mut bytes = s.bytes();
+    while let Some(c) = bytes.next() {
+        if c != b'\\' {
+            buf.push(c);
+            continue;
+        }
+        match bytes.next() {
+            Some(b'r') => buf.push(b'\r'),
+            Some(b'n') => buf.push(b'\n'),
+            Some(b't') => buf.push(b'\t'),
+            Some(b'0') => buf.push(0),
+            Some(b'\\') => buf.push(b'\\'),
+            Some(b'x') => {
+                let hex = [bytes.next().unwrap_or(b'?'), bytes.next().unwrap_or(b'?')];
+                let v = std::str::from_utf8(&hex)
+                    .ok()
+                    .and_then(|h| u8::from_str_radix(h, 16).ok())
+                    .ok_or_else(|| format!("invalid hex escape in {:?}", s))?;
+                buf.push(v);
+            }
+            _ => return Err(format!("invalid escape in {:?}", s)),
+        }
+    }
+    Ok(buf)
+}
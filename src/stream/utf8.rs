@@ -0,0 +1,97 @@
+//! UTF-8 validation and lossy rendering of written data (for text protocols).
+//!
+//! With [`CheckedMockStreamBuilder::utf8_writes`] every write is validated before verification
+//! against the scenario and invalid UTF-8 is rejected with `InvalidData` error, which contains
+//! the offset of the offending byte in written data. Multibyte characters may be split between writes.
+
+use std::borrow::Cow;
+use std::io::{Error, ErrorKind};
+use std::str::{self, Utf8Error};
+
+use super::{CheckedMockStream, CheckedMockStreamBuilder, SimpleMockStream};
+
+// validation state of written data
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(super) struct Utf8State {
+    // count of accepted bytes
+    len: usize,
+    // incomplete character at the end of accepted data
+    pending: Vec<u8>,
+}
+
+impl Utf8State {
+    // check data before write, returns error with offset of the first invalid byte
+    fn check(&self, buf: &[u8]) -> Option<Error> {
+        let mut data = self.pending.clone();
+        data.extend_from_slice(buf);
+        match str::from_utf8(&data) {
+            Err(e) if e.error_len().is_some() => {
+                let offset = e.valid_up_to();
+                Some(Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "invalid UTF-8 in written data at offset {} (byte 0x{:02x})",
+                        self.len - self.pending.len() + offset,
+                        data[offset]
+                    ),
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    // advance by accepted data
+    fn accept(&mut self, buf: &[u8]) {
+        self.len += buf.len();
+        self.pending.extend_from_slice(buf);
+        let valid = match str::from_utf8(&self.pending) {
+            Ok(_) => self.pending.len(),
+            Err(e) => e.valid_up_to(),
+        };
+        self.pending.drain(..valid);
+    }
+}
+
+impl CheckedMockStreamBuilder {
+    /// Reject writes of invalid UTF-8 with `InvalidData` error (for text protocols).
+    pub fn utf8_writes(mut self) -> Self {
+        self.utf8_writes = true;
+        self
+    }
+}
+
+impl SimpleMockStream {
+    /// Gets a written data as UTF-8 string.
+    pub fn written_utf8(&self) -> Result<&str, Utf8Error> {
+        str::from_utf8(self.written())
+    }
+
+    /// Gets a written data as string, invalid UTF-8 sequences are replaced with `U+FFFD`.
+    pub fn written_lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(self.written())
+    }
+}
+
+impl CheckedMockStream {
+    /// Gets a written data as UTF-8 string.
+    pub fn written_utf8(&self) -> Result<&str, Utf8Error> {
+        str::from_utf8(self.written())
+    }
+
+    /// Gets a written data as string, invalid UTF-8 sequences are replaced with `U+FFFD`.
+    pub fn written_lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(self.written())
+    }
+
+    // validate data before write (in UTF-8 writes mode)
+    pub(super) fn check_utf8(&self, buf: &[u8]) -> Option<Error> {
+        self.utf8.as_ref().and_then(|state| state.check(buf))
+    }
+
+    // advance UTF-8 validation by accepted data
+    pub(super) fn accept_utf8(&mut self, buf: &[u8]) {
+        if let Some(ref mut state) = self.utf8 {
+            state.accept(buf);
+        }
+    }
+}
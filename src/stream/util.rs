@@ -0,0 +1,105 @@
+//! Shared helpers for scenario formats.
+
+use std::fmt::Write as _;
+use std::io::ErrorKind;
+use std::time::Duration;
+
+const ERROR_KINDS: &[ErrorKind] = &[
+    ErrorKind::NotFound,
+    ErrorKind::PermissionDenied,
+    ErrorKind::ConnectionRefused,
+    ErrorKind::ConnectionReset,
+    ErrorKind::ConnectionAborted,
+    ErrorKind::NotConnected,
+    ErrorKind::AddrInUse,
+    ErrorKind::AddrNotAvailable,
+    ErrorKind::BrokenPipe,
+    ErrorKind::AlreadyExists,
+    ErrorKind::WouldBlock,
+    ErrorKind::InvalidInput,
+    ErrorKind::InvalidData,
+    ErrorKind::TimedOut,
+    ErrorKind::WriteZero,
+    ErrorKind::Interrupted,
+    ErrorKind::Unsupported,
+    ErrorKind::UnexpectedEof,
+    ErrorKind::OutOfMemory,
+    ErrorKind::Other,
+];
+
+// short aliases for common error kinds
+const ERROR_KIND_ALIASES: &[(&str, ErrorKind)] = &[
+    ("timeout", ErrorKind::TimedOut),
+    ("reset", ErrorKind::ConnectionReset),
+    ("refused", ErrorKind::ConnectionRefused),
+    ("aborted", ErrorKind::ConnectionAborted),
+    ("eof", ErrorKind::UnexpectedEof),
+];
+
+// parse error kind name (`TimedOut`, `timed_out` or alias like `timeout`)
+pub(crate) fn error_kind_from_str(s: &str) -> Option<ErrorKind> {
+    let normalized: String = s
+        .chars()
+        .filter(|&c| c != '_')
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    if let Some((_, kind)) = ERROR_KIND_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == normalized)
+    {
+        return Some(*kind);
+    }
+    ERROR_KINDS
+        .iter()
+        .find(|kind| format!("{:?}", kind).to_ascii_lowercase() == normalized)
+        .copied()
+}
+
+pub(crate) fn parse_duration(s: &str) -> Option<Duration> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit() && c != '.')?;
+    let (value, unit) = s.split_at(split);
+    let value: f64 = value.parse().ok()?;
+    let nanos = match unit.trim() {
+        "ns" => value,
+        "us" => value * 1e3,
+        "ms" => value * 1e6,
+        "s" => value * 1e9,
+        "m" => value * 60e9,
+        _ => return None,
+    };
+    Some(Duration::from_nanos(nanos.round() as u64))
+}
+
+pub(crate) fn format_duration(d: Duration) -> String {
+    if d.subsec_nanos() == 0 {
+        format!("{}s", d.as_secs())
+    } else if d.subsec_nanos() % 1_000_000 == 0 {
+        format!("{}ms", d.as_millis())
+    } else if d.subsec_nanos() % 1_000 == 0 {
+        format!("{}us", d.as_micros())
+    } else {
+        format!("{}ns", d.as_nanos())
+    }
+}
+
+// escape payload as printable text (`\r`, `\n`, `\t`, `\0`, `\\` and `\xHH`)
+pub(crate) fn escape(data: &[u8]) -> String {
+    let mut s = String::with_capacity(data.len());
+    for (i, &c) in data.iter().enumerate() {
+        match c {
+            b'\r' => s.push_str("\\r"),
+            b'\n' => s.push_str("\\n"),
+            b'\t' => s.push_str("\\t"),
+            0 => s.push_str("\\0"),
+            b'\\' => s.push_str("\\\\"),
+            // keep leading/trailing spaces visible
+            b' ' if i == 0 || i == data.len() - 1 => s.push_str("\\x20"),
+            0x20..=0x7e => s.push(c as char),
+            _ => {
+                let _ = write!(s, "\\x{:02x}", c);
+            }
+        }
+    }
+    s
+}
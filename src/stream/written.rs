@@ -0,0 +1,230 @@
+//! Storage of written data.
+
+use std::collections::VecDeque;
+use std::sync::OnceLock;
+
+// chunk capacity of segmented storage
+const CHUNK_SIZE: usize = 64 * 1024;
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// Storage mode of written data for [`super::CheckedMockStream`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WrittenMode {
+    /// Single contiguous buffer (default).
+    #[default]
+    Contiguous,
+    /// List of chunks, without reallocation and copying on grow.
+    /// Contiguous data is built on demand (and cached until the next write).
+    Segmented,
+    /// Retain only the last N bytes of written data.
+    LastBytes(usize),
+    /// Retain only the last N writes (every write is a segment).
+    /// Contiguous data is built on demand (and cached until the next write).
+    LastWrites(usize),
+    /// Verify writes against expectations and discard data (only length is counted).
+    Discard,
+    /// Verify writes against expectations and discard data, but keep a hash of written data
+    /// (see [`hash_bytes`]).
+    Hash,
+}
+
+/// Gets a hash (64-bit FNV-1a) of data, as calculated for written data in [`WrittenMode::Hash`] mode.
+pub fn hash_bytes(data: &[u8]) -> u64 {
+    fnv1a(FNV_OFFSET, data)
+}
+
+fn fnv1a(mut hash: u64, data: &[u8]) -> u64 {
+    for &b in data {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+#[derive(Debug)]
+pub(super) struct Written {
+    mode: WrittenMode,
+    data: Vec<u8>,
+    chunks: VecDeque<Vec<u8>>,
+    // total length of written data (including dropped in ring-buffer modes)
+    len: usize,
+    // hash of written data in hash mode
+    hash: u64,
+    // contiguous data cache for segmented mode
+    flat: OnceLock<Vec<u8>>,
+}
+
+impl Written {
+    pub(super) fn new(mode: WrittenMode, capacity: usize) -> Self {
+        let (data, chunks) = match mode {
+            WrittenMode::Contiguous => (Vec::with_capacity(capacity), VecDeque::new()),
+            WrittenMode::Segmented => (Vec::new(), VecDeque::with_capacity(capacity / CHUNK_SIZE)),
+            WrittenMode::LastBytes(n) => (Vec::with_capacity(capacity.min(n * 2)), VecDeque::new()),
+            WrittenMode::LastWrites(n) => (Vec::new(), VecDeque::with_capacity(n + 1)),
+            WrittenMode::Discard | WrittenMode::Hash => (Vec::new(), VecDeque::new()),
+        };
+        Written {
+            mode,
+            data,
+            chunks,
+            len: 0,
+            hash: FNV_OFFSET,
+            flat: OnceLock::new(),
+        }
+    }
+
+    pub(super) fn push(&mut self, buf: &[u8]) {
+        self.len += buf.len();
+        match self.mode {
+            WrittenMode::Contiguous => self.data.extend_from_slice(buf),
+            WrittenMode::Segmented => {
+                self.flat.take();
+                match self.chunks.back_mut() {
+                    Some(chunk) if chunk.capacity() - chunk.len() >= buf.len() => {
+                        chunk.extend_from_slice(buf)
+                    }
+                    _ => {
+                        let mut chunk = Vec::with_capacity(buf.len().max(CHUNK_SIZE));
+                        chunk.extend_from_slice(buf);
+                        self.chunks.push_back(chunk);
+                    }
+                }
+            }
+            WrittenMode::LastBytes(n) => {
+                if buf.len() >= n {
+                    self.data.clear();
+                    self.data.extend_from_slice(&buf[buf.len() - n..]);
+                } else {
+                    // drop head only when retained data is twice the limit (amortized copy)
+                    if self.data.len() + buf.len() > n * 2 {
+                        let keep = n - buf.len();
+                        self.data.drain(..self.data.len() - keep);
+                    }
+                    self.data.extend_from_slice(buf);
+                }
+            }
+            WrittenMode::LastWrites(n) => {
+                self.flat.take();
+                if n > 0 {
+                    if self.chunks.len() == n {
+                        self.chunks.pop_front();
+                    }
+                    self.chunks.push_back(buf.to_vec());
+                }
+            }
+            WrittenMode::Discard => {}
+            WrittenMode::Hash => self.hash = fnv1a(self.hash, buf),
+        }
+    }
+
+    pub(super) fn clear(&mut self) {
+        self.len = 0;
+        self.hash = FNV_OFFSET;
+        self.data.clear();
+        self.chunks.clear();
+        self.flat.take();
+    }
+
+    // restore length and hash of written data (dropped data can't be restored)
+    pub(super) fn truncate(&mut self, len: usize, hash: u64) {
+        if len >= self.len {
+            return;
+        }
+        let mut drop = self.len - len;
+        self.len = len;
+        self.hash = hash;
+        self.flat.take();
+        self.data.truncate(self.data.len() - drop.min(self.data.len()));
+        while drop > 0 {
+            let chunk = match self.chunks.back_mut() {
+                Some(chunk) => chunk,
+                None => break,
+            };
+            if chunk.len() > drop {
+                chunk.truncate(chunk.len() - drop);
+                break;
+            }
+            drop -= chunk.len();
+            self.chunks.pop_back();
+        }
+    }
+
+    pub(super) fn capacity(&self) -> usize {
+        self.data.capacity() + self.chunks.iter().map(Vec::capacity).sum::<usize>()
+    }
+
+    pub(super) fn shrink_to_fit(&mut self) {
+        if let WrittenMode::LastBytes(n) = self.mode {
+            // drop not retained head
+            let len = self.data.len();
+            self.data.drain(..len - len.min(n));
+        }
+        self.data.shrink_to_fit();
+        self.chunks.iter_mut().for_each(Vec::shrink_to_fit);
+        self.chunks.shrink_to_fit();
+    }
+
+    pub(super) fn clear_capacity(&mut self) {
+        self.clear();
+        self.data = Vec::new();
+        self.chunks = VecDeque::new();
+    }
+
+    pub(super) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(super) fn raw_hash(&self) -> u64 {
+        self.hash
+    }
+
+    pub(super) fn hash(&self) -> Option<u64> {
+        match self.mode {
+            WrittenMode::Hash => Some(self.hash),
+            _ => None,
+        }
+    }
+
+    // length of data retained in memory
+    pub(super) fn retained(&self) -> usize {
+        self.data.len() + self.chunks.iter().map(Vec::len).sum::<usize>()
+    }
+
+    pub(super) fn as_slice(&self) -> &[u8] {
+        match self.mode {
+            WrittenMode::Contiguous => &self.data,
+            WrittenMode::LastBytes(n) => &self.data[self.data.len() - self.data.len().min(n)..],
+            WrittenMode::Discard | WrittenMode::Hash => &[],
+            WrittenMode::Segmented | WrittenMode::LastWrites(_) => match self.chunks.len() {
+                0 => &[],
+                1 => &self.chunks[0],
+                _ => self.flat.get_or_init(|| {
+                    let mut flat = Vec::with_capacity(self.chunks.iter().map(Vec::len).sum());
+                    for chunk in &self.chunks {
+                        flat.extend_from_slice(chunk);
+                    }
+                    flat
+                }),
+            },
+        }
+    }
+
+    // retained data after offset (counted from the stream start)
+    pub(super) fn since(&self, offset: usize) -> &[u8] {
+        let data = self.as_slice();
+        let start = self.len - data.len();
+        &data[offset.clamp(start, self.len) - start..]
+    }
+
+    pub(super) fn segments(&self) -> impl Iterator<Item = &[u8]> {
+        let data = match self.mode {
+            WrittenMode::Contiguous | WrittenMode::LastBytes(_) => Some(self.as_slice()),
+            _ => None,
+        }
+        .filter(|data| !data.is_empty());
+        data.into_iter()
+            .chain(self.chunks.iter().map(|chunk| &chunk[..]))
+    }
+}